@@ -0,0 +1,263 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use emmylua_code_analysis::{DbIndex, FileId};
+use lsp_types::{Diagnostic, NumberOrString};
+use serde::{Deserialize, Serialize};
+
+use crate::{output::get_relative_path, stable_hash::StableHasher};
+
+const BASELINE_VERSION: u32 = 1;
+
+/// The diagnostics accepted by a baseline file. Each entry is keyed by the file, the
+/// diagnostic code and a fingerprint of the offending text, so moving code around does
+/// not invalidate it.
+#[derive(Debug, Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    entries: Vec<BaselineEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub file: String,
+    pub code: String,
+    pub fingerprint: String,
+    #[serde(default = "default_count")]
+    pub count: usize,
+}
+
+fn default_count() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct BaselineKey {
+    file: String,
+    code: String,
+    fingerprint: String,
+}
+
+#[derive(Debug)]
+pub struct Baseline {
+    workspace: PathBuf,
+    /// How many more diagnostics of every key are suppressed
+    known: HashMap<BaselineKey, usize>,
+    /// Every diagnostic seen in this run, used to write a new baseline
+    recorded: BTreeMap<BaselineKey, usize>,
+}
+
+impl Baseline {
+    pub fn new(workspace: PathBuf) -> Self {
+        Baseline {
+            workspace,
+            known: HashMap::new(),
+            recorded: BTreeMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path, workspace: PathBuf) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read baseline \"{}\": {}", path.display(), err))?;
+        let baseline_file: BaselineFile = serde_json::from_str(&content)
+            .map_err(|err| format!("Failed to parse baseline \"{}\": {}", path.display(), err))?;
+        if baseline_file.version != BASELINE_VERSION {
+            return Err(format!(
+                "Unsupported baseline version {} in \"{}\"",
+                baseline_file.version,
+                path.display()
+            )
+            .into());
+        }
+
+        let mut baseline = Self::new(workspace);
+        for entry in baseline_file.entries {
+            let key = BaselineKey {
+                file: entry.file,
+                code: entry.code,
+                fingerprint: entry.fingerprint,
+            };
+            *baseline.known.entry(key).or_default() += entry.count;
+        }
+        Ok(baseline)
+    }
+
+    /// Records the diagnostics of a file and removes the ones the baseline accepts
+    pub fn filter(
+        &mut self,
+        db: &DbIndex,
+        file_id: FileId,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Vec<Diagnostic> {
+        let file = get_relative_path(db, &self.workspace, file_id);
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                let key = BaselineKey {
+                    file: file.clone(),
+                    code: get_code(diagnostic),
+                    fingerprint: get_fingerprint(db, file_id, diagnostic),
+                };
+                *self.recorded.entry(key.clone()).or_default() += 1;
+                match self.known.get_mut(&key) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        false
+                    }
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+
+    /// Baseline entries that no diagnostic of this run matched, the problems were fixed
+    pub fn get_stale_entries(&self) -> Vec<BaselineEntry> {
+        let mut entries = self
+            .known
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(key, count)| BaselineEntry {
+                file: key.file.clone(),
+                code: key.code.clone(),
+                fingerprint: key.fingerprint.clone(),
+                count: *count,
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| {
+            (&a.file, &a.code, &a.fingerprint).cmp(&(&b.file, &b.code, &b.fingerprint))
+        });
+        entries
+    }
+
+    pub fn write(&self, path: &Path) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let baseline_file = BaselineFile {
+            version: BASELINE_VERSION,
+            entries: self
+                .recorded
+                .iter()
+                .map(|(key, count)| BaselineEntry {
+                    file: key.file.clone(),
+                    code: key.code.clone(),
+                    fingerprint: key.fingerprint.clone(),
+                    count: *count,
+                })
+                .collect(),
+        };
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty() && !parent.exists())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&baseline_file)?)?;
+        Ok(self.recorded.values().sum())
+    }
+}
+
+fn get_code(diagnostic: &Diagnostic) -> String {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => code.clone(),
+        Some(NumberOrString::Number(code)) => code.to_string(),
+        None => String::new(),
+    }
+}
+
+/// A hash of the text the diagnostic points at, with whitespace collapsed. Empty ranges
+/// use the text of their line instead.
+fn get_fingerprint(db: &DbIndex, file_id: FileId, diagnostic: &Diagnostic) -> String {
+    let text = db
+        .get_vfs()
+        .get_document(&file_id)
+        .and_then(|document| {
+            let range = document.to_rowan_range(diagnostic.range)?;
+            let range = if range.is_empty() {
+                document.get_line_range(diagnostic.range.start.line as usize)?
+            } else {
+                range
+            };
+            Some(document.get_text_slice(range).to_string())
+        })
+        .unwrap_or_default();
+    let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut hasher = StableHasher::new();
+    hasher.write(normalized.as_bytes());
+    hasher.finish_hex()
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Diagnostic, NumberOrString, Position, Range};
+
+    use super::{Baseline, get_fingerprint};
+    use crate::test_util::TestWorkspace;
+
+    fn make_diagnostic(start: (u32, u32), end: (u32, u32)) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+            code: Some(NumberOrString::String("undefined-global".to_string())),
+            message: "undefined global".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_position_and_whitespace() {
+        let mut ws = TestWorkspace::new("baseline_fingerprint");
+        let a = ws.add_file("a.lua", b"print(foo  .  bar)\n");
+        let b = ws.add_file("b.lua", b"\n\n    print(foo . bar)\n");
+        let db = ws.analysis.compilation.get_db();
+
+        let fingerprint = get_fingerprint(db, a, &make_diagnostic((0, 0), (0, 18)));
+        assert_eq!(
+            fingerprint,
+            get_fingerprint(db, b, &make_diagnostic((2, 4), (2, 20)))
+        );
+        assert_ne!(
+            fingerprint,
+            get_fingerprint(db, a, &make_diagnostic((0, 0), (0, 5)))
+        );
+        // empty ranges use their line
+        assert_eq!(
+            fingerprint,
+            get_fingerprint(db, a, &make_diagnostic((0, 3), (0, 3)))
+        );
+    }
+
+    #[test]
+    fn test_filter_and_stale_entries() {
+        let mut ws = TestWorkspace::new("baseline_filter");
+        let a = ws.add_file("a.lua", b"print(foo)\nprint(foo)\nprint(bar)\n");
+        let db = ws.analysis.compilation.get_db();
+        let foo = make_diagnostic((0, 6), (0, 9));
+        let bar = make_diagnostic((2, 6), (2, 9));
+
+        let mut baseline = Baseline::new(ws.dir.clone());
+        assert_eq!(baseline.filter(db, a, vec![foo.clone(), bar]).len(), 2);
+        let path = ws.dir.join("baseline/baseline.json");
+        assert_eq!(baseline.write(&path).unwrap(), 2);
+
+        // the second `foo` is new, and `bar` was fixed
+        let mut baseline = Baseline::load(&path, ws.dir.clone()).unwrap();
+        let second_foo = make_diagnostic((1, 6), (1, 9));
+        let remaining = baseline.filter(db, a, vec![foo, second_foo.clone()]);
+        assert_eq!(remaining, vec![second_foo]);
+
+        let stale_entries = baseline.get_stale_entries();
+        assert_eq!(stale_entries.len(), 1);
+        assert_eq!(stale_entries[0].file, "a.lua");
+        assert_eq!(stale_entries[0].code, "undefined-global");
+        assert_eq!(stale_entries[0].count, 1);
+    }
+
+    #[test]
+    fn test_reject_unknown_version() {
+        let ws = TestWorkspace::new("baseline_version");
+        let path = ws.dir.join("baseline.json");
+        std::fs::write(&path, r#"{ "version": 2, "entries": [] }"#).unwrap();
+        assert!(Baseline::load(&path, ws.dir.clone()).is_err());
+    }
+}
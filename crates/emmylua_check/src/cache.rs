@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use emmylua_code_analysis::{DbIndex, Emmyrc, FileId};
+use lsp_types::Diagnostic;
+use serde::{Deserialize, Serialize};
+
+use crate::{output::get_relative_path, stable_hash::StableHasher};
+
+const CACHE_FILE_NAME: &str = "emmylua_check_cache.json";
+
+/// The directory of the cache when `--cache-dir` is not given
+pub const DEFAULT_CACHE_DIR: &str = ".emmylua_check_cache";
+
+/// Diagnostics of earlier runs, one entry per file. An entry is reused while the file, the
+/// files it requires directly or through other files, the configuration and the analyzer
+/// version are unchanged.
+#[derive(Debug)]
+pub struct CheckCache {
+    path: PathBuf,
+    workspace: PathBuf,
+    /// Hash of the analyzer version and the configuration
+    config_hash: String,
+    file_hashes: HashMap<FileId, String>,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    diagnostics: Option<Vec<Diagnostic>>,
+}
+
+impl CheckCache {
+    /// Loads the cache in `cache_dir`, a missing or unreadable cache is treated as empty
+    pub fn load(cache_dir: &Path, workspace: PathBuf, db: &DbIndex, emmyrc: &Emmyrc) -> Self {
+        let path = cache_dir.join(CACHE_FILE_NAME);
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let mut hasher = StableHasher::new();
+        hasher.write_str(env!("CARGO_PKG_VERSION"));
+        hasher.write_str(&serde_json::to_string(emmyrc).unwrap_or_default());
+
+        let vfs = db.get_vfs();
+        let file_hashes = vfs
+            .get_all_file_ids()
+            .into_iter()
+            .filter_map(|file_id| {
+                let mut hasher = StableHasher::new();
+                hasher.write_str(vfs.get_file_content(&file_id)?);
+                Some((file_id, hasher.finish_hex()))
+            })
+            .collect();
+
+        CheckCache {
+            path,
+            workspace,
+            config_hash: hasher.finish_hex(),
+            file_hashes,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// The diagnostics of an earlier run of the file
+    pub fn get(&self, db: &DbIndex, file_id: FileId) -> Option<Option<Vec<Diagnostic>>> {
+        let file = get_relative_path(db, &self.workspace, file_id);
+        let key = self.get_file_key(db, file_id)?;
+        let diagnostics = self
+            .lock_entries()
+            .get(&file)
+            .filter(|entry| entry.key == key)?
+            .diagnostics
+            .clone();
+        log::info!("Using cached diagnostics for \"{}\"", file);
+        Some(diagnostics)
+    }
+
+    pub fn insert(&self, db: &DbIndex, file_id: FileId, diagnostics: Option<Vec<Diagnostic>>) {
+        let Some(key) = self.get_file_key(db, file_id) else {
+            return;
+        };
+        let file = get_relative_path(db, &self.workspace, file_id);
+        self.lock_entries()
+            .insert(file, CacheEntry { key, diagnostics });
+    }
+
+    /// Writes the entries of this run together with the ones of the files that were not
+    /// checked, which are validated again when they are read
+    pub fn save(&self) -> Result<(), Box<dyn Error + Sync + Send>> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let entries = self.lock_entries();
+        std::fs::write(&self.path, serde_json::to_string(&*entries)?)?;
+        Ok(())
+    }
+
+    /// Hash of the configuration, the text of the file and the text of every file it
+    /// requires, directly or through other files
+    fn get_file_key(&self, db: &DbIndex, file_id: FileId) -> Option<String> {
+        let mut hasher = StableHasher::new();
+        hasher.write_str(&self.config_hash);
+        hasher.write_str(self.file_hashes.get(&file_id)?);
+
+        let mut dependencies = db
+            .get_file_dependencies_index()
+            .get_file_dependencies()
+            .collect_file_dependencies(file_id)
+            .into_iter()
+            .map(|dependency| {
+                let path = get_relative_path(db, &self.workspace, dependency);
+                let hash = self.file_hashes.get(&dependency).map(String::as_str);
+                (path, hash.unwrap_or_default())
+            })
+            .collect::<Vec<_>>();
+        dependencies.sort();
+        for (path, hash) in dependencies {
+            hasher.write_str(&path);
+            hasher.write_str(hash);
+        }
+        Some(hasher.finish_hex())
+    }
+
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, CacheEntry>> {
+        // the entries stay consistent even when a worker panicked while holding the lock
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use emmylua_code_analysis::Emmyrc;
+
+    use super::CheckCache;
+    use crate::test_util::TestWorkspace;
+
+    #[test]
+    fn test_reuse_when_unchanged() {
+        let mut ws = TestWorkspace::new("cache_reuse");
+        let a = ws.add_file("a.lua", b"local a = 1\n");
+        let b = ws.add_file("b.lua", b"return 1\n");
+        let cache_dir = ws.dir.join("cache");
+        let db = ws.analysis.compilation.get_db();
+        let emmyrc = Emmyrc::default();
+
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        assert_eq!(cache.get(db, a), None);
+        cache.insert(db, a, Some(ws.diagnose(a)));
+        cache.insert(db, b, None);
+        cache.save().unwrap();
+
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        assert_eq!(cache.get(db, a), Some(Some(ws.diagnose(a))));
+        assert_eq!(cache.get(db, b), Some(None));
+    }
+
+    #[test]
+    fn test_keep_entries_of_unchecked_files() {
+        let mut ws = TestWorkspace::new("cache_keep");
+        let a = ws.add_file("a.lua", b"local a = 1\n");
+        let b = ws.add_file("b.lua", b"return 1\n");
+        let cache_dir = ws.dir.join("cache");
+        let db = ws.analysis.compilation.get_db();
+        let emmyrc = Emmyrc::default();
+
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        cache.insert(db, a, Some(Vec::new()));
+        cache.save().unwrap();
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        cache.insert(db, b, Some(Vec::new()));
+        cache.save().unwrap();
+
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        assert!(cache.get(db, a).is_some());
+        assert!(cache.get(db, b).is_some());
+    }
+
+    #[test]
+    fn test_reuse_when_other_file_changed() {
+        let mut ws = TestWorkspace::new("cache_other_file");
+        let a = ws.add_file("a.lua", b"local a = 1\n");
+        ws.add_file("b.lua", b"return 1\n");
+        let cache_dir = ws.dir.join("cache");
+        let emmyrc = Emmyrc::default();
+
+        let db = ws.analysis.compilation.get_db();
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        cache.insert(db, a, Some(Vec::new()));
+        cache.save().unwrap();
+
+        ws.add_file("b.lua", b"return 2\n");
+        let db = ws.analysis.compilation.get_db();
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        assert_eq!(cache.get(db, a), Some(Some(Vec::new())));
+    }
+
+    #[test]
+    fn test_invalidate_on_file_change() {
+        let mut ws = TestWorkspace::new("cache_file");
+        let a = ws.add_file("a.lua", b"local a = 1\n");
+        let cache_dir = ws.dir.join("cache");
+        let emmyrc = Emmyrc::default();
+
+        let db = ws.analysis.compilation.get_db();
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        cache.insert(db, a, Some(Vec::new()));
+        cache.save().unwrap();
+
+        ws.add_file("a.lua", b"local a = 2\n");
+        let db = ws.analysis.compilation.get_db();
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        assert_eq!(cache.get(db, a), None);
+    }
+
+    #[test]
+    fn test_invalidate_on_transitive_require_change() {
+        let mut ws = TestWorkspace::new("cache_require");
+        ws.add_file("c.lua", b"return { value = 1 }\n");
+        ws.add_file("b.lua", b"return require(\"c\")\n");
+        let a = ws.add_file("a.lua", b"local b = require(\"b\")\nreturn b.value\n");
+        let cache_dir = ws.dir.join("cache");
+        let emmyrc = Emmyrc::default();
+
+        let db = ws.analysis.compilation.get_db();
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        cache.insert(db, a, Some(Vec::new()));
+        cache.save().unwrap();
+
+        ws.add_file("c.lua", b"return { other = 1 }\n");
+        let db = ws.analysis.compilation.get_db();
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        assert_eq!(cache.get(db, a), None);
+    }
+
+    #[test]
+    fn test_invalidate_on_config_change() {
+        let mut ws = TestWorkspace::new("cache_config");
+        let a = ws.add_file("a.lua", b"return 1\n");
+        let cache_dir = ws.dir.join("cache");
+        let db = ws.analysis.compilation.get_db();
+
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &Emmyrc::default());
+        cache.insert(db, a, Some(Vec::new()));
+        cache.save().unwrap();
+
+        let mut emmyrc = Emmyrc::default();
+        emmyrc.diagnostics.globals.push("Global".to_string());
+        let cache = CheckCache::load(&cache_dir, ws.dir.clone(), db, &emmyrc);
+        assert_eq!(cache.get(db, a), None);
+    }
+}
@@ -0,0 +1,324 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    ops::Range,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use emmylua_code_analysis::{DbIndex, FileId};
+use lsp_types::Diagnostic;
+
+use crate::output::get_relative_path;
+
+/// The files, and their lines, that changed in the working tree since a git revision
+#[derive(Debug)]
+pub struct ChangedFiles {
+    workspace: PathBuf,
+    /// Changed files relative to the workspace, with the 0-based changed line ranges.
+    /// Untracked files have no ranges, all their lines are new.
+    files: HashMap<String, Option<Vec<Range<u32>>>>,
+}
+
+impl ChangedFiles {
+    pub fn load(workspace: PathBuf, rev: &str) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let diff = run_git(
+            &workspace,
+            &[
+                "diff",
+                "--relative",
+                "--unified=0",
+                "--no-color",
+                "--no-ext-diff",
+                // the prefixes can be changed or removed in the user's git config
+                "--src-prefix=a/",
+                "--dst-prefix=b/",
+                rev,
+                "--",
+            ],
+        )?;
+        let mut files = parse_diff(&diff);
+
+        let untracked = run_git(
+            &workspace,
+            &["ls-files", "--others", "--exclude-standard", "-z"],
+        )?;
+        for file in untracked.split('\0').filter(|file| !file.is_empty()) {
+            files.insert(file.to_string(), None);
+        }
+
+        Ok(ChangedFiles { workspace, files })
+    }
+
+    pub fn contains(&self, db: &DbIndex, file_id: FileId) -> bool {
+        let file = get_relative_path(db, &self.workspace, file_id);
+        self.files.contains_key(&file)
+    }
+
+    /// Keeps the diagnostics which start or end on a changed line
+    pub fn filter_lines(
+        &self,
+        db: &DbIndex,
+        file_id: FileId,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Vec<Diagnostic> {
+        let file = get_relative_path(db, &self.workspace, file_id);
+        let Some(ranges) = self.files.get(&file) else {
+            return Vec::new();
+        };
+        let Some(ranges) = ranges else {
+            return diagnostics;
+        };
+
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                let start = diagnostic.range.start.line;
+                let end = diagnostic.range.end.line;
+                ranges
+                    .iter()
+                    .any(|range| range.start <= end && start < range.end)
+            })
+            .collect()
+    }
+}
+
+fn run_git(workspace: &Path, args: &[&str]) -> Result<String, Box<dyn Error + Sync + Send>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(workspace)
+        .args(["-c", "core.quotePath=false"])
+        .args(args)
+        .output()
+        .map_err(|err| format!("Failed to run git: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Collects the new side of the hunks of a `git diff --unified=0`
+fn parse_diff(diff: &str) -> HashMap<String, Option<Vec<Range<u32>>>> {
+    let mut files: HashMap<String, Option<Vec<Range<u32>>>> = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            // deleted files have no new side
+            current = parse_diff_path(path);
+            if let Some(path) = &current {
+                files.insert(path.clone(), Some(Vec::new()));
+            }
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            let Some(path) = &current else {
+                continue;
+            };
+            let Some(range) = parse_hunk_range(hunk) else {
+                continue;
+            };
+            if let Some(Some(ranges)) = files.get_mut(path) {
+                ranges.push(range);
+            }
+        }
+    }
+
+    files
+}
+
+/// The path of a `+++ ` line without its `b/` prefix. Git quotes paths with special
+/// characters like C strings, and ends unquoted paths that contain spaces with a tab.
+fn parse_diff_path(path: &str) -> Option<String> {
+    let path = match path.strip_prefix('"') {
+        Some(quoted) => unquote_c_style(quoted.strip_suffix('"')?)?,
+        None => path.strip_suffix('\t').unwrap_or(path).to_string(),
+    };
+    path.strip_prefix("b/").map(|path| path.to_string())
+}
+
+/// Undoes the escapes of a quoted path, octal escapes are the bytes of UTF-8 characters
+fn unquote_c_style(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let unescaped = match iter.next()? {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b't' => b'\t',
+            b'n' => b'\n',
+            b'v' => 0x0b,
+            b'f' => 0x0c,
+            b'r' => b'\r',
+            digit @ b'0'..=b'7' => {
+                let mut value = u32::from(digit - b'0');
+                for _ in 0..2 {
+                    let digit = iter.next().filter(|digit| (b'0'..=b'7').contains(digit))?;
+                    value = value * 8 + u32::from(digit - b'0');
+                }
+                u8::try_from(value).ok()?
+            }
+            other => other,
+        };
+        bytes.push(unescaped);
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Parses the `+start,count` part of a hunk header into 0-based lines
+fn parse_hunk_range(hunk: &str) -> Option<Range<u32>> {
+    let new_side = hunk
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match new_side.split_once(',') {
+        Some((start, count)) => (start.parse::<u32>().ok()?, count.parse::<u32>().ok()?),
+        None => (new_side.parse::<u32>().ok()?, 1),
+    };
+    let start = start.saturating_sub(1);
+    Some(start..start + count)
+}
+
+#[cfg(test)]
+// the expected line ranges are single ranges on purpose
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use std::collections::HashMap;
+
+    use lsp_types::{Diagnostic, Position, Range};
+
+    use super::{ChangedFiles, parse_diff, run_git, unquote_c_style};
+    use crate::test_util::TestWorkspace;
+
+    const DIFF: &str = r#"diff --git a/src/a.lua b/src/a.lua
+index 1111111..2222222 100644
+--- a/src/a.lua
++++ b/src/a.lua
+@@ -1 +1 @@
+-local a = 1
++local a = 2
+@@ -10,0 +11,3 @@ local function f()
++print(1)
++print(2)
++print(3)
+@@ -20,2 +23,0 @@
+-print(4)
+-print(5)
+diff --git a/old.lua b/old.lua
+deleted file mode 100644
+--- a/old.lua
++++ /dev/null
+@@ -1 +0,0 @@
+-return 1
+diff --git a/sp ace.lua b/sp ace.lua
+--- a/sp ace.lua	
++++ b/sp ace.lua	
+@@ -1 +1 @@
+-a
++b
+diff --git "a/q\"uote.lua" "b/q\"uote.lua"
+--- "a/q\"uote.lua"
++++ "b/q\"uote.lua"
+@@ -1 +1 @@
+-a
++b
+diff --git "a/\303\274.lua" "b/\303\274.lua"
+--- "a/\303\274.lua"
++++ "b/\303\274.lua"
+@@ -1 +1 @@
+-a
++b
+"#;
+
+    #[test]
+    fn test_parse_diff() {
+        let files = parse_diff(DIFF);
+        assert_eq!(files.len(), 4);
+        assert_eq!(files["src/a.lua"], Some(vec![0..1, 10..13, 22..22]));
+        assert_eq!(files["sp ace.lua"], Some(vec![0..1]));
+        assert_eq!(files["q\"uote.lua"], Some(vec![0..1]));
+        assert_eq!(files["ü.lua"], Some(vec![0..1]));
+        assert!(!files.contains_key("old.lua"));
+    }
+
+    #[test]
+    fn test_unquote_c_style() {
+        assert_eq!(
+            unquote_c_style(r#"a\tb\\c\"d"#).as_deref(),
+            Some("a\tb\\c\"d")
+        );
+        assert_eq!(unquote_c_style(r"\344\270\255").as_deref(), Some("中"));
+        assert_eq!(unquote_c_style(r"\34"), None);
+        assert_eq!(unquote_c_style(r"a\"), None);
+    }
+
+    #[test]
+    fn test_filter_lines() {
+        let mut ws = TestWorkspace::new("changed");
+        let a = ws.add_file("a.lua", b"return 1\n");
+        let b = ws.add_file("b.lua", b"return 1\n");
+        let c = ws.add_file("c.lua", b"return 1\n");
+        let db = ws.analysis.compilation.get_db();
+        let changed_files = ChangedFiles {
+            workspace: ws.dir.clone(),
+            files: HashMap::from([
+                ("a.lua".to_string(), Some(vec![2..4])),
+                ("b.lua".to_string(), None),
+            ]),
+        };
+        let make_diagnostic = |start: u32, end: u32| Diagnostic {
+            range: Range::new(Position::new(start, 0), Position::new(end, 0)),
+            ..Default::default()
+        };
+        let diagnostics = vec![
+            make_diagnostic(0, 1),
+            make_diagnostic(1, 2),
+            make_diagnostic(3, 3),
+            make_diagnostic(4, 5),
+        ];
+
+        assert!(changed_files.contains(db, a));
+        assert!(!changed_files.contains(db, c));
+        assert_eq!(
+            changed_files.filter_lines(db, a, diagnostics.clone()),
+            vec![make_diagnostic(1, 2), make_diagnostic(3, 3)]
+        );
+        assert_eq!(
+            changed_files.filter_lines(db, b, diagnostics.clone()),
+            diagnostics
+        );
+        assert!(changed_files.filter_lines(db, c, diagnostics).is_empty());
+    }
+
+    #[test]
+    fn test_load_ignores_prefix_config() {
+        let ws = TestWorkspace::new("changed_prefix");
+        let git = |args: &[&str]| run_git(&ws.dir, args).unwrap();
+        git(&["init", "-q"]);
+        git(&["config", "diff.noprefix", "true"]);
+        std::fs::write(ws.dir.join("a.lua"), "local a = 1\n").unwrap();
+        git(&["add", "a.lua"]);
+        git(&[
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "-q",
+            "-m",
+            "init",
+        ]);
+        std::fs::write(ws.dir.join("a.lua"), "local a = 1\nlocal b = 2\n").unwrap();
+        std::fs::write(ws.dir.join("new file.lua"), "return 1\n").unwrap();
+
+        let changed_files = ChangedFiles::load(ws.dir.clone(), "HEAD").unwrap();
+        assert_eq!(changed_files.files["a.lua"], Some(vec![1..2]));
+        assert_eq!(changed_files.files["new file.lua"], None);
+    }
+}
@@ -1,5 +1,6 @@
 #[cfg(feature = "cli")]
-use clap::{Parser, ValueEnum};
+use clap::{Args, Parser, ValueEnum};
+use emmylua_code_analysis::{DiagnosticCode, DiagnosticSeveritySetting};
 
 use std::path::PathBuf;
 
@@ -15,7 +16,7 @@ pub struct CmdArgs {
     pub config: Option<Vec<PathBuf>>,
 
     /// Path to the workspace directory
-    #[arg(num_args = 1..)]
+    #[cfg_attr(feature = "cli", arg(num_args = 1..))]
     pub workspace: Vec<PathBuf>,
 
     /// Comma separated list of ignore patterns.
@@ -36,10 +37,60 @@ pub struct CmdArgs {
     )]
     pub output_format: OutputFormat,
 
-    /// Specify output destination (stdout or a file path, only used when output_format is json)
+    /// Specify output destination (stdout or a file path, not used when output_format is text)
     #[cfg_attr(feature = "cli", arg(long, default_value = "stdout"))]
     pub output: OutputDestination,
 
+    #[cfg_attr(feature = "cli", command(flatten))]
+    pub diagnostics: DiagnosticArgs,
+
+    /// Only report diagnostics of files changed since this git revision.
+    /// The whole workspace is still analyzed
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub changed_since: Option<String>,
+
+    /// With --changed-since, only report diagnostics on changed lines
+    #[cfg_attr(feature = "cli", arg(long, requires = "changed_since"))]
+    pub changed_lines_only: bool,
+
+    /// Record the current diagnostics to a baseline file, then exit successfully
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub write_baseline: Option<PathBuf>,
+
+    /// Suppress the diagnostics recorded in a baseline file and only report new ones
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub baseline: Option<PathBuf>,
+
+    /// Directory of the diagnostics cache.
+    /// Defaults to ".emmylua_check_cache" in the workspace directory
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Do not read or write the diagnostics cache
+    #[cfg_attr(feature = "cli", arg(long, conflicts_with = "cache_dir"))]
+    pub no_cache: bool,
+
+    /// Apply the safe quick fixes of the diagnostics to the files
+    #[cfg_attr(feature = "cli", arg(long, conflicts_with = "fix_dry_run"))]
+    pub fix: bool,
+
+    /// Print the diff of the safe quick fixes instead of applying them
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub fix_dry_run: bool,
+
+    /// Keep running and check the changed files, and the files that require them, whenever
+    /// files in the workspace change
+    #[cfg_attr(
+        feature = "cli",
+        arg(long, conflicts_with_all = ["fix", "fix_dry_run", "write_baseline", "changed_since"])
+    )]
+    pub watch: bool,
+
+    /// Number of files to check in parallel.
+    /// Defaults to the number of available CPU cores
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub jobs: Option<usize>,
+
     /// Treat warnings as errors
     #[cfg_attr(feature = "cli", arg(long))]
     pub warnings_as_errors: bool,
@@ -49,11 +100,81 @@ pub struct CmdArgs {
     pub verbose: bool,
 }
 
+/// Overrides of the diagnostics configuration, applied on top of the configuration files
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "cli", derive(Args))]
+pub struct DiagnosticArgs {
+    /// Comma separated list of diagnostic codes to enable, including the ones disabled by
+    /// default
+    #[cfg_attr(
+        feature = "cli",
+        arg(long, value_delimiter = ',', value_parser = parse_diagnostic_code)
+    )]
+    pub enable: Vec<DiagnosticCode>,
+
+    /// Comma separated list of diagnostic codes to disable
+    #[cfg_attr(
+        feature = "cli",
+        arg(long, value_delimiter = ',', value_parser = parse_diagnostic_code)
+    )]
+    pub disable: Vec<DiagnosticCode>,
+
+    /// Override the severity of a diagnostic code, as `code=level`.
+    /// The level is one of error, warning, information and hint
+    #[cfg_attr(
+        feature = "cli",
+        arg(long, value_delimiter = ',', value_parser = parse_severity_override)
+    )]
+    pub severity: Vec<(DiagnosticCode, DiagnosticSeveritySetting)>,
+
+    /// Only report diagnostics of this severity or higher
+    #[cfg_attr(feature = "cli", arg(long, value_parser = parse_severity))]
+    pub min_severity: Option<DiagnosticSeveritySetting>,
+}
+
+pub fn parse_diagnostic_code(s: &str) -> Result<DiagnosticCode, String> {
+    match s.parse::<DiagnosticCode>() {
+        Ok(DiagnosticCode::None) | Err(_) => Err(format!("unknown diagnostic code \"{}\"", s)),
+        Ok(code) => Ok(code),
+    }
+}
+
+pub fn parse_severity(s: &str) -> Result<DiagnosticSeveritySetting, String> {
+    match s.to_lowercase().as_str() {
+        "error" => Ok(DiagnosticSeveritySetting::Error),
+        "warning" => Ok(DiagnosticSeveritySetting::Warning),
+        "information" | "info" => Ok(DiagnosticSeveritySetting::Information),
+        "hint" => Ok(DiagnosticSeveritySetting::Hint),
+        _ => Err(format!(
+            "unknown severity \"{}\", expected error, warning, information or hint",
+            s
+        )),
+    }
+}
+
+pub fn parse_severity_override(
+    s: &str,
+) -> Result<(DiagnosticCode, DiagnosticSeveritySetting), String> {
+    let (code, severity) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected `code=level`, got \"{}\"", s))?;
+    Ok((
+        parse_diagnostic_code(code.trim())?,
+        parse_severity(severity.trim())?,
+    ))
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "cli", derive(ValueEnum))]
 pub enum OutputFormat {
     Json,
     Text,
+    /// SARIF 2.1.0, for code scanning dashboards
+    Sarif,
+    /// JUnit XML, every error and warning is a failed test case
+    Junit,
+    /// Checkstyle XML
+    Checkstyle,
 }
 
 #[allow(unused)]
@@ -72,3 +193,46 @@ impl std::str::FromStr for OutputDestination {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use emmylua_code_analysis::{DiagnosticCode, DiagnosticSeveritySetting};
+
+    use super::{parse_diagnostic_code, parse_severity, parse_severity_override};
+
+    #[test]
+    fn test_parse_severity_override() {
+        assert!(matches!(
+            parse_severity_override("unused=error"),
+            Ok((DiagnosticCode::Unused, DiagnosticSeveritySetting::Error))
+        ));
+        assert!(matches!(
+            parse_severity_override(" undefined-global = Info "),
+            Ok((
+                DiagnosticCode::UndefinedGlobal,
+                DiagnosticSeveritySetting::Information
+            ))
+        ));
+        assert!(parse_severity_override("unused").is_err());
+        assert!(parse_severity_override("unused=fatal").is_err());
+        assert!(parse_severity_override("no-such-code=error").is_err());
+    }
+
+    #[test]
+    fn test_parse_severity() {
+        assert!(matches!(
+            parse_severity("HINT"),
+            Ok(DiagnosticSeveritySetting::Hint)
+        ));
+        assert!(parse_severity("").is_err());
+    }
+
+    #[test]
+    fn test_parse_diagnostic_code() {
+        assert_eq!(
+            parse_diagnostic_code("need-check-nil"),
+            Ok(DiagnosticCode::NeedCheckNil)
+        );
+        assert!(parse_diagnostic_code("none").is_err());
+    }
+}
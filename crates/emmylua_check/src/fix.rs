@@ -0,0 +1,336 @@
+use std::{collections::BTreeMap, error::Error, io::Write, path::PathBuf, str::FromStr};
+
+use emmylua_code_analysis::{
+    DbIndex, DiagnosticCode, FileId, get_unused_fix, write_file_with_encoding,
+};
+use emmylua_parser::{LuaAstNode, LuaStat, LuaSyntaxKind, LuaVarExpr};
+use lsp_types::{Diagnostic, NumberOrString};
+use rowan::TextRange;
+use similar::TextDiff;
+
+use crate::output::get_relative_path;
+
+/// Collects the quick fixes of the diagnostics and applies them to the files on disk.
+///
+/// Only fixes which never change what the code does are applied, and a fix is skipped
+/// when it touches the range of a fix that was already accepted in the same file. Besides
+/// the fixes of the diagnostics, globals that are only used after their assignment in the
+/// same file are declared `local`.
+#[derive(Debug)]
+pub struct Fixer {
+    workspace: PathBuf,
+    dry_run: bool,
+    edits: BTreeMap<FileId, Vec<(TextRange, String)>>,
+}
+
+impl Fixer {
+    pub fn new(workspace: PathBuf, dry_run: bool) -> Self {
+        Fixer {
+            workspace,
+            dry_run,
+            edits: BTreeMap::new(),
+        }
+    }
+
+    /// Records the fixes of a file and returns the diagnostics which will not be fixed.
+    /// On a dry run nothing is written, so every diagnostic is returned.
+    pub fn filter(
+        &mut self,
+        db: &DbIndex,
+        file_id: FileId,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Vec<Diagnostic> {
+        let Some(document) = db.get_vfs().get_document(&file_id) else {
+            return diagnostics;
+        };
+
+        let diagnostics = diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                let Some(edit) = document
+                    .to_rowan_range(diagnostic.range)
+                    .and_then(|range| build_fix(db, file_id, diagnostic, range))
+                else {
+                    return true;
+                };
+                !self.add_edit(file_id, edit) || self.dry_run
+            })
+            .collect();
+
+        for edit in get_missing_local_fixes(db, file_id) {
+            self.add_edit(file_id, edit);
+        }
+        diagnostics
+    }
+
+    /// Records the edit unless it touches an edit already accepted in the file
+    fn add_edit(&mut self, file_id: FileId, edit: (TextRange, String)) -> bool {
+        let edits = self.edits.entry(file_id).or_default();
+        let overlaps = edits
+            .iter()
+            .any(|(range, _)| range.start() <= edit.0.end() && edit.0.start() <= range.end());
+        if overlaps {
+            return false;
+        }
+
+        edits.push(edit);
+        true
+    }
+
+    /// Writes the fixed files with the encoding of the workspace, or writes their diff to
+    /// `out` on a dry run. Returns the number of applied fixes.
+    pub fn apply(
+        &self,
+        db: &DbIndex,
+        out: &mut impl Write,
+    ) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let vfs = db.get_vfs();
+        let encoding = &db.get_emmyrc().workspace.encoding;
+        let mut count = 0;
+        for (file_id, edits) in &self.edits {
+            let (Some(path), Some(original)) =
+                (vfs.get_file_path(file_id), vfs.get_file_content(file_id))
+            else {
+                continue;
+            };
+
+            let fixed = apply_edits(original, edits);
+            if self.dry_run {
+                let relative_path = get_relative_path(db, &self.workspace, *file_id);
+                write!(
+                    out,
+                    "{}",
+                    TextDiff::from_lines(original.as_str(), fixed.as_str())
+                        .unified_diff()
+                        .context_radius(3)
+                        .header(
+                            &format!("a/{}", relative_path),
+                            &format!("b/{}", relative_path)
+                        )
+                )?;
+            } else {
+                write_file_with_encoding(path, &fixed, encoding)
+                    .map_err(|err| format!("Failed to write \"{}\": {}", path.display(), err))?;
+            }
+            count += edits.len();
+        }
+
+        Ok(count)
+    }
+}
+
+fn apply_edits(text: &str, edits: &[(TextRange, String)]) -> String {
+    let mut edits = edits.iter().collect::<Vec<_>>();
+    edits.sort_by_key(|(range, _)| range.start());
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (range, new_text) in edits {
+        let start = usize::from(range.start());
+        result.push_str(&text[last..start]);
+        result.push_str(new_text);
+        last = usize::from(range.end());
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+/// The same fixes as the quick fixes of the language server, limited to the ones that
+/// keep the behaviour of the code
+fn build_fix(
+    db: &DbIndex,
+    file_id: FileId,
+    diagnostic: &Diagnostic,
+    range: TextRange,
+) -> Option<(TextRange, String)> {
+    let Some(NumberOrString::String(code)) = &diagnostic.code else {
+        return None;
+    };
+    match DiagnosticCode::from_str(code).ok()? {
+        DiagnosticCode::Unused => get_unused_fix(db, file_id, range),
+        _ => None,
+    }
+}
+
+/// Declares the globals of the file that can be locals: the global is first assigned by a
+/// statement of the top level block of the file, as `name = value` or `function name()`, and
+/// no code uses it before that statement or outside the file, including through `_G`.
+/// Globals of the configuration are kept.
+fn get_missing_local_fixes(db: &DbIndex, file_id: FileId) -> Vec<(TextRange, String)> {
+    let Some(syntax_tree) = db.get_vfs().get_syntax_tree(&file_id) else {
+        return Vec::new();
+    };
+    let Some(block) = syntax_tree.get_chunk_node().get_block() else {
+        return Vec::new();
+    };
+
+    let globals = &db.get_emmyrc().diagnostics.globals;
+    block
+        .get_stats()
+        .filter_map(|stat| {
+            // the references in the body of a function can see the local of `local function`
+            let (name_expr, visible_from) = match &stat {
+                LuaStat::AssignStat(assign_stat) => match assign_stat.get_var_and_expr_list() {
+                    (vars, exprs) if vars.len() == 1 && !exprs.is_empty() => match &vars[0] {
+                        LuaVarExpr::NameExpr(name_expr) => {
+                            (name_expr.clone(), assign_stat.get_range().end())
+                        }
+                        _ => return None,
+                    },
+                    _ => return None,
+                },
+                LuaStat::FuncStat(func_stat) => match func_stat.get_func_name()? {
+                    LuaVarExpr::NameExpr(name_expr) => {
+                        let visible_from = name_expr.get_range().end();
+                        (name_expr, visible_from)
+                    }
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            let name = name_expr.get_name_text()?;
+            if globals.contains(&name) {
+                return None;
+            }
+
+            let decl_ids = db.get_global_index().get_global_decl_ids(&name)?;
+            let [decl_id] = decl_ids.as_slice() else {
+                return None;
+            };
+            let decl = db.get_decl_index().get_decl(decl_id)?;
+            if decl.get_file_id() != file_id || decl.get_range() != name_expr.get_range() {
+                return None;
+            }
+
+            let references = db.get_reference_index().get_global_references(&name)?;
+            let is_private = references.iter().all(|reference| {
+                let range = reference.value.get_range();
+                reference.file_id == file_id
+                    && reference.value.get_kind() == LuaSyntaxKind::NameExpr
+                    && (range == decl.get_range() || range.start() >= visible_from)
+            });
+            is_private.then(|| (TextRange::empty(stat.get_position()), "local ".to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::NumberOrString;
+
+    use super::Fixer;
+    use crate::test_util::TestWorkspace;
+
+    fn get_codes(diagnostics: &[lsp_types::Diagnostic]) -> Vec<String> {
+        diagnostics
+            .iter()
+            .filter_map(|diagnostic| match &diagnostic.code {
+                Some(NumberOrString::String(code)) => Some(code.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fix_unused() {
+        let mut ws = TestWorkspace::new("fix");
+        let file_id = ws.add_file("a.lua", b"\xEF\xBB\xBFlocal a = 1\n");
+        let db = ws.analysis.compilation.get_db();
+        let mut fixer = Fixer::new(ws.dir.clone(), false);
+        let diagnostics = ws.diagnose(file_id);
+        assert!(get_codes(&diagnostics).contains(&"unused".to_string()));
+
+        let remaining = fixer.filter(db, file_id, diagnostics);
+        assert!(!get_codes(&remaining).contains(&"unused".to_string()));
+
+        let mut out = Vec::new();
+        assert_eq!(fixer.apply(db, &mut out).unwrap(), 1);
+        assert!(out.is_empty());
+        assert_eq!(
+            std::fs::read(ws.dir.join("a.lua")).unwrap(),
+            b"\xEF\xBB\xBFlocal _a = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_dry_run_keeps_diagnostics() {
+        let mut ws = TestWorkspace::new("fix_dry_run");
+        let file_id = ws.add_file("a.lua", b"local a = 1\n");
+        let db = ws.analysis.compilation.get_db();
+        let mut fixer = Fixer::new(ws.dir.clone(), true);
+        let diagnostics = ws.diagnose(file_id);
+        let count = diagnostics.len();
+
+        assert_eq!(fixer.filter(db, file_id, diagnostics).len(), count);
+
+        let mut out = Vec::new();
+        assert_eq!(fixer.apply(db, &mut out).unwrap(), 1);
+        let diff = String::from_utf8(out).unwrap();
+        assert!(diff.contains("--- a/a.lua"));
+        assert!(diff.contains("+local _a = 1"));
+        assert_eq!(
+            std::fs::read(ws.dir.join("a.lua")).unwrap(),
+            b"local a = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_skip_taken_name() {
+        let mut ws = TestWorkspace::new("fix_taken");
+        let file_id = ws.add_file("a.lua", b"local a = 1\nlocal _a = 2\nreturn _a\n");
+        let db = ws.analysis.compilation.get_db();
+        let mut fixer = Fixer::new(ws.dir.clone(), false);
+        let diagnostics = ws.diagnose(file_id);
+        let count = diagnostics.len();
+
+        assert_eq!(fixer.filter(db, file_id, diagnostics).len(), count);
+        assert_eq!(fixer.apply(db, &mut Vec::new()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_need_check_nil_is_not_fixed() {
+        let mut ws = TestWorkspace::new("fix_nil");
+        let file_id = ws.add_file("a.lua", b"---@type { x: number }?\nlocal t\nreturn t.x\n");
+        let db = ws.analysis.compilation.get_db();
+        let mut fixer = Fixer::new(ws.dir.clone(), false);
+        let diagnostics = ws.diagnose(file_id);
+        assert!(get_codes(&diagnostics).contains(&"need-check-nil".to_string()));
+
+        let remaining = fixer.filter(db, file_id, diagnostics);
+        assert!(get_codes(&remaining).contains(&"need-check-nil".to_string()));
+        assert_eq!(fixer.apply(db, &mut Vec::new()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_fix_missing_local() {
+        let mut ws = TestWorkspace::new("fix_local");
+        let file_id = ws.add_file(
+            "a.lua",
+            b"count = 0\nfunction bump()\n    count = count + 1\n    return bump\nend\nreturn bump\n",
+        );
+        let db = ws.analysis.compilation.get_db();
+        let mut fixer = Fixer::new(ws.dir.clone(), false);
+        fixer.filter(db, file_id, ws.diagnose(file_id));
+
+        assert_eq!(fixer.apply(db, &mut Vec::new()).unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(ws.dir.join("a.lua")).unwrap(),
+            "local count = 0\nlocal function bump()\n    count = count + 1\n    return bump\nend\nreturn bump\n"
+        );
+    }
+
+    #[test]
+    fn test_keep_globals_used_elsewhere() {
+        let mut ws = TestWorkspace::new("fix_local_kept");
+        let file_id = ws.add_file(
+            "a.lua",
+            b"local before = Early\nEarly = 1\nif before then\n    Nested = 1\nend\nShared = 1\nViaG = 1\nreturn _G.ViaG\n",
+        );
+        ws.add_file("b.lua", b"return Shared\n");
+        let db = ws.analysis.compilation.get_db();
+        let mut fixer = Fixer::new(ws.dir.clone(), false);
+        fixer.filter(db, file_id, ws.diagnose(file_id));
+
+        assert_eq!(fixer.apply(db, &mut Vec::new()).unwrap(), 0);
+    }
+}
@@ -2,12 +2,17 @@ use emmylua_code_analysis::{
     EmmyLuaAnalysis, Emmyrc, LuaFileInfo, load_configs, load_workspace_files, update_code_style,
 };
 use fern::Dispatch;
+
+use crate::cmd_args::DiagnosticArgs;
 use log::LevelFilter;
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-fn root_from_configs(config_paths: &Vec<PathBuf>, fallback: &PathBuf) -> PathBuf {
+pub fn root_from_configs(config_paths: &[PathBuf], fallback: &Path) -> PathBuf {
     if config_paths.len() != 1 {
-        fallback.clone()
+        fallback.to_path_buf()
     } else {
         let config_path = &config_paths[0];
         // Need to convert to canonical path to ensure parent() is not an empty
@@ -20,7 +25,7 @@ fn root_from_configs(config_paths: &Vec<PathBuf>, fallback: &PathBuf) -> PathBuf
                     config_path,
                     err
                 );
-                fallback.clone()
+                fallback.to_path_buf()
             }
         }
     }
@@ -58,18 +63,35 @@ pub fn setup_logger(verbose: bool) {
     }
 }
 
-pub fn load_workspace(
-    main_path: PathBuf,
-    mut workspace_folders: Vec<PathBuf>,
-    config_paths: Option<Vec<PathBuf>>,
-    ignore: Option<Vec<String>>,
-) -> Option<EmmyLuaAnalysis> {
+/// Overlays the diagnostic options of the command line on the configuration files
+fn apply_diagnostic_args(emmyrc: &mut Emmyrc, diagnostic_args: &DiagnosticArgs) {
+    let diagnostics = &mut emmyrc.diagnostics;
+    for code in &diagnostic_args.enable {
+        diagnostics.disable.retain(|disabled| disabled != code);
+        if !diagnostics.enables.contains(code) {
+            diagnostics.enables.push(*code);
+        }
+    }
+
+    for code in &diagnostic_args.disable {
+        diagnostics.enables.retain(|enabled| enabled != code);
+        if !diagnostics.disable.contains(code) {
+            diagnostics.disable.push(*code);
+        }
+    }
+
+    for (code, severity) in &diagnostic_args.severity {
+        diagnostics.severity.insert(*code, *severity);
+    }
+}
+
+/// Loads the configuration files given on the command line, or the ones found in the
+/// main workspace directory
+pub fn load_emmyrc(main_path: &Path, config_paths: Option<Vec<PathBuf>>) -> Emmyrc {
     let (config_files, config_root): (Vec<PathBuf>, PathBuf) =
         if let Some(config_paths) = config_paths {
-            (
-                config_paths.clone(),
-                root_from_configs(&config_paths, &main_path),
-            )
+            let config_root = root_from_configs(&config_paths, main_path);
+            (config_paths, config_root)
         } else {
             (
                 vec![
@@ -79,7 +101,7 @@ pub fn load_workspace(
                 .into_iter()
                 .filter(|path| path.exists())
                 .collect(),
-                main_path.clone(),
+                main_path.to_path_buf(),
             )
         };
 
@@ -89,6 +111,18 @@ pub fn load_workspace(
         config_root.display()
     );
     emmyrc.pre_process_emmyrc(&config_root);
+    emmyrc
+}
+
+pub fn load_workspace(
+    main_path: PathBuf,
+    mut workspace_folders: Vec<PathBuf>,
+    config_paths: Option<Vec<PathBuf>>,
+    ignore: Option<Vec<String>>,
+    diagnostic_args: &DiagnosticArgs,
+) -> Option<EmmyLuaAnalysis> {
+    let mut emmyrc = load_emmyrc(&main_path, config_paths);
+    apply_diagnostic_args(&mut emmyrc, diagnostic_args);
 
     let mut analysis = EmmyLuaAnalysis::new();
     analysis.update_config(emmyrc.clone().into());
@@ -134,7 +168,7 @@ pub fn load_workspace(
 }
 
 pub fn collect_files(
-    workspaces: &Vec<PathBuf>,
+    workspaces: &[PathBuf],
     emmyrc: &Emmyrc,
     ignore: Option<Vec<String>>,
 ) -> Vec<LuaFileInfo> {
@@ -206,3 +240,41 @@ pub fn calculate_include_and_exclude(
 
     (include, exclude, exclude_dirs)
 }
+
+#[cfg(test)]
+mod tests {
+    use emmylua_code_analysis::{DiagnosticCode, DiagnosticSeveritySetting, Emmyrc};
+
+    use super::apply_diagnostic_args;
+    use crate::cmd_args::DiagnosticArgs;
+
+    #[test]
+    fn test_apply_diagnostic_args() {
+        let mut emmyrc = Emmyrc::default();
+        emmyrc.diagnostics.disable.push(DiagnosticCode::Unused);
+        emmyrc
+            .diagnostics
+            .enables
+            .push(DiagnosticCode::UnknownDocTag);
+        apply_diagnostic_args(
+            &mut emmyrc,
+            &DiagnosticArgs {
+                enable: vec![DiagnosticCode::Unused],
+                disable: vec![DiagnosticCode::UnknownDocTag],
+                severity: vec![(
+                    DiagnosticCode::UndefinedGlobal,
+                    DiagnosticSeveritySetting::Hint,
+                )],
+                min_severity: None,
+            },
+        );
+
+        let diagnostics = &emmyrc.diagnostics;
+        assert_eq!(diagnostics.enables, vec![DiagnosticCode::Unused]);
+        assert_eq!(diagnostics.disable, vec![DiagnosticCode::UnknownDocTag]);
+        assert!(matches!(
+            diagnostics.severity.get(&DiagnosticCode::UndefinedGlobal),
+            Some(DiagnosticSeveritySetting::Hint)
+        ));
+    }
+}
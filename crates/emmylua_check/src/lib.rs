@@ -1,14 +1,34 @@
+mod baseline;
+mod cache;
+mod changed;
 pub mod cmd_args;
-mod init;
+mod fix;
+pub mod init;
 mod output;
+mod stable_hash;
 mod terminal_display;
+#[cfg(test)]
+mod test_util;
+mod watch;
 
+use baseline::Baseline;
+use cache::{CheckCache, DEFAULT_CACHE_DIR};
+use changed::ChangedFiles;
 pub use cmd_args::*;
-use output::output_result;
-use std::{error::Error, sync::Arc};
+use fix::Fixer;
+use lsp_types::Diagnostic;
+use output::{OutputOptions, output_result};
+use std::{
+    error::Error,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
 use tokio_util::sync::CancellationToken;
 
 use crate::init::setup_logger;
+use emmylua_code_analysis::{EmmyLuaAnalysis, FileId};
 
 pub async fn run_check(cmd_args: CmdArgs) -> Result<(), Box<dyn Error + Sync + Send>> {
     setup_logger(cmd_args.verbose);
@@ -16,10 +36,10 @@ pub async fn run_check(cmd_args: CmdArgs) -> Result<(), Box<dyn Error + Sync + S
     let cwd = std::env::current_dir()?;
     let workspaces: Vec<_> = cmd_args
         .workspace
-        .into_iter()
+        .iter()
         .map(|workspace| {
             if workspace.is_absolute() {
-                workspace
+                workspace.clone()
             } else {
                 cwd.join(workspace)
             }
@@ -33,8 +53,9 @@ pub async fn run_check(cmd_args: CmdArgs) -> Result<(), Box<dyn Error + Sync + S
     let analysis = match init::load_workspace(
         main_path.clone(),
         workspaces.clone(),
-        cmd_args.config,
-        cmd_args.ignore,
+        cmd_args.config.clone(),
+        cmd_args.ignore.clone(),
+        &cmd_args.diagnostics,
     ) {
         Some(analysis) => analysis,
         None => {
@@ -43,32 +64,157 @@ pub async fn run_check(cmd_args: CmdArgs) -> Result<(), Box<dyn Error + Sync + S
     };
 
     let db = analysis.compilation.get_db();
-    let need_check_files = db.get_module_index().get_main_workspace_file_ids();
+    let mut need_check_files = db.get_module_index().get_main_workspace_file_ids();
+
+    let changed_files = match &cmd_args.changed_since {
+        Some(rev) => {
+            let changed_files = ChangedFiles::load(main_path.clone(), rev)?;
+            need_check_files.retain(|file_id| changed_files.contains(db, *file_id));
+            Some(Arc::new(changed_files))
+        }
+        None => None,
+    };
+    let changed_lines = if cmd_args.changed_lines_only {
+        changed_files
+    } else {
+        None
+    };
+
+    let cache = if cmd_args.no_cache {
+        None
+    } else {
+        let cache_dir = cmd_args
+            .cache_dir
+            .clone()
+            .unwrap_or_else(|| main_path.join(DEFAULT_CACHE_DIR));
+        Some(Arc::new(CheckCache::load(
+            &cache_dir,
+            main_path.clone(),
+            db,
+            &analysis.emmyrc,
+        )))
+    };
+
+    // check the files in path order, so the output order does not depend on timing
+    let vfs = db.get_vfs();
+    need_check_files.sort_by(|a, b| vfs.get_file_path(a).cmp(&vfs.get_file_path(b)));
+    let need_check_files = Arc::new(need_check_files);
+    let jobs = cmd_args
+        .jobs
+        .filter(|jobs| *jobs > 0)
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .min(need_check_files.len().max(1));
 
     let (sender, receiver) = tokio::sync::mpsc::channel(100);
     let analysis = Arc::new(analysis);
     let db = analysis.compilation.get_db();
-    for file_id in need_check_files.clone() {
+    let next_index = Arc::new(AtomicUsize::new(0));
+    let mut workers = Vec::new();
+    for _ in 0..jobs {
         let sender = sender.clone();
         let analysis = analysis.clone();
-        tokio::spawn(async move {
-            let cancel_token = CancellationToken::new();
-            let diagnostics = analysis.diagnose_file(file_id, cancel_token);
-            sender.send((file_id, diagnostics)).await.unwrap();
-        });
+        let cache = cache.clone();
+        let changed_lines = changed_lines.clone();
+        let need_check_files = need_check_files.clone();
+        let next_index = next_index.clone();
+        workers.push(tokio::task::spawn_blocking(move || {
+            loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let Some(file_id) = need_check_files.get(index).copied() else {
+                    break;
+                };
+                let diagnostics = check_file(
+                    &analysis,
+                    cache.as_deref(),
+                    changed_lines.as_deref(),
+                    file_id,
+                );
+                if sender.blocking_send((index, file_id, diagnostics)).is_err() {
+                    break;
+                }
+            }
+        }));
     }
+    // the receiver stops once every worker is done, even when no file needs a check
+    drop(sender);
+
+    let mut baseline = match (&cmd_args.baseline, &cmd_args.write_baseline) {
+        (Some(path), _) => Some(Baseline::load(path, main_path.clone())?),
+        (None, Some(_)) => Some(Baseline::new(main_path.clone())),
+        (None, None) => None,
+    };
+
+    let mut fixer = if cmd_args.fix || cmd_args.fix_dry_run {
+        Some(Fixer::new(main_path.clone(), cmd_args.fix_dry_run))
+    } else {
+        None
+    };
 
     let exit_code = output_result(
         need_check_files.len(),
         db,
-        main_path,
         receiver,
-        cmd_args.output_format,
-        cmd_args.output,
-        cmd_args.warnings_as_errors,
+        OutputOptions {
+            workspace: main_path.clone(),
+            output_format: cmd_args.output_format.clone(),
+            output: cmd_args.output.clone(),
+            warnings_as_errors: cmd_args.warnings_as_errors,
+            min_severity: cmd_args.diagnostics.min_severity.map(Into::into),
+            baseline: baseline.as_mut(),
+            fixer: fixer.as_mut(),
+        },
     )
     .await;
 
+    if let Some(fixer) = &fixer {
+        let count = fixer.apply(db, &mut std::io::stdout())?;
+        if cmd_args.fix_dry_run {
+            eprintln!("{} fixes can be applied with --fix", count);
+        } else {
+            eprintln!("{} fixes applied", count);
+        }
+    }
+
+    if let Err(err) = cache.as_ref().map_or(Ok(()), |cache| cache.save()) {
+        log::warn!("Failed to save the check cache: {}", err);
+    }
+
+    if let Some(baseline) = &baseline {
+        let stale_entries = baseline.get_stale_entries();
+        if !stale_entries.is_empty() {
+            eprintln!(
+                "{} baseline entries no longer match any diagnostic:",
+                stale_entries.len()
+            );
+            for entry in &stale_entries {
+                eprintln!(
+                    "  {} [{}] {} (x{})",
+                    entry.file, entry.code, entry.fingerprint, entry.count
+                );
+            }
+        }
+    }
+
+    if let (Some(baseline), Some(path)) = (&baseline, &cmd_args.write_baseline) {
+        let count = baseline.write(path)?;
+        eprintln!(
+            "Baseline with {} diagnostics written to \"{}\"",
+            count,
+            path.display()
+        );
+        return Ok(());
+    }
+
+    if cmd_args.watch {
+        for worker in workers {
+            worker.await?;
+        }
+        let analysis = Arc::try_unwrap(analysis)
+            .map_err(|_| "The analysis is still used by a check worker")?;
+        return watch::watch_workspace(analysis, main_path, workspaces, &cmd_args).await;
+    }
+
     if exit_code != 0 {
         return Err(format!("exit code: {}", exit_code).into());
     }
@@ -76,3 +222,32 @@ pub async fn run_check(cmd_args: CmdArgs) -> Result<(), Box<dyn Error + Sync + S
     eprintln!("Check finished");
     Ok(())
 }
+
+/// Diagnoses a file, or takes its diagnostics from the cache when nothing it depends on
+/// changed
+fn check_file(
+    analysis: &EmmyLuaAnalysis,
+    cache: Option<&CheckCache>,
+    changed_lines: Option<&ChangedFiles>,
+    file_id: FileId,
+) -> Option<Vec<Diagnostic>> {
+    let db = analysis.compilation.get_db();
+    let diagnostics = match cache {
+        Some(cache) => match cache.get(db, file_id) {
+            Some(diagnostics) => diagnostics,
+            None => {
+                let diagnostics = analysis.diagnose_file(file_id, CancellationToken::new());
+                cache.insert(db, file_id, diagnostics.clone());
+                diagnostics
+            }
+        },
+        None => analysis.diagnose_file(file_id, CancellationToken::new()),
+    };
+
+    match changed_lines {
+        Some(changed_lines) => {
+            diagnostics.map(|diagnostics| changed_lines.filter_lines(db, file_id, diagnostics))
+        }
+        None => diagnostics,
+    }
+}
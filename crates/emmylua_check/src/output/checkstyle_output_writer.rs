@@ -0,0 +1,120 @@
+use std::{fs::File, io::Write, path::PathBuf};
+
+use emmylua_code_analysis::{DbIndex, FileId};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+use crate::cmd_args::OutputDestination;
+
+use super::{OutputWriter, create_output_file, escape_xml, get_relative_path};
+
+/// Writes a Checkstyle XML report with one `<file>` element per file that has diagnostics,
+/// named by its path relative to the workspace like in the other reports
+#[derive(Debug)]
+pub struct CheckstyleOutputWriter {
+    workspace: PathBuf,
+    output: Option<File>,
+    files: Vec<String>,
+}
+
+impl CheckstyleOutputWriter {
+    pub fn new(workspace: PathBuf, output: OutputDestination) -> Self {
+        CheckstyleOutputWriter {
+            workspace,
+            output: create_output_file(output),
+            files: Vec::new(),
+        }
+    }
+
+    fn build_report(&self) -> String {
+        let mut report = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        report.push_str("<checkstyle version=\"4.3\">\n");
+        for file in &self.files {
+            report.push_str(file);
+        }
+        report.push_str("</checkstyle>\n");
+        report
+    }
+}
+
+impl OutputWriter for CheckstyleOutputWriter {
+    fn write(&mut self, db: &DbIndex, file_id: FileId, diagnostics: Vec<Diagnostic>) {
+        if diagnostics.is_empty() {
+            return;
+        }
+
+        let relative_path = get_relative_path(db, &self.workspace, file_id);
+        let mut file = format!("  <file name=\"{}\">\n", escape_xml(&relative_path));
+        for diagnostic in &diagnostics {
+            let source = match &diagnostic.code {
+                Some(NumberOrString::String(code)) => format!("emmylua.{}", code),
+                Some(NumberOrString::Number(code)) => format!("emmylua.{}", code),
+                None => "emmylua".to_string(),
+            };
+            file.push_str(&format!(
+                "    <error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\" source=\"{}\"/>\n",
+                diagnostic.range.start.line + 1,
+                diagnostic.range.start.character + 1,
+                get_checkstyle_severity(diagnostic.severity),
+                escape_xml(&diagnostic.message),
+                escape_xml(&source)
+            ));
+        }
+        file.push_str("  </file>\n");
+        self.files.push(file);
+    }
+
+    fn finish(&mut self) {
+        let report = self.build_report();
+        if let Some(output) = self.output.as_mut() {
+            output.write_all(report.as_bytes()).unwrap();
+        } else {
+            print!("{}", report);
+        }
+    }
+}
+
+fn get_checkstyle_severity(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        _ => "info",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+
+    use super::CheckstyleOutputWriter;
+    use crate::{cmd_args::OutputDestination, output::OutputWriter, test_util::TestWorkspace};
+
+    #[test]
+    fn test_report() {
+        let mut ws = TestWorkspace::new("checkstyle");
+        let a = ws.add_file("src/a.lua", b"return 1\n");
+        let b = ws.add_file("b.lua", b"return 1\n");
+        let db = ws.analysis.compilation.get_db();
+        let mut writer = CheckstyleOutputWriter::new(ws.dir.clone(), OutputDestination::Stdout);
+        writer.write(
+            db,
+            a,
+            vec![Diagnostic {
+                range: Range::new(Position::new(2, 4), Position::new(2, 5)),
+                severity: Some(DiagnosticSeverity::HINT),
+                code: Some(NumberOrString::String("unused".to_string())),
+                message: "\"x\"\u{7}".to_string(),
+                ..Default::default()
+            }],
+        );
+        writer.write(db, b, Vec::new());
+
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<checkstyle version="4.3">
+  <file name="src/a.lua">
+    <error line="3" column="5" severity="info" message="&quot;x&quot;" source="emmylua.unused"/>
+  </file>
+</checkstyle>
+"#;
+        assert_eq!(writer.build_report(), expected);
+    }
+}
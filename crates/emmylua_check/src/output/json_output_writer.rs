@@ -6,7 +6,7 @@ use serde_json::{Value, json};
 
 use crate::cmd_args::OutputDestination;
 
-use super::OutputWriter;
+use super::{OutputWriter, create_output_file};
 
 #[derive(Debug)]
 pub struct JsonOutputWriter {
@@ -17,18 +17,7 @@ pub struct JsonOutputWriter {
 
 impl JsonOutputWriter {
     pub fn new(output: OutputDestination) -> Self {
-        let output = match output {
-            OutputDestination::Stdout => None,
-            OutputDestination::File(path) => {
-                if let Some(parent) = path.parent() {
-                    if !parent.exists() {
-                        std::fs::create_dir_all(parent).unwrap();
-                    }
-                }
-
-                Some(std::fs::File::create(path).unwrap())
-            }
-        };
+        let output = create_output_file(output);
         JsonOutputWriter {
             output,
             first_write: true,
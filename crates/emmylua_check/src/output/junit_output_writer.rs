@@ -0,0 +1,173 @@
+use std::{fs::File, io::Write, path::PathBuf};
+
+use emmylua_code_analysis::{DbIndex, FileId};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+use crate::cmd_args::OutputDestination;
+
+use super::{OutputWriter, create_output_file, escape_xml, get_relative_path, get_severity_name};
+
+/// Writes a JUnit XML report with one test suite per file. Every error and warning is a
+/// failed test case, information and hints are passing test cases with the message as
+/// output, and files without diagnostics get a single passing test case
+#[derive(Debug)]
+pub struct JunitOutputWriter {
+    workspace: PathBuf,
+    output: Option<File>,
+    test_suites: Vec<String>,
+    test_count: usize,
+    failure_count: usize,
+}
+
+impl JunitOutputWriter {
+    pub fn new(workspace: PathBuf, output: OutputDestination) -> Self {
+        JunitOutputWriter {
+            workspace,
+            output: create_output_file(output),
+            test_suites: Vec::new(),
+            test_count: 0,
+            failure_count: 0,
+        }
+    }
+
+    fn build_report(&self) -> String {
+        let mut report = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        report.push_str(&format!(
+            "<testsuites name=\"emmylua_check\" tests=\"{}\" failures=\"{}\" errors=\"0\">\n",
+            self.test_count, self.failure_count
+        ));
+        for test_suite in &self.test_suites {
+            report.push_str(test_suite);
+        }
+        report.push_str("</testsuites>\n");
+        report
+    }
+}
+
+impl OutputWriter for JunitOutputWriter {
+    fn write(&mut self, db: &DbIndex, file_id: FileId, diagnostics: Vec<Diagnostic>) {
+        let relative_path = get_relative_path(db, &self.workspace, file_id);
+        let file_name = escape_xml(&relative_path);
+        let mut test_cases = String::new();
+        for diagnostic in &diagnostics {
+            let line = diagnostic.range.start.line + 1;
+            let column = diagnostic.range.start.character + 1;
+            let code = match &diagnostic.code {
+                Some(NumberOrString::String(code)) => code.clone(),
+                Some(NumberOrString::Number(code)) => code.to_string(),
+                None => "diagnostic".to_string(),
+            };
+            let severity = get_severity_name(diagnostic.severity);
+            test_cases.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}:{}:{} {}\">\n",
+                file_name, file_name, line, column, code
+            ));
+            let report = format!(
+                "{}:{}:{} [{}] {}",
+                file_name,
+                line,
+                column,
+                severity,
+                escape_xml(&diagnostic.message)
+            );
+            if is_failure(diagnostic) {
+                test_cases.push_str(&format!(
+                    "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                    escape_xml(&code),
+                    escape_xml(&diagnostic.message),
+                    report
+                ));
+            } else {
+                test_cases.push_str(&format!("      <system-out>{}</system-out>\n", report));
+            }
+            test_cases.push_str("    </testcase>\n");
+        }
+
+        let failures = diagnostics
+            .iter()
+            .filter(|diagnostic| is_failure(diagnostic))
+            .count();
+        let tests = diagnostics.len().max(1);
+        if diagnostics.is_empty() {
+            test_cases.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\"/>\n",
+                file_name, file_name
+            ));
+        }
+        self.test_count += tests;
+        self.failure_count += failures;
+        self.test_suites.push(format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\">\n{}  </testsuite>\n",
+            file_name, tests, failures, test_cases
+        ));
+    }
+
+    fn finish(&mut self) {
+        let report = self.build_report();
+        if let Some(output) = self.output.as_mut() {
+            output.write_all(report.as_bytes()).unwrap();
+        } else {
+            print!("{}", report);
+        }
+    }
+}
+
+fn is_failure(diagnostic: &Diagnostic) -> bool {
+    matches!(
+        diagnostic.severity,
+        Some(DiagnosticSeverity::ERROR) | Some(DiagnosticSeverity::WARNING)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+
+    use super::JunitOutputWriter;
+    use crate::{cmd_args::OutputDestination, output::OutputWriter, test_util::TestWorkspace};
+
+    fn make_diagnostic(line: u32, severity: DiagnosticSeverity, message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, 2), Position::new(line, 3)),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("unused".to_string())),
+            message: message.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let mut ws = TestWorkspace::new("junit");
+        let a = ws.add_file("src/a.lua", b"return 1\n");
+        let b = ws.add_file("b.lua", b"return 1\n");
+        let db = ws.analysis.compilation.get_db();
+        let mut writer = JunitOutputWriter::new(ws.dir.clone(), OutputDestination::Stdout);
+        writer.write(
+            db,
+            a,
+            vec![
+                make_diagnostic(0, DiagnosticSeverity::ERROR, "a < b"),
+                make_diagnostic(1, DiagnosticSeverity::HINT, "hint"),
+            ],
+        );
+        writer.write(db, b, Vec::new());
+
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="emmylua_check" tests="3" failures="1" errors="0">
+  <testsuite name="src/a.lua" tests="2" failures="1" errors="0">
+    <testcase classname="src/a.lua" name="src/a.lua:1:3 unused">
+      <failure type="unused" message="a &lt; b">src/a.lua:1:3 [error] a &lt; b</failure>
+    </testcase>
+    <testcase classname="src/a.lua" name="src/a.lua:2:3 unused">
+      <system-out>src/a.lua:2:3 [hint] hint</system-out>
+    </testcase>
+  </testsuite>
+  <testsuite name="b.lua" tests="1" failures="0" errors="0">
+    <testcase classname="b.lua" name="b.lua"/>
+  </testsuite>
+</testsuites>
+"#;
+        assert_eq!(writer.build_report(), expected);
+    }
+}
@@ -1,27 +1,64 @@
+mod checkstyle_output_writer;
 mod json_output_writer;
+mod junit_output_writer;
+mod sarif_output_writer;
 mod text_output_writer;
 
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
 
 use emmylua_code_analysis::{DbIndex, FileId};
-use lsp_types::Diagnostic;
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
 use tokio::sync::mpsc::Receiver;
 
+use crate::baseline::Baseline;
 use crate::cmd_args::{OutputDestination, OutputFormat};
+use crate::fix::Fixer;
 
 use crate::terminal_display::TerminalDisplay;
 
+/// How the results of a check are filtered and reported
+pub struct OutputOptions<'a> {
+    pub workspace: PathBuf,
+    pub output_format: OutputFormat,
+    pub output: OutputDestination,
+    pub warnings_as_errors: bool,
+    pub min_severity: Option<DiagnosticSeverity>,
+    pub baseline: Option<&'a mut Baseline>,
+    pub fixer: Option<&'a mut Fixer>,
+}
+
 pub async fn output_result(
     total_count: usize,
     db: &DbIndex,
-    workspace: PathBuf,
-    mut receiver: Receiver<(FileId, Option<Vec<Diagnostic>>)>,
-    output_format: OutputFormat,
-    output: OutputDestination,
-    warnings_as_errors: bool,
+    mut receiver: Receiver<(usize, FileId, Option<Vec<Diagnostic>>)>,
+    options: OutputOptions<'_>,
 ) -> i32 {
+    let OutputOptions {
+        workspace,
+        output_format,
+        output,
+        warnings_as_errors,
+        min_severity,
+        mut baseline,
+        mut fixer,
+    } = options;
     let mut writer: Box<dyn OutputWriter> = match output_format {
         OutputFormat::Json => Box::new(json_output_writer::JsonOutputWriter::new(output)),
+        OutputFormat::Sarif => Box::new(sarif_output_writer::SarifOutputWriter::new(
+            workspace.clone(),
+            output,
+        )),
+        OutputFormat::Junit => Box::new(junit_output_writer::JunitOutputWriter::new(
+            workspace.clone(),
+            output,
+        )),
+        OutputFormat::Checkstyle => Box::new(
+            checkstyle_output_writer::CheckstyleOutputWriter::new(workspace.clone(), output),
+        ),
         OutputFormat::Text => {
             Box::new(text_output_writer::TextOutputWriter::new(workspace.clone()))
         }
@@ -35,35 +72,59 @@ pub async fn output_result(
     let mut info_count = 0;
     let mut hint_count = 0;
 
-    while let Some((file_id, diagnostics)) = receiver.recv().await {
-        count += 1;
-        if let Some(diagnostics) = diagnostics {
-            for diagnostic in &diagnostics {
-                match diagnostic.severity {
-                    Some(lsp_types::DiagnosticSeverity::ERROR) => {
-                        has_error = true;
-                        error_count += 1;
-                    }
-                    Some(lsp_types::DiagnosticSeverity::WARNING) => {
-                        if warnings_as_errors {
+    // results arrive in any order, but are written in the order of their index
+    let mut pending = BTreeMap::new();
+    while count < total_count {
+        let Some((index, file_id, diagnostics)) = receiver.recv().await else {
+            break;
+        };
+        pending.insert(index, (file_id, diagnostics));
+        while let Some((file_id, diagnostics)) = pending.remove(&count) {
+            count += 1;
+            if let Some(mut diagnostics) = diagnostics {
+                // checkers may report in hash order, keep the output stable between runs
+                diagnostics.sort_by(|a, b| {
+                    let a_key = (a.range.start, a.range.end, get_code(a), &a.message);
+                    let b_key = (b.range.start, b.range.end, get_code(b), &b.message);
+                    a_key.cmp(&b_key)
+                });
+                if let Some(min_severity) = min_severity {
+                    // lower values are more severe
+                    diagnostics.retain(|diagnostic| {
+                        diagnostic
+                            .severity
+                            .is_none_or(|severity| severity <= min_severity)
+                    });
+                }
+                if let Some(fixer) = fixer.as_mut() {
+                    diagnostics = fixer.filter(db, file_id, diagnostics);
+                }
+                if let Some(baseline) = baseline.as_mut() {
+                    diagnostics = baseline.filter(db, file_id, diagnostics);
+                }
+                for diagnostic in &diagnostics {
+                    match diagnostic.severity {
+                        Some(lsp_types::DiagnosticSeverity::ERROR) => {
                             has_error = true;
+                            error_count += 1;
                         }
-                        warning_count += 1;
-                    }
-                    Some(lsp_types::DiagnosticSeverity::INFORMATION) => {
-                        info_count += 1;
-                    }
-                    Some(lsp_types::DiagnosticSeverity::HINT) => {
-                        hint_count += 1;
+                        Some(lsp_types::DiagnosticSeverity::WARNING) => {
+                            if warnings_as_errors {
+                                has_error = true;
+                            }
+                            warning_count += 1;
+                        }
+                        Some(lsp_types::DiagnosticSeverity::INFORMATION) => {
+                            info_count += 1;
+                        }
+                        Some(lsp_types::DiagnosticSeverity::HINT) => {
+                            hint_count += 1;
+                        }
+                        _ => {}
                     }
-                    _ => {}
                 }
+                writer.write(db, file_id, diagnostics);
             }
-            writer.write(db, file_id, diagnostics);
-        }
-
-        if count == total_count {
-            break;
         }
     }
 
@@ -82,3 +143,220 @@ trait OutputWriter {
 
     fn finish(&mut self);
 }
+
+/// Opens the file to write a report to, or `None` when the report goes to stdout
+fn create_output_file(output: OutputDestination) -> Option<File> {
+    match output {
+        OutputDestination::Stdout => None,
+        OutputDestination::File(path) => {
+            if let Some(parent) = path.parent().filter(|parent| !parent.exists()) {
+                std::fs::create_dir_all(parent).unwrap();
+            }
+
+            Some(File::create(path).unwrap())
+        }
+    }
+}
+
+/// The path of a file relative to the workspace, with `/` separators
+pub(crate) fn get_relative_path(db: &DbIndex, workspace: &Path, file_id: FileId) -> String {
+    let file_path = db.get_vfs().get_file_path(&file_id).unwrap();
+    file_path
+        .strip_prefix(workspace)
+        .unwrap_or(file_path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn get_code(diagnostic: &Diagnostic) -> Option<String> {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => Some(code.clone()),
+        Some(NumberOrString::Number(code)) => Some(code.to_string()),
+        None => None,
+    }
+}
+
+fn get_severity_name(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "unknown",
+    }
+}
+
+/// Escapes the text for XML attributes and elements. Control characters that XML 1.0 does
+/// not allow at all are dropped.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            '\t' => escaped.push_str("&#9;"),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use emmylua_code_analysis::FileId;
+    use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+
+    use super::{OutputOptions, escape_xml, output_result};
+    use crate::{
+        cmd_args::{OutputDestination, OutputFormat},
+        test_util::TestWorkspace,
+    };
+
+    fn make_diagnostic(line: u32, severity: DiagnosticSeverity) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, 1)),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("unused".to_string())),
+            message: format!("line {}", line),
+            ..Default::default()
+        }
+    }
+
+    /// Reports the results as JSON and returns the exit code and the report
+    async fn report(
+        ws: &TestWorkspace,
+        results: Vec<(usize, FileId, Option<Vec<Diagnostic>>)>,
+        warnings_as_errors: bool,
+        min_severity: Option<DiagnosticSeverity>,
+    ) -> (i32, serde_json::Value) {
+        let (sender, receiver) = tokio::sync::mpsc::channel(results.len().max(1));
+        let total_count = results.len();
+        for result in results {
+            sender.send(result).await.unwrap();
+        }
+        drop(sender);
+
+        let path = ws.dir.join("report.json");
+        let exit_code = output_result(
+            total_count,
+            ws.analysis.compilation.get_db(),
+            receiver,
+            OutputOptions {
+                workspace: ws.dir.clone(),
+                output_format: OutputFormat::Json,
+                output: OutputDestination::File(path.clone()),
+                warnings_as_errors,
+                min_severity,
+                baseline: None,
+                fixer: None,
+            },
+        )
+        .await;
+        let report = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        (exit_code, report)
+    }
+
+    #[tokio::test]
+    async fn test_min_severity() {
+        let mut ws = TestWorkspace::new("output_min_severity");
+        let file_id = ws.add_file("a.lua", b"return 1\n");
+        let diagnostics = vec![
+            make_diagnostic(0, DiagnosticSeverity::HINT),
+            make_diagnostic(1, DiagnosticSeverity::WARNING),
+            make_diagnostic(2, DiagnosticSeverity::ERROR),
+        ];
+
+        let (exit_code, report) = report(
+            &ws,
+            vec![(0, file_id, Some(diagnostics))],
+            false,
+            Some(DiagnosticSeverity::WARNING),
+        )
+        .await;
+        assert_eq!(exit_code, 1);
+        let messages = report[0]["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|diagnostic| diagnostic["message"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["line 1", "line 2"]);
+    }
+
+    #[tokio::test]
+    async fn test_warnings_as_errors() {
+        let mut ws = TestWorkspace::new("output_warnings_as_errors");
+        let file_id = ws.add_file("a.lua", b"return 1\n");
+        let diagnostics = vec![make_diagnostic(0, DiagnosticSeverity::WARNING)];
+
+        let (exit_code, _) = report(
+            &ws,
+            vec![(0, file_id, Some(diagnostics.clone()))],
+            false,
+            None,
+        )
+        .await;
+        assert_eq!(exit_code, 0);
+        let (exit_code, _) = report(&ws, vec![(0, file_id, Some(diagnostics))], true, None).await;
+        assert_eq!(exit_code, 1);
+    }
+
+    #[tokio::test]
+    async fn test_report_in_index_order() {
+        let mut ws = TestWorkspace::new("output_order");
+        let a = ws.add_file("a.lua", b"return 1\n");
+        let b = ws.add_file("b.lua", b"return 1\n");
+        let c = ws.add_file("c.lua", b"return 1\n");
+
+        // the workers finish in any order
+        let (_, report) = report(
+            &ws,
+            vec![
+                (2, c, Some(Vec::new())),
+                (
+                    0,
+                    a,
+                    Some(vec![make_diagnostic(0, DiagnosticSeverity::ERROR)]),
+                ),
+                (1, b, None),
+            ],
+            false,
+            None,
+        )
+        .await;
+        let files = report
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|file| file["file"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        let vfs = ws.analysis.compilation.get_db().get_vfs();
+        let expected = [a, c]
+            .iter()
+            .map(|file_id| {
+                vfs.get_file_path(file_id)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(files, expected);
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml("a < b && c > \"d\" 'e'"),
+            "a &lt; b &amp;&amp; c &gt; &quot;d&quot; &apos;e&apos;"
+        );
+        assert_eq!(escape_xml("a\nb\r\tc"), "a&#10;b&#13;&#9;c");
+        assert_eq!(escape_xml("a\u{0}b\u{1b}[0mc\u{ffff}"), "ab[0mc");
+        assert_eq!(escape_xml("名前"), "名前");
+    }
+}
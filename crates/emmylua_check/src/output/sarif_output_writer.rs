@@ -0,0 +1,202 @@
+use std::{fs::File, io::Write, path::PathBuf};
+
+use emmylua_code_analysis::{
+    DbIndex, DiagnosticCode, FileId, file_path_to_uri, get_default_severity,
+};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+use serde_json::{Value, json};
+
+use crate::cmd_args::OutputDestination;
+
+use super::{OutputWriter, create_output_file, get_relative_path};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SRC_ROOT: &str = "%SRCROOT%";
+
+/// Writes a SARIF 2.1.0 log with one run, every `DiagnosticCode` is a rule of the tool
+#[derive(Debug)]
+pub struct SarifOutputWriter {
+    workspace: PathBuf,
+    output: Option<File>,
+    rules: Vec<DiagnosticCode>,
+    results: Vec<Value>,
+}
+
+impl SarifOutputWriter {
+    pub fn new(workspace: PathBuf, output: OutputDestination) -> Self {
+        SarifOutputWriter {
+            workspace,
+            output: create_output_file(output),
+            rules: DiagnosticCode::all()
+                .into_iter()
+                .filter(|code| *code != DiagnosticCode::None)
+                .collect(),
+            results: Vec::new(),
+        }
+    }
+
+    fn build_rule(code: DiagnosticCode) -> Value {
+        let description = match code.get_description() {
+            "" => code.get_name(),
+            description => description,
+        };
+        json!({
+            "id": code.get_name(),
+            "name": code.get_name(),
+            "shortDescription": { "text": description },
+            "defaultConfiguration": {
+                "level": get_level(Some(get_default_severity(code))),
+            },
+        })
+    }
+
+    fn build_log(&self) -> Value {
+        let rules = self
+            .rules
+            .iter()
+            .map(|code| Self::build_rule(*code))
+            .collect::<Vec<_>>();
+        let mut workspace_uri = file_path_to_uri(&self.workspace)
+            .map(|uri| uri.as_str().to_string())
+            .unwrap_or_default();
+        if !workspace_uri.ends_with('/') {
+            workspace_uri.push('/');
+        }
+
+        json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "emmylua_check",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules,
+                    }
+                },
+                "originalUriBaseIds": {
+                    SRC_ROOT: { "uri": workspace_uri },
+                },
+                "results": self.results,
+            }]
+        })
+    }
+}
+
+impl OutputWriter for SarifOutputWriter {
+    fn write(&mut self, db: &DbIndex, file_id: FileId, diagnostics: Vec<Diagnostic>) {
+        let relative_path = get_relative_path(db, &self.workspace, file_id);
+        for diagnostic in diagnostics {
+            let range = diagnostic.range;
+            let mut result = json!({
+                "level": get_level(diagnostic.severity),
+                "message": { "text": diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": relative_path,
+                            "uriBaseId": SRC_ROOT,
+                        },
+                        "region": {
+                            "startLine": range.start.line + 1,
+                            "startColumn": range.start.character + 1,
+                            "endLine": range.end.line + 1,
+                            "endColumn": range.end.character + 1,
+                        }
+                    }
+                }],
+            });
+
+            if let Some(NumberOrString::String(code)) = &diagnostic.code {
+                result["ruleId"] = json!(code);
+                let rule_index = self.rules.iter().position(|rule| rule.get_name() == code);
+                if let Some(rule_index) = rule_index {
+                    result["ruleIndex"] = json!(rule_index);
+                }
+            }
+            self.results.push(result);
+        }
+    }
+
+    fn finish(&mut self) {
+        let pretty_json = serde_json::to_string_pretty(&self.build_log()).unwrap();
+        if let Some(output) = self.output.as_mut() {
+            output.write_all(pretty_json.as_bytes()).unwrap();
+        } else {
+            println!("{}", pretty_json);
+        }
+    }
+}
+
+fn get_level(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) | Some(DiagnosticSeverity::HINT) => "note",
+        _ => "warning",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use emmylua_code_analysis::DiagnosticCode;
+    use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+
+    use super::{SRC_ROOT, SarifOutputWriter};
+    use crate::{cmd_args::OutputDestination, output::OutputWriter, test_util::TestWorkspace};
+
+    #[test]
+    fn test_log() {
+        let mut ws = TestWorkspace::new("sarif");
+        let file_id = ws.add_file("src/a.lua", b"local a = 1\n");
+        let db = ws.analysis.compilation.get_db();
+        let mut writer = SarifOutputWriter::new(ws.dir.clone(), OutputDestination::Stdout);
+        writer.write(
+            db,
+            file_id,
+            vec![
+                Diagnostic {
+                    range: Range::new(Position::new(0, 6), Position::new(0, 7)),
+                    severity: Some(DiagnosticSeverity::HINT),
+                    code: Some(NumberOrString::String("unused".to_string())),
+                    message: "a is never used".to_string(),
+                    ..Default::default()
+                },
+                Diagnostic {
+                    range: Range::new(Position::new(0, 0), Position::new(0, 1)),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    message: "no code".to_string(),
+                    ..Default::default()
+                },
+            ],
+        );
+
+        let log = writer.build_log();
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        let base_uri = run["originalUriBaseIds"][SRC_ROOT]["uri"].as_str().unwrap();
+        assert!(base_uri.starts_with("file://") && base_uri.ends_with('/'));
+
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert!(rules.iter().all(|rule| rule["id"] != "none"));
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        let unused = &results[0];
+        assert_eq!(unused["level"], "note");
+        assert_eq!(unused["ruleId"], "unused");
+        let rule_index = unused["ruleIndex"].as_u64().unwrap() as usize;
+        assert_eq!(rules[rule_index]["id"], DiagnosticCode::Unused.get_name());
+        let location = &unused["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/a.lua");
+        assert_eq!(location["artifactLocation"]["uriBaseId"], SRC_ROOT);
+        assert_eq!(location["region"]["startLine"], 1);
+        assert_eq!(location["region"]["startColumn"], 7);
+        assert_eq!(location["region"]["endColumn"], 8);
+
+        let no_code = &results[1];
+        assert_eq!(no_code["level"], "error");
+        assert!(no_code.get("ruleId").is_none());
+    }
+}
@@ -0,0 +1,31 @@
+/// 64-bit FNV-1a. Unlike `DefaultHasher` its output does not change between Rust versions,
+/// so it can be written to files that outlive the process.
+#[derive(Debug, Clone)]
+pub struct StableHasher {
+    hash: u64,
+}
+
+impl StableHasher {
+    pub fn new() -> Self {
+        Self {
+            hash: 0xcbf29ce484222325,
+        }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    /// Writes the text followed by a separator, so `"ab", "c"` and `"a", "bc"` differ
+    pub fn write_str(&mut self, text: &str) {
+        self.write(text.as_bytes());
+        self.write(&[0xff]);
+    }
+
+    pub fn finish_hex(&self) -> String {
+        format!("{:016x}", self.hash)
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use emmylua_code_analysis::{EmmyLuaAnalysis, FileId};
+use lsp_types::Diagnostic;
+use tokio_util::sync::CancellationToken;
+
+/// A main workspace in a temporary directory, removed when dropped
+pub struct TestWorkspace {
+    pub dir: PathBuf,
+    pub analysis: EmmyLuaAnalysis,
+}
+
+impl TestWorkspace {
+    pub fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("emmylua_check_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut analysis = EmmyLuaAnalysis::new();
+        analysis.add_main_workspace(dir.clone());
+        TestWorkspace { dir, analysis }
+    }
+
+    /// Writes the file to disk and loads it the way the workspace loader does
+    pub fn add_file(&mut self, name: &str, bytes: &[u8]) -> FileId {
+        let path = self.dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, bytes).unwrap();
+        let content = String::from_utf8_lossy(bytes)
+            .trim_start_matches('\u{feff}')
+            .to_string();
+        self.analysis
+            .update_file_by_path(&path, Some(content))
+            .unwrap()
+    }
+
+    pub fn diagnose(&self, file_id: FileId) -> Vec<Diagnostic> {
+        self.analysis
+            .diagnose_file(file_id, CancellationToken::new())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for TestWorkspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use emmylua_code_analysis::{EmmyLuaAnalysis, FileId, file_path_to_uri, read_file_with_encoding};
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    baseline::Baseline,
+    cmd_args::CmdArgs,
+    output::{OutputOptions, output_result},
+};
+
+/// How long to wait for more file events before checking, editors often write a file in
+/// several steps
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Checks the changed files, and the files that require them, every time files in the
+/// workspaces change. Runs until the process is stopped.
+pub async fn watch_workspace(
+    mut analysis: EmmyLuaAnalysis,
+    main_path: PathBuf,
+    workspaces: Vec<PathBuf>,
+    cmd_args: &CmdArgs,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = RecommendedWatcher::new(
+        move |res: notify::Result<Event>| match res {
+            Ok(event) => {
+                let _ = sender.send(event);
+            }
+            Err(err) => log::warn!("File watcher error: {}", err),
+        },
+        Config::default(),
+    )?;
+    for workspace in &workspaces {
+        watcher.watch(workspace, RecursiveMode::Recursive)?;
+    }
+
+    eprintln!("Watching for changes, press Ctrl+C to stop");
+    while let Some(event) = receiver.recv().await {
+        let mut paths = HashSet::new();
+        collect_event_paths(&analysis, event, &mut paths);
+        loop {
+            match tokio::time::timeout(DEBOUNCE_INTERVAL, receiver.recv()).await {
+                Ok(Some(event)) => collect_event_paths(&analysis, event, &mut paths),
+                Ok(None) => return Ok(()),
+                Err(_) => break,
+            }
+        }
+
+        let changed_files = update_files(&mut analysis, &workspaces, paths);
+        if changed_files.is_empty() {
+            continue;
+        }
+
+        let need_check_files = reindex_dependents(&mut analysis, changed_files);
+        if need_check_files.is_empty() {
+            continue;
+        }
+        check_files(&analysis, &main_path, need_check_files, cmd_args).await?;
+    }
+
+    Ok(())
+}
+
+fn collect_event_paths(analysis: &EmmyLuaAnalysis, event: Event, paths: &mut HashSet<PathBuf>) {
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return;
+    }
+
+    let extensions = &analysis.emmyrc.runtime.extensions;
+    for path in event.paths {
+        let file_name = path.to_string_lossy();
+        let is_lua_file = file_name.ends_with(".lua")
+            || extensions.iter().any(|extension| {
+                let extension = extension.trim_start_matches('*');
+                extension.starts_with('.') && file_name.ends_with(extension)
+            });
+        if is_lua_file {
+            paths.insert(path);
+        }
+    }
+}
+
+/// Loads the new text of the changed paths, returns the files whose text was changed or
+/// removed
+fn update_files(
+    analysis: &mut EmmyLuaAnalysis,
+    workspaces: &[PathBuf],
+    paths: HashSet<PathBuf>,
+) -> Vec<FileId> {
+    let encoding = analysis.emmyrc.workspace.encoding.clone();
+    let mut files = Vec::new();
+    let mut removed_files = Vec::new();
+    for path in paths {
+        let Some(uri) = file_path_to_uri(&path) else {
+            continue;
+        };
+        let file_id = analysis.get_file_id(&uri);
+        match read_file_with_encoding(&path, &encoding) {
+            Some(text) => {
+                let vfs = analysis.compilation.get_db().get_vfs();
+                let unchanged = file_id
+                    .and_then(|file_id| vfs.get_file_content(&file_id))
+                    .is_some_and(|content| *content == text);
+                let in_workspace = file_id.is_some() || is_in_workspaces(&path, workspaces);
+                if !unchanged && in_workspace {
+                    files.push((path, Some(text)));
+                }
+            }
+            None => {
+                if let Some(file_id) = file_id {
+                    removed_files.push(file_id);
+                    files.push((path, None));
+                }
+            }
+        }
+    }
+
+    let mut changed_files = analysis.update_files_by_path(files);
+    changed_files.extend(removed_files);
+    changed_files
+}
+
+fn is_in_workspaces(path: &Path, workspaces: &[PathBuf]) -> bool {
+    workspaces
+        .iter()
+        .any(|workspace| path.starts_with(workspace))
+}
+
+/// Analyzes the files that require the changed files again, returns the main workspace
+/// files to check in path order
+fn reindex_dependents(analysis: &mut EmmyLuaAnalysis, changed_files: Vec<FileId>) -> Vec<FileId> {
+    let db = analysis.compilation.get_db();
+    let changed_set = changed_files.iter().copied().collect::<HashSet<_>>();
+    let dependents = db
+        .get_file_dependencies_index()
+        .get_file_dependencies()
+        .collect_file_dependents(changed_files.clone())
+        .into_iter()
+        .filter(|file_id| !changed_set.contains(file_id))
+        .collect::<Vec<_>>();
+    if !dependents.is_empty() {
+        analysis.compilation.remove_index(dependents.clone());
+        analysis.compilation.update_index(dependents.clone());
+    }
+
+    let db = analysis.compilation.get_db();
+    let vfs = db.get_vfs();
+    let module_index = db.get_module_index();
+    let mut need_check_files = changed_files
+        .into_iter()
+        .chain(dependents)
+        .filter(|file_id| vfs.get_file_content(file_id).is_some() && module_index.is_main(file_id))
+        .collect::<Vec<_>>();
+    need_check_files.sort_by(|a, b| vfs.get_file_path(a).cmp(&vfs.get_file_path(b)));
+    need_check_files.dedup();
+    need_check_files
+}
+
+async fn check_files(
+    analysis: &EmmyLuaAnalysis,
+    main_path: &Path,
+    need_check_files: Vec<FileId>,
+    cmd_args: &CmdArgs,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    eprintln!();
+    eprintln!("Checking {} files", need_check_files.len());
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(need_check_files.len().max(1));
+    for (index, file_id) in need_check_files.iter().enumerate() {
+        let diagnostics = analysis.diagnose_file(*file_id, CancellationToken::new());
+        sender.send((index, *file_id, diagnostics)).await?;
+    }
+    drop(sender);
+
+    let mut baseline = match &cmd_args.baseline {
+        Some(path) => Some(Baseline::load(path, main_path.to_path_buf())?),
+        None => None,
+    };
+    output_result(
+        need_check_files.len(),
+        analysis.compilation.get_db(),
+        receiver,
+        OutputOptions {
+            workspace: main_path.to_path_buf(),
+            output_format: cmd_args.output_format.clone(),
+            output: cmd_args.output.clone(),
+            warnings_as_errors: cmd_args.warnings_as_errors,
+            min_severity: cmd_args.diagnostics.min_severity.map(Into::into),
+            baseline: baseline.as_mut(),
+            fixer: None,
+        },
+    )
+    .await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{reindex_dependents, update_files};
+    use crate::test_util::TestWorkspace;
+
+    #[test]
+    fn test_update_files() {
+        let mut ws = TestWorkspace::new("watch_update");
+        let changed = ws.add_file("changed.lua", b"return 1\n");
+        ws.add_file("unchanged.lua", b"return 1\n");
+        let removed = ws.add_file("removed.lua", b"return 1\n");
+        std::fs::write(ws.dir.join("changed.lua"), "return 2\n").unwrap();
+        std::fs::write(ws.dir.join("created.lua"), "return 1\n").unwrap();
+        std::fs::remove_file(ws.dir.join("removed.lua")).unwrap();
+        let outside = std::env::temp_dir().join(format!(
+            "emmylua_check_watch_outside_{}.lua",
+            std::process::id()
+        ));
+        std::fs::write(&outside, "return 1\n").unwrap();
+
+        let paths = ["changed.lua", "unchanged.lua", "removed.lua", "created.lua"]
+            .iter()
+            .map(|name| ws.dir.join(name))
+            .chain([outside.clone()])
+            .collect::<HashSet<_>>();
+        let workspaces = vec![ws.dir.clone()];
+        let changed_files = update_files(&mut ws.analysis, &workspaces, paths)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        std::fs::remove_file(outside).unwrap();
+
+        let vfs = ws.analysis.compilation.get_db().get_vfs();
+        let mut changed_paths = changed_files
+            .iter()
+            .filter_map(|file_id| vfs.get_file_path(file_id))
+            .map(|path| {
+                path.strip_prefix(&ws.dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        changed_paths.sort();
+        assert_eq!(
+            changed_paths,
+            vec!["changed.lua", "created.lua", "removed.lua"]
+        );
+        assert!(changed_files.contains(&changed));
+        assert!(changed_files.contains(&removed));
+        assert!(vfs.get_file_content(&removed).is_none());
+    }
+
+    #[test]
+    fn test_reindex_dependents() {
+        let mut ws = TestWorkspace::new("watch_dependents");
+        let a = ws.add_file("a.lua", b"return { value = 1 }\n");
+        let b = ws.add_file("b.lua", b"local a = require(\"a\")\nreturn a.value\n");
+        ws.add_file("c.lua", b"return 1\n");
+
+        let need_check_files = reindex_dependents(&mut ws.analysis, vec![a]);
+        assert_eq!(need_check_files, vec![a, b]);
+    }
+}
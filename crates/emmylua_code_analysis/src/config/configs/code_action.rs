@@ -8,12 +8,17 @@ pub struct EmmyrcCodeAction {
     #[serde(default = "default_false")]
     #[schemars(extend("x-vscode-setting" = true))]
     pub insert_space: bool,
+    /// Separate library, workspace and unresolved modules when organizing the requires.
+    #[serde(default = "default_false")]
+    #[schemars(extend("x-vscode-setting" = true))]
+    pub group_requires: bool,
 }
 
 impl Default for EmmyrcCodeAction {
     fn default() -> Self {
         Self {
             insert_space: default_false(),
+            group_requires: default_false(),
         }
     }
 }
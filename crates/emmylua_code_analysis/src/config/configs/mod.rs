@@ -19,7 +19,7 @@ mod workspace;
 pub use code_action::EmmyrcCodeAction;
 pub use codelen::EmmyrcCodeLens;
 pub use completion::{EmmyrcCompletion, EmmyrcFilenameConvention};
-pub use diagnostics::EmmyrcDiagnostic;
+pub use diagnostics::{DiagnosticSeveritySetting, EmmyrcDiagnostic};
 pub use doc::{DocSyntax, EmmyrcDoc};
 pub use document_color::EmmyrcDocumentColor;
 pub use hover::EmmyrcHover;
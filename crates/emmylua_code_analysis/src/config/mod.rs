@@ -9,7 +9,9 @@ use std::{
 
 pub use crate::config::configs::{EmmyrcExternalTool, EmmyrcReformat};
 pub use config_loader::{load_configs, load_configs_raw};
-pub use configs::{DocSyntax, EmmyrcFilenameConvention, EmmyrcLuaVersion};
+pub use configs::{
+    DiagnosticSeveritySetting, DocSyntax, EmmyrcFilenameConvention, EmmyrcLuaVersion,
+};
 use configs::{
     EmmyrcCodeAction, EmmyrcCodeLens, EmmyrcCompletion, EmmyrcDiagnostic, EmmyrcDoc,
     EmmyrcDocumentColor, EmmyrcHover, EmmyrcInlayHint, EmmyrcInlineValues, EmmyrcReference,
@@ -93,6 +93,24 @@ impl<'a> FileDependencyRelation<'a> {
         }
         result.into_iter().collect()
     }
+
+    /// Get all files the file requires, directly or through other files
+    pub fn collect_file_dependencies(&self, file_id: FileId) -> Vec<FileId> {
+        let mut result = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(file_id);
+        while let Some(file_id) = queue.pop_front() {
+            if let Some(deps) = self.dependencies.get(&file_id) {
+                for &dep in deps {
+                    if result.insert(dep) {
+                        queue.push_back(dep);
+                    }
+                }
+            }
+        }
+        result.remove(&file_id);
+        result.into_iter().collect()
+    }
 }
 
 #[cfg(test)]
@@ -148,4 +166,21 @@ mod tests {
         result.sort();
         assert_eq!(result, vec![FileId::new(1), FileId::new(2), FileId::new(4)]);
     }
+
+    #[test]
+    fn test_collect_file_dependencies() {
+        let mut deps = HashMap::new();
+        deps.insert(FileId::new(1), [FileId::new(2)].iter().cloned().collect());
+        deps.insert(
+            FileId::new(2),
+            [FileId::new(3), FileId::new(1)].iter().cloned().collect(),
+        );
+        deps.insert(FileId::new(3), HashSet::new());
+        deps.insert(FileId::new(4), [FileId::new(3)].iter().cloned().collect());
+
+        let rel = FileDependencyRelation::new(&deps);
+        let mut result = rel.collect_file_dependencies(FileId::new(1));
+        result.sort();
+        assert_eq!(result, vec![FileId::new(2), FileId::new(3)]);
+    }
 }
@@ -11,50 +11,62 @@ impl Checker for DuplicateRequireChecker {
     const CODES: &[DiagnosticCode] = &[DiagnosticCode::DuplicateRequire];
 
     fn check(context: &mut DiagnosticContext, semantic_model: &SemanticModel) {
-        let root = semantic_model.get_root().clone();
-        let mut require_calls = Vec::new();
-        for call_expr in root.descendants::<LuaCallExpr>() {
-            if call_expr.is_require() {
-                check_require_call_expr(context, semantic_model, call_expr, &mut require_calls);
-            }
+        for (call_expr, _) in get_duplicate_requires(semantic_model) {
+            context.add_diagnostic(
+                DiagnosticCode::DuplicateRequire,
+                call_expr.get_range(),
+                t!("The same file is required multiple times.").to_string(),
+                None,
+            );
         }
     }
 }
 
-fn check_require_call_expr(
-    context: &mut DiagnosticContext,
+/// The `require` calls of a file which was already required in the same or an enclosing
+/// block, each with the first call which required it
+pub fn get_duplicate_requires(semantic_model: &SemanticModel) -> Vec<(LuaCallExpr, LuaCallExpr)> {
+    let root = semantic_model.get_root().clone();
+    let mut require_calls: Vec<(TextRange, String, LuaCallExpr)> = Vec::new();
+    let mut duplicates = Vec::new();
+    for call_expr in root.descendants::<LuaCallExpr>() {
+        if !call_expr.is_require() {
+            continue;
+        }
+        let Some((parent_block, file_name)) = get_required_file(semantic_model, &call_expr) else {
+            continue;
+        };
+
+        let parent_position = parent_block.get_position();
+        let first = require_calls.iter().find(|(range, first_file_name, _)| {
+            range.contains(parent_position) && *first_file_name == file_name
+        });
+        match first {
+            Some((_, _, first)) => duplicates.push((call_expr, first.clone())),
+            None => require_calls.push((parent_block.get_range(), file_name, call_expr)),
+        }
+    }
+
+    duplicates
+}
+
+fn get_required_file(
     semantic_model: &SemanticModel,
-    call_expr: LuaCallExpr,
-    require_calls: &mut Vec<(TextRange, String)>,
-) -> Option<()> {
-    if let Some(_) = call_expr.get_parent::<LuaIndexExpr>() {
-        return Some(());
+    call_expr: &LuaCallExpr,
+) -> Option<(LuaBlock, String)> {
+    if call_expr.get_parent::<LuaIndexExpr>().is_some() {
+        return None;
     }
     let args_list = call_expr.get_args_list()?;
     let arg_expr = args_list.get_args().next()?;
 
     let ty = semantic_model.infer_expr(arg_expr).unwrap_or(LuaType::Any);
-    if let LuaType::StringConst(s) = ty {
-        let parent_block = call_expr
-            .ancestors::<LuaBlock>()
-            .next()
-            .unwrap_or(semantic_model.get_root().get_block()?);
-
-        let parent_position = parent_block.get_position();
-        for (range, file_name) in require_calls.iter() {
-            if range.contains(parent_position) && file_name == s.as_str() {
-                context.add_diagnostic(
-                    DiagnosticCode::DuplicateRequire,
-                    call_expr.get_range(),
-                    t!("The same file is required multiple times.").to_string(),
-                    None,
-                );
-                return Some(());
-            }
-        }
-
-        require_calls.push((parent_block.get_range(), s.as_str().to_string()));
-    }
+    let LuaType::StringConst(s) = ty else {
+        return None;
+    };
+    let parent_block = call_expr
+        .ancestors::<LuaBlock>()
+        .next()
+        .or_else(|| semantic_model.get_root().get_block())?;
 
-    Some(())
+    Some((parent_block, s.as_str().to_string()))
 }
@@ -34,6 +34,9 @@ mod unnecessary_assert;
 mod unnecessary_if;
 mod unused;
 
+pub use duplicate_require::get_duplicate_requires;
+pub use unused::{get_unused_fix, is_decl_used};
+
 use emmylua_parser::{
     LuaAstNode, LuaClosureExpr, LuaComment, LuaReturnStat, LuaStat, LuaSyntaxKind,
 };
@@ -259,7 +262,8 @@ impl<'a> DiagnosticContext<'a> {
     }
 }
 
-fn get_closure_expr_comment(closure_expr: &LuaClosureExpr) -> Option<LuaComment> {
+/// The comment of the statement which defines the closure
+pub fn get_closure_expr_comment(closure_expr: &LuaClosureExpr) -> Option<LuaComment> {
     let comment = closure_expr
         .ancestors::<LuaStat>()
         .next()?
@@ -1,4 +1,7 @@
-use crate::{DiagnosticCode, LuaDecl, LuaReferenceIndex, SemanticModel};
+use emmylua_parser::{LuaAstNode, LuaTokenKind};
+use rowan::TextRange;
+
+use crate::{DbIndex, DiagnosticCode, FileId, LuaDecl, LuaReferenceIndex, SemanticModel};
 
 use super::{Checker, DiagnosticContext};
 
@@ -38,7 +41,8 @@ impl Checker for UnusedChecker {
     }
 }
 
-fn is_decl_used(decl: &LuaDecl, local_refs: &LuaReferenceIndex) -> bool {
+/// Whether a local is read or written anywhere, globals and `...` always count as used
+pub fn is_decl_used(decl: &LuaDecl, local_refs: &LuaReferenceIndex) -> bool {
     if decl.is_global() {
         return true;
     } else if decl.is_param() && decl.get_name() == "..." {
@@ -52,3 +56,32 @@ fn is_decl_used(decl: &LuaDecl, local_refs: &LuaReferenceIndex) -> bool {
 
     false
 }
+
+/// The edit that prefixes the unused local at `range` with an underscore, as the message
+/// suggests. There is none when the file already uses the new name, which the renamed local
+/// could capture.
+pub fn get_unused_fix(
+    db: &DbIndex,
+    file_id: FileId,
+    range: TextRange,
+) -> Option<(TextRange, String)> {
+    let vfs = db.get_vfs();
+    let document = vfs.get_document(&file_id)?;
+    let name = document.get_text_slice(range);
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    let new_name = format!("_{}", name);
+    let root = vfs.get_syntax_tree(&file_id)?.get_chunk_node();
+    let is_taken = root
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .any(|token| token.kind() == LuaTokenKind::TkName.into() && token.text() == new_name);
+    if is_taken {
+        return None;
+    }
+
+    Some((TextRange::empty(range.start()), "_".to_string()))
+}
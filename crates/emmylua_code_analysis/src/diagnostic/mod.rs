@@ -4,5 +4,6 @@ mod lua_diagnostic_code;
 mod lua_diagnostic_config;
 mod test;
 
+pub use checker::{get_closure_expr_comment, get_duplicate_requires, get_unused_fix, is_decl_used};
 pub use lua_diagnostic::LuaDiagnostic;
-pub use lua_diagnostic_code::DiagnosticCode;
+pub use lua_diagnostic_code::{DiagnosticCode, get_default_severity};
@@ -46,6 +46,7 @@ pub struct EmmyLuaAnalysis {
     pub diagnostic: LuaDiagnostic,
     pub emmyrc: Arc<Emmyrc>,
     lib_workspace_counter: u32,
+    config_revision: u64,
 }
 
 impl EmmyLuaAnalysis {
@@ -56,6 +57,7 @@ impl EmmyLuaAnalysis {
             diagnostic: LuaDiagnostic::new(),
             emmyrc,
             lib_workspace_counter: 2,
+            config_revision: 0,
         }
     }
 
@@ -107,6 +109,12 @@ impl EmmyLuaAnalysis {
             .add_workspace_root(root, id);
     }
 
+    /// Returns a number which changes every time the config is changed or the workspace is
+    /// indexed again
+    pub fn get_config_revision(&self) -> u64 {
+        self.config_revision
+    }
+
     pub fn update_file_by_uri(&mut self, uri: &Uri, text: Option<String>) -> Option<FileId> {
         let is_removed = text.is_none();
         let file_id = self
@@ -204,6 +212,7 @@ impl EmmyLuaAnalysis {
     }
 
     pub fn update_config(&mut self, config: Arc<Emmyrc>) {
+        self.config_revision += 1;
         self.emmyrc = config.clone();
         self.compilation.update_config(config.clone());
         self.diagnostic.update_config(config);
@@ -223,6 +232,7 @@ impl EmmyLuaAnalysis {
     }
 
     pub fn reindex(&mut self) {
+        self.config_revision += 1;
         let module = self.compilation.get_db().get_module_index();
         let std_file_ids = module.get_std_file_ids();
         let main_file_ids = module.get_main_workspace_file_ids();
@@ -1,7 +1,7 @@
-use encoding_rs::{Encoding, UTF_8};
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
 use std::{
     error::Error,
-    fs,
+    fs, io,
     path::{Path, PathBuf},
 };
 use wax::Pattern;
@@ -102,3 +102,71 @@ pub fn read_file_with_encoding(path: &Path, encoding: &str) -> Option<String> {
 
     Some(content.to_string())
 }
+
+/// Writes the text back to a file read by `read_file_with_encoding`, in the encoding it
+/// was read with and keeping its byte order mark
+pub fn write_file_with_encoding(path: &Path, content: &str, encoding: &str) -> io::Result<()> {
+    let origin_content = fs::read(path).unwrap_or_default();
+    fs::write(
+        path,
+        encode_file_content(&origin_content, content, encoding),
+    )
+}
+
+fn encode_file_content(origin_content: &[u8], content: &str, encoding: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(content.len());
+    let encoding = match Encoding::for_bom(origin_content) {
+        Some((bom_encoding, bom_len)) => {
+            bytes.extend_from_slice(&origin_content[..bom_len]);
+            bom_encoding
+        }
+        None => {
+            let encoding = Encoding::for_label(encoding.as_bytes()).unwrap_or(UTF_8);
+            // a file that is not valid in the configured encoding was read as utf-8
+            if encoding
+                .decode_without_bom_handling_and_without_replacement(origin_content)
+                .is_some()
+            {
+                encoding
+            } else {
+                UTF_8
+            }
+        }
+    };
+
+    if encoding == UTF_16LE {
+        bytes.extend(content.encode_utf16().flat_map(u16::to_le_bytes));
+    } else if encoding == UTF_16BE {
+        bytes.extend(content.encode_utf16().flat_map(u16::to_be_bytes));
+    } else {
+        let (encoded, _, _) = encoding.encode(content);
+        bytes.extend_from_slice(&encoded);
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_keeps_bom() {
+        let origin = b"\xEF\xBB\xBFlocal a = 1";
+        let bytes = encode_file_content(origin, "local a = 2", "utf-8");
+        assert_eq!(bytes, b"\xEF\xBB\xBFlocal a = 2");
+    }
+
+    #[test]
+    fn test_encode_with_configured_encoding() {
+        let (origin, _, _) = encoding_rs::GBK.encode("-- \u{4e2d}\u{6587}");
+        let bytes = encode_file_content(&origin, "-- \u{4e2d}\u{6587}\n", "gbk");
+        assert_eq!(bytes, [origin.as_ref(), b"\n"].concat());
+    }
+
+    #[test]
+    fn test_encode_utf16() {
+        let origin = b"\xFF\xFEa\x00";
+        let bytes = encode_file_content(origin, "ab", "utf-8");
+        assert_eq!(bytes, b"\xFF\xFEa\x00b\x00");
+    }
+}
@@ -8,7 +8,9 @@ pub use document::LuaDocument;
 use emmylua_parser::{LineIndex, LuaParseError, LuaParser, LuaSyntaxTree};
 pub use file_id::{FileId, InFiled};
 pub use file_uri_handler::{file_path_to_uri, uri_to_file_path};
-pub use loader::{LuaFileInfo, load_workspace_files, read_file_with_encoding};
+pub use loader::{
+    LuaFileInfo, load_workspace_files, read_file_with_encoding, write_file_with_encoding,
+};
 use lsp_types::Uri;
 use rowan::NodeCache;
 use std::collections::HashMap;
@@ -0,0 +1,283 @@
+use std::collections::HashSet;
+
+use emmylua_parser::{
+    LuaAstNode, LuaAstToken, LuaBinaryExpr, LuaCallArgList, LuaCallExpr, LuaExpr, LuaIndexExpr,
+    LuaSyntaxId, LuaSyntaxKind, LuaSyntaxNode, LuaSyntaxToken, LuaTableExpr, LuaTokenKind,
+};
+
+use crate::{
+    style_ruler::{apply_style, IndentRuler},
+    styles::LuaCodeStyle,
+};
+
+use super::{LuaFormatter, LuaLineMeasure, TokenNodeChange};
+
+/// Wraps lines that are wider than `max_line_width`.
+///
+/// For every line that is too long, the outermost table constructor, argument list,
+/// binary operator chain or method call chain that starts on that line is laid out with
+/// one item per line, and tables get a trailing separator. The text is measured again
+/// after each round, so nested constructs are only wrapped when their new line is still
+/// too long. Closures written on one line are never wrapped inside.
+pub fn apply_layout(formatter: &mut LuaFormatter, styles: &LuaCodeStyle) {
+    if styles.max_line_width == 0 {
+        return;
+    }
+
+    let mut expanded = HashSet::new();
+    loop {
+        let measure = formatter.measure_lines(styles);
+        let mut changed = false;
+        for (line, width) in measure.line_widths.iter().enumerate() {
+            if *width <= styles.max_line_width {
+                continue;
+            }
+
+            let Some(layout) = find_wrap_layout(&measure, line, &expanded) else {
+                continue;
+            };
+
+            expanded.insert(layout.syntax_id);
+            for token in &layout.line_breaks {
+                formatter.add_line_break_before(LuaSyntaxId::from_token(token));
+            }
+            if let Some(token) = &layout.trailing_separator_after {
+                formatter.add_token_change(
+                    LuaSyntaxId::from_token(token),
+                    TokenNodeChange::AddRight(",".to_string()),
+                );
+            }
+            changed = true;
+        }
+
+        if !changed {
+            break;
+        }
+
+        formatter.clear_indents();
+        apply_style::<IndentRuler>(formatter, styles);
+    }
+}
+
+#[derive(Debug)]
+struct WrapLayout {
+    syntax_id: LuaSyntaxId,
+    line_breaks: Vec<LuaSyntaxToken>,
+    trailing_separator_after: Option<LuaSyntaxToken>,
+}
+
+fn find_wrap_layout(
+    measure: &LuaLineMeasure,
+    line: usize,
+    expanded: &HashSet<LuaSyntaxId>,
+) -> Option<WrapLayout> {
+    let mut candidates: Vec<(usize, LuaSyntaxNode)> = Vec::new();
+    let mut visited = HashSet::new();
+    for token in measure.line_tokens.get(line)? {
+        for node in token.parent_ancestors() {
+            let syntax_id = LuaSyntaxId::from_node(&node);
+            if !visited.insert(syntax_id) {
+                break;
+            }
+            // an expanded call chain can still move its first call to a new line
+            let is_chain = LuaCallExpr::can_cast(node.kind().into());
+            if (expanded.contains(&syntax_id) && !is_chain)
+                || !is_wrap_candidate(&node)
+                || is_in_one_line_closure(measure, &node)
+            {
+                continue;
+            }
+
+            let Some(first_token) = node.first_token() else {
+                continue;
+            };
+            match measure
+                .positions
+                .get(&LuaSyntaxId::from_token(&first_token))
+            {
+                Some((start_line, column)) if *start_line == line => {
+                    candidates.push((*column, node));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // the outermost candidate starts first, a larger node wins when two start together
+    candidates.sort_by_key(|(column, node)| (*column, std::cmp::Reverse(node.text_range().len())));
+    candidates.into_iter().find_map(|(_, node)| {
+        let layout = build_wrap_layout(&node, expanded)?;
+        let needs_break = layout
+            .line_breaks
+            .iter()
+            .any(|token| !is_line_start(measure, token));
+        needs_break.then_some(layout)
+    })
+}
+
+fn is_wrap_candidate(node: &LuaSyntaxNode) -> bool {
+    let kind: LuaSyntaxKind = node.kind().into();
+    match kind {
+        LuaSyntaxKind::TableArrayExpr | LuaSyntaxKind::TableObjectExpr => true,
+        LuaSyntaxKind::CallArgList => true,
+        LuaSyntaxKind::BinaryExpr => node
+            .parent()
+            .is_none_or(|parent| get_binary_op_kind(&parent) != get_binary_op_kind(node)),
+        _ if LuaCallExpr::can_cast(kind) => node.parent().is_none_or(|parent| {
+            let parent_kind: LuaSyntaxKind = parent.kind().into();
+            parent_kind != LuaSyntaxKind::IndexExpr && !LuaCallExpr::can_cast(parent_kind)
+        }),
+        _ => false,
+    }
+}
+
+/// Whether the node is inside a closure that is written on one line, such as
+/// `function(x) return x + 1 end`, which is kept as it is
+fn is_in_one_line_closure(measure: &LuaLineMeasure, node: &LuaSyntaxNode) -> bool {
+    let get_line = |token: Option<LuaSyntaxToken>| {
+        let token = token?;
+        measure
+            .positions
+            .get(&LuaSyntaxId::from_token(&token))
+            .map(|(line, _)| *line)
+    };
+    node.ancestors()
+        .skip(1)
+        .filter(|ancestor| ancestor.kind() == LuaSyntaxKind::ClosureExpr.into())
+        .any(|closure| {
+            let first_line = get_line(closure.first_token());
+            first_line.is_some() && first_line == get_line(closure.last_token())
+        })
+}
+
+fn build_wrap_layout(node: &LuaSyntaxNode, expanded: &HashSet<LuaSyntaxId>) -> Option<WrapLayout> {
+    let mut layout = WrapLayout {
+        syntax_id: LuaSyntaxId::from_node(node),
+        line_breaks: Vec::new(),
+        trailing_separator_after: None,
+    };
+
+    if let Some(table) = LuaTableExpr::cast(node.clone()) {
+        let mut last_field = None;
+        for field in table.get_fields() {
+            layout.line_breaks.extend(field.syntax().first_token());
+            last_field = Some(field);
+        }
+        let last_field = last_field?;
+        let has_separator = last_field
+            .syntax()
+            .siblings_with_tokens(rowan::Direction::Next)
+            .skip(1)
+            .filter_map(|element| element.into_token())
+            .any(|token| {
+                matches!(
+                    token.kind().into(),
+                    LuaTokenKind::TkComma | LuaTokenKind::TkSemicolon
+                )
+            });
+        if !has_separator {
+            layout.trailing_separator_after = last_field.syntax().last_token();
+        }
+        layout.line_breaks.extend(
+            table
+                .token_by_kind(LuaTokenKind::TkRightBrace)
+                .map(|it| it.syntax().clone()),
+        );
+    } else if let Some(args_list) = LuaCallArgList::cast(node.clone()) {
+        let right_paren = args_list.token_by_kind(LuaTokenKind::TkRightParen)?;
+        for arg in args_list.get_args() {
+            layout.line_breaks.extend(arg.syntax().first_token());
+        }
+        if layout.line_breaks.is_empty() {
+            return None;
+        }
+        layout.line_breaks.push(right_paren.syntax().clone());
+    } else if let Some(binary_expr) = LuaBinaryExpr::cast(node.clone()) {
+        let op_kind = get_binary_op_kind(node)?;
+        collect_binary_chain(&binary_expr, op_kind, &mut layout.line_breaks);
+    } else if let Some(call_expr) = LuaCallExpr::cast(node.clone()) {
+        let mut index_tokens = Vec::new();
+        collect_call_chain(call_expr, &mut index_tokens);
+        if index_tokens.len() < 2 {
+            return None;
+        }
+        // the first call of the chain stays on the line of the object it is called on, unless
+        // that line is still too long once the rest of the chain is wrapped, as the chain is
+        // wrapped before the argument lists
+        let first_call = index_tokens.pop()?;
+        if expanded.contains(&layout.syntax_id) {
+            layout.syntax_id = LuaSyntaxId::from_token(&first_call);
+            if expanded.contains(&layout.syntax_id) {
+                return None;
+            }
+            index_tokens = vec![first_call];
+        }
+        layout.line_breaks = index_tokens;
+    }
+
+    Some(layout)
+}
+
+fn collect_binary_chain(
+    binary_expr: &LuaBinaryExpr,
+    op_kind: LuaTokenKind,
+    line_breaks: &mut Vec<LuaSyntaxToken>,
+) {
+    let Some((left, right)) = binary_expr.get_exprs() else {
+        return;
+    };
+    if let LuaExpr::BinaryExpr(left) = &left {
+        if get_binary_op_kind(left.syntax()) == Some(op_kind) {
+            collect_binary_chain(left, op_kind, line_breaks);
+        }
+    }
+    if let Some(op_token) = binary_expr.get_op_token() {
+        line_breaks.push(op_token.syntax().clone());
+    }
+    if let LuaExpr::BinaryExpr(right) = &right {
+        if get_binary_op_kind(right.syntax()) == Some(op_kind) {
+            collect_binary_chain(right, op_kind, line_breaks);
+        }
+    }
+}
+
+/// Collects the `.` and `:` tokens of the method calls in a call chain, last call first
+fn collect_call_chain(call_expr: LuaCallExpr, index_tokens: &mut Vec<LuaSyntaxToken>) {
+    let mut prefix = call_expr.get_prefix_expr();
+    while let Some(expr) = prefix {
+        prefix = match expr {
+            LuaExpr::CallExpr(call_expr) => call_expr.get_prefix_expr(),
+            LuaExpr::IndexExpr(index_expr) => {
+                let index_token = index_expr
+                    .get_index_token()
+                    .filter(|token| token.is_dot() || token.is_colon());
+                if let Some(index_token) = index_token.filter(|_| is_called(&index_expr)) {
+                    index_tokens.push(index_token.syntax().clone());
+                }
+                index_expr.get_prefix_expr()
+            }
+            _ => None,
+        };
+    }
+}
+
+fn is_called(index_expr: &LuaIndexExpr) -> bool {
+    index_expr
+        .syntax()
+        .parent()
+        .is_some_and(|parent| LuaCallExpr::can_cast(parent.kind().into()))
+}
+
+fn get_binary_op_kind(node: &LuaSyntaxNode) -> Option<LuaTokenKind> {
+    let binary_expr = LuaBinaryExpr::cast(node.clone())?;
+    Some(binary_expr.get_op_token()?.syntax().kind().into())
+}
+
+fn is_line_start(measure: &LuaLineMeasure, token: &LuaSyntaxToken) -> bool {
+    let Some((line, _)) = measure.positions.get(&LuaSyntaxId::from_token(token)) else {
+        return true;
+    };
+    measure.line_tokens[*line]
+        .first()
+        .is_some_and(|first| first == token)
+}
@@ -1,28 +1,45 @@
+mod layout;
+mod printer;
 mod syntax_node_change;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use emmylua_parser::{LuaAst, LuaAstNode, LuaSyntaxId};
-use rowan::NodeOrToken;
 
-use crate::format::syntax_node_change::TokenNodeChange;
+pub use layout::apply_layout;
+pub use printer::LuaLineMeasure;
+pub use syntax_node_change::TokenNodeChange;
+
+use crate::styles::LuaCodeStyle;
 
-#[allow(unused)]
 #[derive(Debug)]
 pub struct LuaFormatter {
     root: LuaAst,
     token_changes: HashMap<LuaSyntaxId, TokenNodeChange>,
+    space_before: HashMap<LuaSyntaxId, usize>,
+    space_after: HashMap<LuaSyntaxId, usize>,
+    indents: HashMap<LuaSyntaxId, usize>,
+    line_breaks: HashSet<LuaSyntaxId>,
+    base_indent: String,
 }
 
-#[allow(unused)]
 impl LuaFormatter {
     pub fn new(root: LuaAst) -> Self {
         Self {
             root,
             token_changes: HashMap::new(),
+            space_before: HashMap::new(),
+            space_after: HashMap::new(),
+            indents: HashMap::new(),
+            line_breaks: HashSet::new(),
+            base_indent: String::new(),
         }
     }
 
+    pub fn get_root(&self) -> &LuaAst {
+        &self.root
+    }
+
     pub fn add_token_change(&mut self, syntax_id: LuaSyntaxId, change: TokenNodeChange) {
         self.token_changes.insert(syntax_id, change);
     }
@@ -31,32 +48,68 @@ impl LuaFormatter {
         self.token_changes.get(syntax_id)
     }
 
-    pub fn get_formatted_text(&self) -> String {
-        let mut formatted_text = String::new();
-        for node_or_token in self.root.syntax().descendants_with_tokens() {
-            if let NodeOrToken::Token(token) = node_or_token {
-                let syntax_id = LuaSyntaxId::from_token(&token);
-                if let Some(change) = self.token_changes.get(&syntax_id) {
-                    match change {
-                        TokenNodeChange::Remove => continue,
-                        TokenNodeChange::AddLeft(s) => {
-                            formatted_text.push_str(s);
-                            formatted_text.push_str(&token.text());
-                        }
-                        TokenNodeChange::AddRight(s) => {
-                            formatted_text.push_str(&token.text());
-                            formatted_text.push_str(s);
-                        }
-                        TokenNodeChange::ReplaceWith(s) => {
-                            formatted_text.push_str(s);
-                        }
-                    }
-                } else {
-                    formatted_text.push_str(&token.text());
-                }
-            }
-        }
+    /// Set the number of spaces between the token and the previous one when they share a line
+    pub fn set_space_before(&mut self, syntax_id: LuaSyntaxId, spaces: usize) {
+        self.space_before.insert(syntax_id, spaces);
+    }
+
+    pub fn get_space_before(&self, syntax_id: &LuaSyntaxId) -> Option<usize> {
+        self.space_before.get(syntax_id).copied()
+    }
+
+    /// Set the number of spaces between the token and the next one when they share a line
+    pub fn set_space_after(&mut self, syntax_id: LuaSyntaxId, spaces: usize) {
+        self.space_after.insert(syntax_id, spaces);
+    }
+
+    pub fn get_space_after(&self, syntax_id: &LuaSyntaxId) -> Option<usize> {
+        self.space_after.get(syntax_id).copied()
+    }
+
+    /// Set the indentation level of a token that starts a line
+    pub fn set_indent(&mut self, syntax_id: LuaSyntaxId, level: usize) {
+        self.indents.insert(syntax_id, level);
+    }
+
+    pub fn get_indent(&self, syntax_id: &LuaSyntaxId) -> Option<usize> {
+        self.indents.get(syntax_id).copied()
+    }
+
+    pub fn clear_indents(&mut self) {
+        self.indents.clear();
+    }
+
+    /// Start a new line before the token even if the source has it on the same line
+    pub fn add_line_break_before(&mut self, syntax_id: LuaSyntaxId) {
+        self.line_breaks.insert(syntax_id);
+    }
+
+    pub fn has_line_break_before(&self, syntax_id: &LuaSyntaxId) -> bool {
+        self.line_breaks.contains(syntax_id)
+    }
+
+    /// Set the indentation of the line the root starts on, it is repeated in front of every
+    /// following line when the root is part of a larger document
+    pub fn set_base_indent(&mut self, base_indent: String) {
+        self.base_indent = base_indent;
+    }
+
+    pub fn get_base_indent(&self) -> &str {
+        &self.base_indent
+    }
+
+    pub fn get_formatted_text(&self, styles: &LuaCodeStyle) -> String {
+        let source = self.root.syntax().text().to_string();
+        let mut printer = printer::LuaPrinter::new(self, styles, &source);
+        printer.print();
+        printer.finish()
+    }
 
-        formatted_text
+    /// Print the current state and report where every token ends up
+    pub fn measure_lines(&self, styles: &LuaCodeStyle) -> LuaLineMeasure {
+        let source = self.root.syntax().text().to_string();
+        let mut printer = printer::LuaPrinter::new(self, styles, &source);
+        printer.print();
+        printer.finish_measure()
     }
 }
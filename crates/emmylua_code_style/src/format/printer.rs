@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use emmylua_parser::{
+    LuaAst, LuaAstNode, LuaSyntaxId, LuaSyntaxKind, LuaSyntaxToken, LuaTokenKind,
+};
+use rowan::{NodeOrToken, WalkEvent};
+
+use crate::{styles::LuaCodeStyle, util::is_block_open};
+
+use super::{LuaFormatter, TokenNodeChange};
+
+/// Rebuilds the text of the formatter root, replacing the original whitespace between
+/// tokens with the spacing and indentation decided by the style rulers.
+pub struct LuaPrinter<'a> {
+    formatter: &'a LuaFormatter,
+    styles: &'a LuaCodeStyle,
+    end_of_line: &'static str,
+    output: String,
+    /// Whitespace seen since the last printed token on the current line
+    pending_space: String,
+    /// Line endings seen since the last printed token
+    pending_newlines: usize,
+    prev_token: Option<LuaSyntaxToken>,
+    comment_depth: usize,
+    prev_in_comment: bool,
+    comment_start: bool,
+    /// Every token of the current line was removed, so its line ending is dropped too
+    skip_line: bool,
+    measure: LuaLineMeasure,
+}
+
+/// Where the tokens ended up in the printed text, used to find lines that are too long
+#[derive(Debug)]
+pub struct LuaLineMeasure {
+    /// Width of every printed line
+    pub line_widths: Vec<usize>,
+    /// Tokens starting on every printed line
+    pub line_tokens: Vec<Vec<LuaSyntaxToken>>,
+    /// Line and column at which each token starts
+    pub positions: HashMap<LuaSyntaxId, (usize, usize)>,
+    column: usize,
+}
+
+impl LuaLineMeasure {
+    fn new(column: usize) -> Self {
+        Self {
+            line_widths: Vec::new(),
+            line_tokens: vec![Vec::new()],
+            positions: HashMap::new(),
+            column,
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.line_widths.push(self.column);
+        self.line_tokens.push(Vec::new());
+        self.column = 0;
+    }
+
+    fn add_text(&mut self, text: &str) {
+        match text.rfind('\n') {
+            Some(pos) => {
+                for _ in text.matches('\n') {
+                    self.new_line();
+                }
+                self.column = text[pos + 1..].chars().count();
+            }
+            None => self.column += text.chars().count(),
+        }
+    }
+
+    fn add_token(&mut self, token: &LuaSyntaxToken) {
+        let line = self.line_widths.len();
+        self.positions
+            .insert(LuaSyntaxId::from_token(token), (line, self.column));
+        if let Some(tokens) = self.line_tokens.last_mut() {
+            tokens.push(token.clone());
+        }
+    }
+
+    fn finish(&mut self) {
+        self.line_widths.push(self.column);
+    }
+}
+
+impl<'a> LuaPrinter<'a> {
+    pub fn new(formatter: &'a LuaFormatter, styles: &'a LuaCodeStyle, source: &str) -> Self {
+        Self {
+            formatter,
+            styles,
+            end_of_line: styles.end_of_line.resolve(source),
+            output: String::with_capacity(source.len()),
+            pending_space: String::new(),
+            pending_newlines: 0,
+            prev_token: None,
+            comment_depth: 0,
+            prev_in_comment: false,
+            comment_start: false,
+            skip_line: false,
+            measure: LuaLineMeasure::new(get_text_width(formatter.get_base_indent())),
+        }
+    }
+
+    pub fn print(&mut self) {
+        let root = self.formatter.get_root().syntax().clone();
+        for event in root.preorder_with_tokens() {
+            match event {
+                WalkEvent::Enter(NodeOrToken::Node(node)) => {
+                    if node.kind() == LuaSyntaxKind::Comment.into() {
+                        self.comment_depth += 1;
+                        self.comment_start = true;
+                    }
+                }
+                WalkEvent::Leave(NodeOrToken::Node(node)) => {
+                    if node.kind() == LuaSyntaxKind::Comment.into() {
+                        self.comment_depth -= 1;
+                    }
+                }
+                WalkEvent::Enter(NodeOrToken::Token(token)) => self.visit_token(token),
+                WalkEvent::Leave(NodeOrToken::Token(_)) => {}
+            }
+        }
+    }
+
+    pub fn finish_measure(mut self) -> LuaLineMeasure {
+        self.measure.finish();
+        self.measure
+    }
+
+    pub fn finish(mut self) -> String {
+        // a node that is formatted on its own is part of a larger document, the text after
+        // it belongs to its parent
+        let is_chunk = matches!(self.formatter.get_root(), LuaAst::LuaChunk(_));
+        if is_chunk && self.styles.insert_final_newline && !self.output.is_empty() {
+            self.output.push_str(self.end_of_line);
+        }
+        self.output
+    }
+
+    fn visit_token(&mut self, token: LuaSyntaxToken) {
+        match token.kind().into() {
+            LuaTokenKind::TkWhitespace => {
+                if self.pending_newlines == 0 {
+                    self.pending_space.push_str(token.text());
+                }
+            }
+            LuaTokenKind::TkEndOfLine => {
+                if self.skip_line {
+                    self.skip_line = false;
+                } else {
+                    self.pending_newlines += 1;
+                    self.pending_space.clear();
+                }
+            }
+            _ => self.print_token(token),
+        }
+    }
+
+    fn print_token(&mut self, token: LuaSyntaxToken) {
+        let syntax_id = LuaSyntaxId::from_token(&token);
+        let change = self.formatter.get_token_change(&syntax_id);
+        if matches!(change, Some(TokenNodeChange::Remove)) {
+            if self.pending_newlines > 0 {
+                self.skip_line = true;
+            }
+            return;
+        }
+        self.skip_line = false;
+
+        let in_comment = self.comment_depth > 0;
+        if self.pending_newlines == 0 && self.formatter.has_line_break_before(&syntax_id) {
+            self.pending_newlines = 1;
+        }
+
+        if let Some(prev_token) = &self.prev_token {
+            if self.pending_newlines > 0 {
+                let lines = if is_block_open(prev_token) || is_block_close(&token) {
+                    1
+                } else {
+                    self.pending_newlines.min(self.styles.max_blank_lines + 1)
+                };
+                for _ in 0..lines {
+                    self.output.push_str(self.end_of_line);
+                    self.measure.new_line();
+                }
+                let base_indent = self.formatter.get_base_indent();
+                let level = self.formatter.get_indent(&syntax_id).unwrap_or(0);
+                self.output.push_str(base_indent);
+                self.output.push_str(&self.styles.indent.indent_text(level));
+                self.measure.column +=
+                    get_text_width(base_indent) + self.styles.indent.width() * level;
+            } else if in_comment && (self.prev_in_comment || self.comment_start) {
+                // whitespace inside a comment, and before a trailing comment, is kept as
+                // written unless a ruler asked for a specific spacing
+                let spaces = self.get_explicit_spaces(prev_token, &token);
+                if let Some(spaces) = spaces.filter(|_| !self.comment_start) {
+                    self.output.push_str(&" ".repeat(spaces));
+                    self.measure.column += spaces;
+                } else if self.comment_start && self.pending_space.is_empty() {
+                    self.output.push(' ');
+                    self.measure.column += 1;
+                } else {
+                    self.output.push_str(&self.pending_space);
+                    self.measure.add_text(&self.pending_space);
+                }
+            } else {
+                let spaces = self.get_spaces_between(prev_token, &token);
+                self.output.push_str(&" ".repeat(spaces));
+                self.measure.column += spaces;
+            }
+        }
+
+        self.measure.add_token(&token);
+        let start = self.output.len();
+        match change {
+            Some(TokenNodeChange::AddLeft(s)) => {
+                self.output.push_str(s);
+                self.output.push_str(token.text());
+            }
+            Some(TokenNodeChange::AddRight(s)) => {
+                self.output.push_str(token.text());
+                self.output.push_str(s);
+            }
+            Some(TokenNodeChange::ReplaceWith(s)) => self.output.push_str(s),
+            _ => self.output.push_str(token.text()),
+        }
+        self.measure.add_text(&self.output[start..]);
+
+        self.pending_space.clear();
+        self.pending_newlines = 0;
+        self.prev_in_comment = in_comment;
+        self.comment_start = false;
+        self.prev_token = Some(token);
+    }
+
+    fn get_explicit_spaces(&self, prev: &LuaSyntaxToken, next: &LuaSyntaxToken) -> Option<usize> {
+        self.formatter
+            .get_space_before(&LuaSyntaxId::from_token(next))
+            .or_else(|| {
+                self.formatter
+                    .get_space_after(&LuaSyntaxId::from_token(prev))
+            })
+    }
+
+    fn get_spaces_between(&self, prev: &LuaSyntaxToken, next: &LuaSyntaxToken) -> usize {
+        let spaces = self
+            .get_explicit_spaces(prev, next)
+            .unwrap_or(if self.pending_space.is_empty() { 0 } else { 1 });
+
+        if spaces == 0 && would_merge(prev.text(), next.text()) {
+            1
+        } else {
+            spaces
+        }
+    }
+}
+
+/// The width of indentation text, counting a tab as four columns
+fn get_text_width(text: &str) -> usize {
+    text.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum()
+}
+
+/// Whether the token closes a block or bracket, blank lines before it are dropped
+fn is_block_close(token: &LuaSyntaxToken) -> bool {
+    matches!(
+        token.kind().into(),
+        LuaTokenKind::TkEnd
+            | LuaTokenKind::TkElse
+            | LuaTokenKind::TkElseIf
+            | LuaTokenKind::TkUntil
+            | LuaTokenKind::TkRightBrace
+            | LuaTokenKind::TkRightParen
+    )
+}
+
+/// Whether two tokens printed without a space between them would be read back as
+/// different tokens, e.g. `local x` becoming `localx` or `- -x` becoming a comment.
+fn would_merge(prev: &str, next: &str) -> bool {
+    let (Some(left), Some(right)) = (prev.chars().last(), next.chars().next()) else {
+        return false;
+    };
+
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    if is_word(left) && is_word(right) {
+        return true;
+    }
+
+    if left.is_ascii_digit() && right == '.' {
+        return true;
+    }
+
+    matches!(
+        (left, right),
+        ('-', '-')
+            | ('.', '.')
+            | ('[', '[')
+            | ('[', '=')
+            | ('/', '/')
+            | ('=', '=')
+            | ('~', '=')
+            | ('<', '=')
+            | ('>', '=')
+            | ('<', '<')
+            | ('>', '>')
+            | (':', ':')
+    )
+}
@@ -1,24 +1,39 @@
 mod test;
 
 use emmylua_parser::{LuaAst, LuaParser, ParserConfig};
-use styles::LuaCodeStyle;
 
 mod format;
 mod style_ruler;
 mod styles;
+mod util;
+
+pub use styles::{LuaCodeStyle, LuaEndOfLine, LuaIndent};
+pub use util::{get_line_indent, is_block_open};
 
 pub fn reformat_lua_code(code: &str, styles: &LuaCodeStyle) -> String {
     let tree = LuaParser::parse(code, ParserConfig::default());
 
     let mut formatter = format::LuaFormatter::new(LuaAst::LuaChunk(tree.get_chunk_node()));
     style_ruler::apply_styles(&mut formatter, styles);
-    let formatted_text = formatter.get_formatted_text();
-    formatted_text
+    format::apply_layout(&mut formatter, styles);
+    formatter.get_formatted_text(styles)
 }
 
 pub fn reformat_node(node: &LuaAst, styles: &LuaCodeStyle) -> String {
+    reformat_node_with_indent(node, "", styles)
+}
+
+/// Formats a node that is part of a larger document. `base_indent` is the indentation of
+/// the line the node starts on; every following line of the result starts with it, and
+/// no final line ending is added.
+pub fn reformat_node_with_indent(
+    node: &LuaAst,
+    base_indent: &str,
+    styles: &LuaCodeStyle,
+) -> String {
     let mut formatter = format::LuaFormatter::new(node.clone());
+    formatter.set_base_indent(base_indent.to_string());
     style_ruler::apply_styles(&mut formatter, styles);
-    let formatted_text = formatter.get_formatted_text();
-    formatted_text
+    format::apply_layout(&mut formatter, styles);
+    formatter.get_formatted_text(styles)
 }
@@ -1,3 +1,6 @@
+use emmylua_parser::{LuaAstNode, LuaSyntaxId, LuaSyntaxKind, LuaSyntaxToken, LuaTokenKind};
+use rowan::{NodeOrToken, WalkEvent};
+
 use crate::{format::LuaFormatter, styles::LuaCodeStyle};
 
 use super::StyleRuler;
@@ -5,5 +8,166 @@ use super::StyleRuler;
 pub struct BasicSpaceRuler;
 
 impl StyleRuler for BasicSpaceRuler {
-    fn apply_style(_: &mut LuaFormatter, _: &LuaCodeStyle) {}
+    fn apply_style(formatter: &mut LuaFormatter, styles: &LuaCodeStyle) {
+        let root = formatter.get_root().syntax().clone();
+        let mut comment_depth = 0;
+        for event in root.preorder_with_tokens() {
+            match event {
+                WalkEvent::Enter(NodeOrToken::Node(node))
+                    if node.kind() == LuaSyntaxKind::Comment.into() =>
+                {
+                    comment_depth += 1;
+                }
+                WalkEvent::Leave(NodeOrToken::Node(node))
+                    if node.kind() == LuaSyntaxKind::Comment.into() =>
+                {
+                    comment_depth -= 1;
+                }
+                WalkEvent::Enter(NodeOrToken::Token(token)) if comment_depth == 0 => {
+                    apply_token_space(formatter, styles, &token);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn apply_token_space(formatter: &mut LuaFormatter, styles: &LuaCodeStyle, token: &LuaSyntaxToken) {
+    let Some(parent) = token.parent() else {
+        return;
+    };
+    let parent_kind: LuaSyntaxKind = parent.kind().into();
+    let syntax_id = LuaSyntaxId::from_token(token);
+    let token_kind: LuaTokenKind = token.kind().into();
+    match token_kind {
+        LuaTokenKind::TkComma => {
+            formatter.set_space_before(syntax_id, 0);
+            formatter.set_space_after(syntax_id, 1);
+        }
+        LuaTokenKind::TkSemicolon => {
+            formatter.set_space_before(syntax_id, 0);
+        }
+        LuaTokenKind::TkDot | LuaTokenKind::TkColon if parent_kind == LuaSyntaxKind::IndexExpr => {
+            formatter.set_space_before(syntax_id, 0);
+            formatter.set_space_after(syntax_id, 0);
+        }
+        LuaTokenKind::TkLeftParen => {
+            if matches!(
+                parent_kind,
+                LuaSyntaxKind::ParamList | LuaSyntaxKind::CallArgList
+            ) {
+                formatter.set_space_before(syntax_id, 0);
+            }
+            formatter.set_space_after(syntax_id, 0);
+        }
+        LuaTokenKind::TkRightParen => {
+            formatter.set_space_before(syntax_id, 0);
+        }
+        LuaTokenKind::TkLeftBracket => {
+            if parent_kind == LuaSyntaxKind::IndexExpr {
+                formatter.set_space_before(syntax_id, 0);
+            }
+            formatter.set_space_after(syntax_id, 0);
+        }
+        LuaTokenKind::TkRightBracket => {
+            formatter.set_space_before(syntax_id, 0);
+        }
+        LuaTokenKind::TkLeftBrace | LuaTokenKind::TkRightBrace => {
+            let spaces = if parent_kind == LuaSyntaxKind::TableEmptyExpr {
+                0
+            } else if styles.space_inside_table_braces {
+                1
+            } else {
+                0
+            };
+            if token_kind == LuaTokenKind::TkLeftBrace {
+                formatter.set_space_after(syntax_id, spaces);
+            } else {
+                formatter.set_space_before(syntax_id, spaces);
+            }
+        }
+        LuaTokenKind::TkLt if parent_kind == LuaSyntaxKind::Attribute => {
+            formatter.set_space_before(syntax_id, 1);
+            formatter.set_space_after(syntax_id, 0);
+        }
+        LuaTokenKind::TkGt if parent_kind == LuaSyntaxKind::Attribute => {
+            formatter.set_space_before(syntax_id, 0);
+        }
+        LuaTokenKind::TkDbColon if parent_kind == LuaSyntaxKind::LabelStat => {
+            if token
+                .prev_token()
+                .is_some_and(|prev| prev.kind() == LuaTokenKind::TkName.into())
+            {
+                formatter.set_space_before(syntax_id, 0);
+            } else {
+                formatter.set_space_after(syntax_id, 0);
+            }
+        }
+        kind if kind.is_assign_op() => {
+            formatter.set_space_before(syntax_id, 1);
+            formatter.set_space_after(syntax_id, 1);
+        }
+        LuaTokenKind::TkAnd | LuaTokenKind::TkOr => {
+            formatter.set_space_before(syntax_id, 1);
+            formatter.set_space_after(syntax_id, 1);
+        }
+        LuaTokenKind::TkNot => {
+            formatter.set_space_after(syntax_id, 1);
+        }
+        _ if parent_kind == LuaSyntaxKind::UnaryExpr => {
+            formatter.set_space_after(syntax_id, 0);
+        }
+        _ if parent_kind == LuaSyntaxKind::BinaryExpr && is_binary_operator(token_kind) => {
+            let spaces = if styles.space_around_binary_operator {
+                1
+            } else {
+                0
+            };
+            formatter.set_space_before(syntax_id, spaces);
+            formatter.set_space_after(syntax_id, spaces);
+        }
+        LuaTokenKind::TkLocal
+        | LuaTokenKind::TkIf
+        | LuaTokenKind::TkElseIf
+        | LuaTokenKind::TkWhile
+        | LuaTokenKind::TkUntil
+        | LuaTokenKind::TkFor
+        | LuaTokenKind::TkIn
+        | LuaTokenKind::TkReturn
+        | LuaTokenKind::TkGoto => {
+            formatter.set_space_after(syntax_id, 1);
+        }
+        LuaTokenKind::TkFunction if parent_kind != LuaSyntaxKind::ClosureExpr => {
+            formatter.set_space_after(syntax_id, 1);
+        }
+        LuaTokenKind::TkThen | LuaTokenKind::TkDo => {
+            formatter.set_space_before(syntax_id, 1);
+        }
+        _ => {}
+    }
+}
+
+fn is_binary_operator(kind: LuaTokenKind) -> bool {
+    matches!(
+        kind,
+        LuaTokenKind::TkPlus
+            | LuaTokenKind::TkMinus
+            | LuaTokenKind::TkMul
+            | LuaTokenKind::TkDiv
+            | LuaTokenKind::TkIDiv
+            | LuaTokenKind::TkMod
+            | LuaTokenKind::TkPow
+            | LuaTokenKind::TkConcat
+            | LuaTokenKind::TkEq
+            | LuaTokenKind::TkNe
+            | LuaTokenKind::TkLt
+            | LuaTokenKind::TkLe
+            | LuaTokenKind::TkGt
+            | LuaTokenKind::TkGe
+            | LuaTokenKind::TkBitAnd
+            | LuaTokenKind::TkBitOr
+            | LuaTokenKind::TkBitXor
+            | LuaTokenKind::TkShl
+            | LuaTokenKind::TkShr
+    )
 }
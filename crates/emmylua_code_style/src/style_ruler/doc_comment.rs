@@ -0,0 +1,348 @@
+use std::collections::BTreeSet;
+
+use emmylua_parser::{
+    LuaAstNode, LuaAstToken, LuaComment, LuaDocTagDiagnostic, LuaDocTagField, LuaDocTagParam,
+    LuaSyntaxElement, LuaSyntaxId, LuaSyntaxKind, LuaSyntaxNode, LuaSyntaxToken, LuaTokenKind,
+};
+
+use crate::{
+    format::{LuaFormatter, TokenNodeChange},
+    styles::LuaCodeStyle,
+};
+
+use super::StyleRuler;
+
+/// Normalizes `---@` annotations: spacing around `|` in types, column alignment of
+/// consecutive `---@param` and `---@field` lines, and merging of consecutive
+/// `---@diagnostic` lines with the same action.
+pub struct DocCommentRuler;
+
+impl StyleRuler for DocCommentRuler {
+    fn apply_style(formatter: &mut LuaFormatter, styles: &LuaCodeStyle) {
+        let root = formatter.get_root().syntax().clone();
+        for comment in root.descendants().filter_map(LuaComment::cast) {
+            apply_union_space(formatter, styles, &comment);
+            let lines = collect_doc_lines(&comment);
+            merge_diagnostic_lines(formatter, &lines);
+            if styles.align_doc_tags {
+                align_tag_lines(formatter, &lines);
+            }
+        }
+    }
+}
+
+fn apply_union_space(formatter: &mut LuaFormatter, styles: &LuaCodeStyle, comment: &LuaComment) {
+    let spaces = if styles.space_around_doc_union { 1 } else { 0 };
+    for token in comment
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+    {
+        let is_union = token.kind() == LuaTokenKind::TkDocOr.into()
+            && token
+                .parent()
+                .is_some_and(|parent| parent.kind() == LuaSyntaxKind::TypeBinary.into());
+        if is_union {
+            let syntax_id = LuaSyntaxId::from_token(&token);
+            formatter.set_space_before(syntax_id, spaces);
+            formatter.set_space_after(syntax_id, spaces);
+        }
+    }
+}
+
+/// One line of a doc comment that consists of `---@`, a tag and an optional description
+#[derive(Debug)]
+struct DocLine {
+    tag: Option<LuaSyntaxNode>,
+    description: Option<LuaSyntaxNode>,
+    tokens: Vec<LuaSyntaxToken>,
+}
+
+fn collect_doc_lines(comment: &LuaComment) -> Vec<DocLine> {
+    let mut lines = Vec::new();
+    let mut elements: Vec<LuaSyntaxElement> = Vec::new();
+    for element in comment.syntax().children_with_tokens() {
+        match element.kind().into() {
+            LuaTokenKind::TkEndOfLine => {
+                lines.push(build_doc_line(std::mem::take(&mut elements)));
+            }
+            LuaTokenKind::TkWhitespace => {}
+            _ => elements.push(element),
+        }
+    }
+    lines.push(build_doc_line(elements));
+    lines
+}
+
+fn build_doc_line(elements: Vec<LuaSyntaxElement>) -> DocLine {
+    let mut line = DocLine {
+        tag: None,
+        description: None,
+        tokens: Vec::new(),
+    };
+    for element in &elements {
+        match element {
+            LuaSyntaxElement::Node(node) => line.tokens.extend(
+                node.descendants_with_tokens()
+                    .filter_map(|it| it.into_token()),
+            ),
+            LuaSyntaxElement::Token(token) => line.tokens.push(token.clone()),
+        }
+    }
+
+    let is_tag_line = matches!(
+        elements.first(),
+        Some(LuaSyntaxElement::Token(token)) if token.kind() == LuaTokenKind::TkDocStart.into()
+    );
+    if !is_tag_line {
+        return line;
+    }
+
+    match &elements[1..] {
+        [LuaSyntaxElement::Node(tag)] => line.tag = Some(tag.clone()),
+        [LuaSyntaxElement::Node(tag), LuaSyntaxElement::Node(description)]
+            if description.kind() == LuaSyntaxKind::DocDescription.into() =>
+        {
+            line.tag = Some(tag.clone());
+            line.description = Some(description.clone());
+        }
+        _ => {}
+    }
+    line
+}
+
+fn merge_diagnostic_lines(formatter: &mut LuaFormatter, lines: &[DocLine]) {
+    let mut index = 0;
+    while index < lines.len() {
+        let Some((action, _)) = get_diagnostic(&lines[index]) else {
+            index += 1;
+            continue;
+        };
+
+        let mut end = index + 1;
+        while end < lines.len()
+            && get_diagnostic(&lines[end]).is_some_and(|(other, _)| other == action)
+        {
+            end += 1;
+        }
+
+        let group = &lines[index..end];
+        let mut codes = BTreeSet::new();
+        for line in group {
+            if let Some((_, line_codes)) = get_diagnostic(line) {
+                codes.extend(line_codes.iter().map(|code| code.text().to_string()));
+            }
+        }
+
+        let (_, first_codes) = get_diagnostic(&group[0]).unwrap_or_default();
+        let merged = codes.into_iter().collect::<Vec<_>>().join(", ");
+        let written = first_codes
+            .iter()
+            .map(|code| code.text())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if group.len() > 1 || merged != written {
+            rewrite_code_list(formatter, &group[0], merged);
+            for line in &group[1..] {
+                for token in &line.tokens {
+                    formatter
+                        .add_token_change(LuaSyntaxId::from_token(token), TokenNodeChange::Remove);
+                }
+            }
+        }
+
+        index = end;
+    }
+}
+
+/// The action and codes of a `---@diagnostic action: codes` line without a description
+fn get_diagnostic(line: &DocLine) -> Option<(String, Vec<LuaSyntaxToken>)> {
+    if line.description.is_some() {
+        return None;
+    }
+    let diagnostic = LuaDocTagDiagnostic::cast(line.tag.clone()?)?;
+    let action = diagnostic.get_action_token()?.get_name_text().to_string();
+    let codes = diagnostic
+        .get_code_list()?
+        .get_codes()
+        .map(|code| code.syntax().clone())
+        .collect::<Vec<_>>();
+    if codes.is_empty() {
+        return None;
+    }
+    Some((action, codes))
+}
+
+fn rewrite_code_list(formatter: &mut LuaFormatter, line: &DocLine, merged: String) {
+    let Some(code_list) = line
+        .tag
+        .clone()
+        .and_then(LuaDocTagDiagnostic::cast)
+        .and_then(|diagnostic| diagnostic.get_code_list())
+    else {
+        return;
+    };
+
+    let mut tokens = code_list
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.kind() != LuaTokenKind::TkWhitespace.into());
+    let Some(first) = tokens.next() else {
+        return;
+    };
+    formatter.add_token_change(
+        LuaSyntaxId::from_token(&first),
+        TokenNodeChange::ReplaceWith(merged),
+    );
+    for token in tokens {
+        formatter.add_token_change(LuaSyntaxId::from_token(&token), TokenNodeChange::Remove);
+    }
+}
+
+/// The parts of a `---@param` or `---@field` line that are aligned in columns
+#[derive(Debug)]
+struct TagColumns {
+    name_tokens: Vec<LuaSyntaxToken>,
+    type_tokens: Vec<LuaSyntaxToken>,
+    description_start: Option<LuaSyntaxToken>,
+}
+
+fn align_tag_lines(formatter: &mut LuaFormatter, lines: &[DocLine]) {
+    let mut index = 0;
+    while index < lines.len() {
+        let Some(kind) = get_alignable_kind(&lines[index]) else {
+            index += 1;
+            continue;
+        };
+
+        let mut group = Vec::new();
+        while index < lines.len() && get_alignable_kind(&lines[index]) == Some(kind) {
+            if let Some(columns) = get_tag_columns(&lines[index]) {
+                group.push(columns);
+            }
+            index += 1;
+        }
+
+        if group.len() > 1 {
+            align_group(formatter, &group);
+        }
+    }
+}
+
+fn get_alignable_kind(line: &DocLine) -> Option<LuaSyntaxKind> {
+    let kind: LuaSyntaxKind = line.tag.as_ref()?.kind().into();
+    matches!(
+        kind,
+        LuaSyntaxKind::DocTagParam | LuaSyntaxKind::DocTagField
+    )
+    .then_some(kind)
+}
+
+fn get_tag_columns(line: &DocLine) -> Option<TagColumns> {
+    let tag = line.tag.as_ref()?;
+    let doc_type = if let Some(param) = LuaDocTagParam::cast(tag.clone()) {
+        param.get_type()?
+    } else {
+        LuaDocTagField::cast(tag.clone())?.get_type()?
+    };
+
+    let type_range = doc_type.get_range();
+    let mut name_tokens = Vec::new();
+    let mut type_tokens = Vec::new();
+    // the first token is the tag keyword itself
+    for token in tag
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .skip(1)
+        .filter(|token| token.kind() != LuaTokenKind::TkWhitespace.into())
+    {
+        let start = token.text_range().start();
+        if start < type_range.start() {
+            name_tokens.push(token);
+        } else if type_range.contains_range(token.text_range()) {
+            type_tokens.push(token);
+        } else {
+            return None;
+        }
+    }
+
+    if name_tokens.is_empty() || type_tokens.is_empty() {
+        return None;
+    }
+
+    Some(TagColumns {
+        name_tokens,
+        type_tokens,
+        description_start: line
+            .description
+            .as_ref()
+            .and_then(|description| description.first_token()),
+    })
+}
+
+fn align_group(formatter: &mut LuaFormatter, group: &[TagColumns]) {
+    for columns in group {
+        formatter.set_space_before(LuaSyntaxId::from_token(&columns.name_tokens[0]), 1);
+        for token in &columns.name_tokens[1..] {
+            if has_whitespace_before(token) {
+                formatter.set_space_before(LuaSyntaxId::from_token(token), 1);
+            }
+        }
+    }
+
+    let name_widths = group
+        .iter()
+        .map(|columns| get_tokens_width(formatter, &columns.name_tokens))
+        .collect::<Vec<_>>();
+    let type_widths = group
+        .iter()
+        .map(|columns| get_tokens_width(formatter, &columns.type_tokens))
+        .collect::<Vec<_>>();
+    let max_name_width = name_widths.iter().copied().max().unwrap_or(0);
+    let max_type_width = group
+        .iter()
+        .zip(&type_widths)
+        .filter(|(columns, _)| columns.description_start.is_some())
+        .map(|(_, width)| *width)
+        .max()
+        .unwrap_or(0);
+
+    for (i, columns) in group.iter().enumerate() {
+        formatter.set_space_before(
+            LuaSyntaxId::from_token(&columns.type_tokens[0]),
+            1 + max_name_width - name_widths[i],
+        );
+        if let Some(description_start) = &columns.description_start {
+            formatter.set_space_before(
+                LuaSyntaxId::from_token(description_start),
+                1 + max_type_width - type_widths[i],
+            );
+        }
+    }
+}
+
+/// The printed width of a run of tokens on one line, using the spacing decided so far
+fn get_tokens_width(formatter: &LuaFormatter, tokens: &[LuaSyntaxToken]) -> usize {
+    let mut width = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        width += token.text().chars().count();
+        if i == 0 {
+            continue;
+        }
+
+        let prev = &tokens[i - 1];
+        let spaces = formatter
+            .get_space_before(&LuaSyntaxId::from_token(token))
+            .or_else(|| formatter.get_space_after(&LuaSyntaxId::from_token(prev)));
+        width += spaces
+            .unwrap_or_else(|| usize::from(token.text_range().start() - prev.text_range().end()));
+    }
+    width
+}
+
+fn has_whitespace_before(token: &LuaSyntaxToken) -> bool {
+    token
+        .prev_token()
+        .is_some_and(|prev| prev.kind() == LuaTokenKind::TkWhitespace.into())
+}
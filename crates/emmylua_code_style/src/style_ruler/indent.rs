@@ -0,0 +1,289 @@
+use emmylua_parser::{
+    LuaAstNode, LuaCallExpr, LuaSyntaxId, LuaSyntaxKind, LuaSyntaxNode, LuaSyntaxToken,
+    LuaTokenKind,
+};
+use rowan::{NodeOrToken, WalkEvent};
+
+use crate::{format::LuaFormatter, styles::LuaCodeStyle};
+
+use super::StyleRuler;
+
+/// Computes the indentation level of every token that starts a line.
+///
+/// Line breaks are kept as written; only the leading whitespace of each line is decided
+/// here. Blocks indent their statements one level deeper than the line they were opened
+/// on, brackets that are broken across lines indent their content, and a statement that
+/// continues on the following lines indents the continuation by one level.
+pub struct IndentRuler;
+
+impl StyleRuler for IndentRuler {
+    fn apply_style(formatter: &mut LuaFormatter, _: &LuaCodeStyle) {
+        let root = formatter.get_root().syntax().clone();
+        let mut context = IndentContext::new(&root);
+        for event in root.preorder_with_tokens() {
+            match event {
+                WalkEvent::Enter(NodeOrToken::Node(node)) => context.enter_node(&node),
+                WalkEvent::Leave(NodeOrToken::Node(node)) => context.leave_node(&node),
+                WalkEvent::Enter(NodeOrToken::Token(token)) => {
+                    let syntax_id = LuaSyntaxId::from_token(&token);
+                    let line_break = formatter.has_line_break_before(&syntax_id);
+                    if let Some(level) = context.visit_token(&token, line_break) {
+                        formatter.set_indent(syntax_id, level);
+                    }
+                }
+                WalkEvent::Leave(NodeOrToken::Token(_)) => {}
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameKind {
+    Root,
+    /// Statements of a block, indented one level deeper than the line the block opened on
+    Block,
+    /// Content of a bracket pair, closed by the token of the given kind
+    Bracket(LuaTokenKind),
+    /// A statement or closure, lines after its first one are continuations
+    Node,
+    /// A comment keeps all of its lines at the indentation of its first line
+    Comment,
+}
+
+#[derive(Debug)]
+struct Frame {
+    kind: FrameKind,
+    node: Option<LuaSyntaxNode>,
+    /// The indentation of the line this frame started on, unknown until its first token
+    line_indent: Option<usize>,
+}
+
+struct IndentContext {
+    frames: Vec<Frame>,
+    line_start: bool,
+    current_line_indent: usize,
+}
+
+impl IndentContext {
+    fn new(root: &LuaSyntaxNode) -> Self {
+        Self {
+            frames: vec![Frame {
+                kind: FrameKind::Root,
+                node: Some(root.clone()),
+                line_indent: Some(0),
+            }],
+            line_start: true,
+            current_line_indent: 0,
+        }
+    }
+
+    fn enter_node(&mut self, node: &LuaSyntaxNode) {
+        let kind = match node.kind().into() {
+            LuaSyntaxKind::Block => {
+                let is_top_level = node
+                    .parent()
+                    .is_none_or(|parent| parent.kind() == LuaSyntaxKind::Chunk.into())
+                    || self.is_root(node);
+                if is_top_level {
+                    return;
+                }
+                // the block is indented relative to the first line of the statement owning
+                // it, so a condition wrapped over several lines does not shift the body
+                let owner_indent = self
+                    .frames
+                    .last()
+                    .filter(|frame| frame.node.is_some() && frame.node == node.parent())
+                    .and_then(|frame| frame.line_indent);
+                self.frames.push(Frame {
+                    kind: FrameKind::Block,
+                    node: Some(node.clone()),
+                    line_indent: Some(owner_indent.unwrap_or(self.current_line_indent)),
+                });
+                return;
+            }
+            LuaSyntaxKind::Comment => FrameKind::Comment,
+            LuaSyntaxKind::ClosureExpr
+            | LuaSyntaxKind::ElseIfClauseStat
+            | LuaSyntaxKind::ElseClauseStat => FrameKind::Node,
+            kind if is_stat(kind) => FrameKind::Node,
+            kind if is_expr_chain_root(node, kind) => FrameKind::Node,
+            _ => return,
+        };
+
+        if self.is_root(node) {
+            return;
+        }
+
+        self.frames.push(Frame {
+            kind,
+            node: Some(node.clone()),
+            line_indent: None,
+        });
+    }
+
+    fn leave_node(&mut self, node: &LuaSyntaxNode) {
+        if !self
+            .frames
+            .iter()
+            .any(|frame| frame.node.as_ref() == Some(node))
+        {
+            return;
+        }
+
+        while let Some(frame) = self.frames.pop() {
+            if frame.node.as_ref() == Some(node) {
+                break;
+            }
+        }
+    }
+
+    fn visit_token(&mut self, token: &LuaSyntaxToken, line_break: bool) -> Option<usize> {
+        let kind: LuaTokenKind = token.kind().into();
+        match kind {
+            LuaTokenKind::TkEndOfLine => {
+                self.line_start = true;
+                return None;
+            }
+            LuaTokenKind::TkWhitespace => return None,
+            _ => {}
+        }
+
+        let mut result = None;
+        if self.line_start || line_break {
+            let level = self.compute_indent(kind, self.frames.len() - 1);
+            self.current_line_indent = level;
+            self.line_start = false;
+            result = Some(level);
+        }
+
+        for frame in self.frames.iter_mut().rev() {
+            if frame.line_indent.is_some() {
+                break;
+            }
+            frame.line_indent = Some(self.current_line_indent);
+        }
+
+        if self.in_comment() {
+            return result;
+        }
+
+        if let Some(close_kind) = get_close_bracket(kind) {
+            self.frames.push(Frame {
+                kind: FrameKind::Bracket(close_kind),
+                node: None,
+                line_indent: Some(self.current_line_indent),
+            });
+        } else if self
+            .frames
+            .last()
+            .is_some_and(|top| top.kind == FrameKind::Bracket(kind))
+        {
+            self.frames.pop();
+        }
+
+        result
+    }
+
+    fn compute_indent(&self, token_kind: LuaTokenKind, index: usize) -> usize {
+        let frame = &self.frames[index];
+        let Some(line_indent) = frame.line_indent else {
+            // the first token of a frame is positioned by the frame that contains it
+            return if index == 0 {
+                0
+            } else {
+                self.compute_indent(token_kind, index - 1)
+            };
+        };
+
+        match frame.kind {
+            FrameKind::Root => line_indent,
+            FrameKind::Block => line_indent + 1,
+            FrameKind::Bracket(close_kind) if close_kind == token_kind => line_indent,
+            FrameKind::Bracket(_) => line_indent + 1,
+            FrameKind::Comment => line_indent,
+            FrameKind::Node if is_block_end_keyword(token_kind) => line_indent,
+            // a wrapped condition is indented one level deeper than the body it guards
+            FrameKind::Node if frame.node.as_ref().is_some_and(is_block_condition) => {
+                line_indent + 2
+            }
+            FrameKind::Node => line_indent + 1,
+        }
+    }
+
+    fn in_comment(&self) -> bool {
+        self.frames
+            .iter()
+            .rev()
+            .any(|frame| frame.kind == FrameKind::Comment)
+    }
+
+    fn is_root(&self, node: &LuaSyntaxNode) -> bool {
+        self.frames[0].node.as_ref() == Some(node)
+    }
+}
+
+fn is_stat(kind: LuaSyntaxKind) -> bool {
+    matches!(
+        kind,
+        LuaSyntaxKind::EmptyStat
+            | LuaSyntaxKind::LocalStat
+            | LuaSyntaxKind::LocalFuncStat
+            | LuaSyntaxKind::IfStat
+            | LuaSyntaxKind::WhileStat
+            | LuaSyntaxKind::DoStat
+            | LuaSyntaxKind::ForStat
+            | LuaSyntaxKind::ForRangeStat
+            | LuaSyntaxKind::RepeatStat
+            | LuaSyntaxKind::FuncStat
+            | LuaSyntaxKind::LabelStat
+            | LuaSyntaxKind::BreakStat
+            | LuaSyntaxKind::ReturnStat
+            | LuaSyntaxKind::GotoStat
+            | LuaSyntaxKind::CallExprStat
+            | LuaSyntaxKind::AssignStat
+            | LuaSyntaxKind::GlobalStat
+            | LuaSyntaxKind::UnknownStat
+    )
+}
+
+/// Operator chains and call chains indent their continuation lines relative to their own
+/// first line, so they stay readable when they are nested in brackets
+fn is_expr_chain_root(node: &LuaSyntaxNode, kind: LuaSyntaxKind) -> bool {
+    let parent_kind: Option<LuaSyntaxKind> = node.parent().map(|parent| parent.kind().into());
+    match kind {
+        LuaSyntaxKind::BinaryExpr => parent_kind != Some(LuaSyntaxKind::BinaryExpr),
+        kind if kind == LuaSyntaxKind::IndexExpr || LuaCallExpr::can_cast(kind) => !parent_kind
+            .is_some_and(|parent_kind| {
+                parent_kind == LuaSyntaxKind::IndexExpr || LuaCallExpr::can_cast(parent_kind)
+            }),
+        _ => false,
+    }
+}
+
+/// Whether the node is the condition of an `if`, `elseif` or `while`, which is followed by
+/// the body of the statement
+fn is_block_condition(node: &LuaSyntaxNode) -> bool {
+    node.parent().is_some_and(|parent| {
+        matches!(
+            parent.kind().into(),
+            LuaSyntaxKind::IfStat | LuaSyntaxKind::ElseIfClauseStat | LuaSyntaxKind::WhileStat
+        )
+    })
+}
+
+fn is_block_end_keyword(kind: LuaTokenKind) -> bool {
+    matches!(
+        kind,
+        LuaTokenKind::TkEnd | LuaTokenKind::TkElse | LuaTokenKind::TkElseIf | LuaTokenKind::TkUntil
+    )
+}
+
+/// The closing bracket kind if the token opens a bracket pair whose content may be indented
+fn get_close_bracket(kind: LuaTokenKind) -> Option<LuaTokenKind> {
+    match kind {
+        LuaTokenKind::TkLeftParen => Some(LuaTokenKind::TkRightParen),
+        LuaTokenKind::TkLeftBrace => Some(LuaTokenKind::TkRightBrace),
+        LuaTokenKind::TkLeftBracket => Some(LuaTokenKind::TkRightBracket),
+        _ => None,
+    }
+}
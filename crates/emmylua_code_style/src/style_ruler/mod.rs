@@ -1,10 +1,15 @@
 mod basic_space;
+mod doc_comment;
+mod indent;
 
 use crate::{format::LuaFormatter, styles::LuaCodeStyle};
 
-#[allow(unused)]
+pub use indent::IndentRuler;
+
 pub fn apply_styles(formatter: &mut LuaFormatter, styles: &LuaCodeStyle) {
     apply_style::<basic_space::BasicSpaceRuler>(formatter, styles);
+    apply_style::<doc_comment::DocCommentRuler>(formatter, styles);
+    apply_style::<indent::IndentRuler>(formatter, styles);
 }
 
 pub trait StyleRuler {
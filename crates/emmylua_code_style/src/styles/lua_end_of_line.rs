@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum LuaEndOfLine {
+    /// Keep the line ending used by the first line of the source
+    #[default]
+    Auto,
+    /// Always use `\n`
+    LF,
+    /// Always use `\r\n`
+    CRLF,
+}
+
+impl LuaEndOfLine {
+    /// Resolve the line ending text to use for the given source
+    pub fn resolve(&self, source: &str) -> &'static str {
+        match self {
+            LuaEndOfLine::LF => "\n",
+            LuaEndOfLine::CRLF => "\r\n",
+            LuaEndOfLine::Auto => match source.find('\n') {
+                Some(pos) if pos > 0 && source.as_bytes()[pos - 1] == b'\r' => "\r\n",
+                _ => "\n",
+            },
+        }
+    }
+}
@@ -13,3 +13,21 @@ impl Default for LuaIndent {
         LuaIndent::Space(4)
     }
 }
+
+impl LuaIndent {
+    /// The text for `level` levels of indentation
+    pub fn indent_text(&self, level: usize) -> String {
+        match self {
+            LuaIndent::Tab => "\t".repeat(level),
+            LuaIndent::Space(size) => " ".repeat(size * level),
+        }
+    }
+
+    /// The visual width of one level of indentation, tabs count as 4 columns
+    pub fn width(&self) -> usize {
+        match self {
+            LuaIndent::Tab => 4,
+            LuaIndent::Space(size) => *size,
+        }
+    }
+}
@@ -1,12 +1,45 @@
+mod lua_end_of_line;
 mod lua_indent;
 
-use lua_indent::LuaIndent;
+pub use lua_end_of_line::LuaEndOfLine;
+pub use lua_indent::LuaIndent;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LuaCodeStyle {
     /// The indentation style to use
     pub indent: LuaIndent,
     /// The maximum width of a line before wrapping
     pub max_line_width: usize,
+    /// The line ending to use
+    pub end_of_line: LuaEndOfLine,
+    /// The maximum number of consecutive blank lines to keep
+    pub max_blank_lines: usize,
+    /// Whether the formatted text ends with a line ending
+    pub insert_final_newline: bool,
+    /// Whether binary operators such as `+` and `==` are surrounded by spaces
+    pub space_around_binary_operator: bool,
+    /// Whether non-empty table braces are padded with spaces, `{ 1, 2 }` instead of `{1, 2}`
+    pub space_inside_table_braces: bool,
+    /// Whether the `|` of union types in doc comments is surrounded by spaces, `a | b` instead of `a|b`
+    pub space_around_doc_union: bool,
+    /// Whether consecutive `---@param` and `---@field` lines are aligned in columns
+    pub align_doc_tags: bool,
+}
+
+impl Default for LuaCodeStyle {
+    fn default() -> Self {
+        Self {
+            indent: LuaIndent::default(),
+            max_line_width: 120,
+            end_of_line: LuaEndOfLine::default(),
+            max_blank_lines: 1,
+            insert_final_newline: true,
+            space_around_binary_operator: true,
+            space_inside_table_braces: true,
+            space_around_doc_union: false,
+            align_doc_tags: true,
+        }
+    }
 }
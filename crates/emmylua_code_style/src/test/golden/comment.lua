@@ -0,0 +1,21 @@
+#!/usr/bin/env lua
+-- header comment
+
+
+
+local a = 1   -- aligned
+local bb = 2  -- trailing
+local function f()
+        ---@param x number
+        ---@return string
+  --[[ long
+  comment ]]
+    return x -- result
+
+
+end
+if a then
+
+    print(a)
+
+end
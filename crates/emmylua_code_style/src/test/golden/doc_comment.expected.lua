@@ -0,0 +1,17 @@
+---@class Point
+---@field x     number     the horizontal position
+---@field label string|nil the text shown next to it
+---@field color string|nil
+local Point = {}
+
+---@diagnostic disable-next-line: missing-parameter, undefined-global, unused-local
+local unused = foo
+
+---Moves the point
+---@param dx         number offset on the x axis
+---@param distance_y number offset on the y axis
+---@param options    table|nil
+---@return Point
+function Point:move(dx, distance_y, options)
+    return self
+end
@@ -0,0 +1,23 @@
+local function foo(x, y)
+    if x then
+        return x
+    elseif not y then
+        return -x
+    else
+        print("neither")
+    end
+    for i = 1, 10 do
+        print(i)
+    end
+    while x do x = x - 1 end
+    repeat
+        x = x + 1
+    until x > 10
+    call(function()
+        return 1
+    end)
+    local s = "a"
+        .. "b"
+    obj:method(1)
+        :other(2)
+end
@@ -0,0 +1,16 @@
+local   a,b = 1 ,2
+local c=a+b*2-(a/b)
+local d = a..b
+local t = {1,2,3}
+local e = { }
+local f = t [1]
+local g = -a
+local h = # t
+local i = not(a==b)
+local j <const> = a~=b and a<=b or a>=b
+t . x = 1
+t:method( 1 , 2 )
+print ( "x" ) ;
+::label::
+goto label
+local k = - -a
@@ -0,0 +1,12 @@
+local obj = {
+    name = "x",
+    nested = {
+        a = 1,
+    },
+    [1] = 2;
+    list = { 1,
+        2 },
+}
+call({
+    a = 1,
+})
@@ -0,0 +1,11 @@
+local config = { name = "emmylua-analyzer", version = "0.11.0", description = "a language server", features = { "format", "check" } }
+local result = some_module.some_function(first_argument_value, second_argument_value, third_argument_value, fourth_value)
+local message = "the quick brown fox jumps " .. "over the lazy dog " .. "and keeps running through the forest " .. name .. suffix
+local value = builder:with_name("emmylua-analyzer"):with_version("0.11.0"):with_feature("formatting"):with_feature("check"):build()
+local short = { 1, 2, 3 }
+if first_condition_is_true_enough and second_condition_is_true_enough and third_condition_is_also_true_enough_as_well then
+    print("done")
+end
+local list = { "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta", "iota", "kappa", "lambda", "mu", "nu"; }
+register_handler("on_document_changed", function(event) return event.changed_line_count + document_line_offset_value + extra_line_padding + trailing_margin end, handler_options)
+local node = document.root_node.children_collection:find_by_name("first_child_node_name", "second_argument_value_name", true):get_attribute("name")
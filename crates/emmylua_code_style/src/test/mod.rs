@@ -1,6 +1,53 @@
 #[cfg(test)]
-mod test {
-    use crate::{reformat_lua_code, styles::LuaCodeStyle};
+mod tests {
+    use std::path::PathBuf;
+
+    use emmylua_parser::{LuaAst, LuaAstNode, LuaIfStat, LuaParser, ParserConfig};
+
+    use crate::{reformat_lua_code, reformat_node_with_indent, styles::LuaCodeStyle};
+
+    /// Formats every `golden/<name>.lua` and compares it with `golden/<name>.expected.lua`
+    #[test]
+    fn test_golden_files() {
+        let golden_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/test/golden");
+        let styles = LuaCodeStyle::default();
+        let mut failures = Vec::new();
+        let mut count = 0;
+        for entry in std::fs::read_dir(&golden_dir).unwrap() {
+            let path = entry.unwrap().path();
+            let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+            let Some(name) = file_name.strip_suffix(".lua") else {
+                continue;
+            };
+            if name.ends_with(".expected") {
+                continue;
+            }
+
+            count += 1;
+            let input = std::fs::read_to_string(&path).unwrap();
+            let expected =
+                std::fs::read_to_string(golden_dir.join(format!("{}.expected.lua", name))).unwrap();
+            let formatted = reformat_lua_code(&input, &styles);
+            if formatted != expected {
+                failures.push(format!(
+                    "{}:\n--- expected\n{}\n--- actual\n{}",
+                    name, expected, formatted
+                ));
+                continue;
+            }
+
+            let reformatted = reformat_lua_code(&formatted, &styles);
+            if reformatted != formatted {
+                failures.push(format!(
+                    "{} is not stable:\n--- first\n{}\n--- second\n{}",
+                    name, formatted, reformatted
+                ));
+            }
+        }
+
+        assert!(count > 0, "no golden files found in {:?}", golden_dir);
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
 
     #[test]
     fn test_reformat_lua_code() {
@@ -13,6 +60,85 @@ mod test {
 
         let styles = LuaCodeStyle::default();
         let formatted_code = reformat_lua_code(code, &styles);
-        println!("Formatted code:\n{}", formatted_code);
+        assert_eq!(
+            formatted_code,
+            "local a = 1\nlocal b = 2\nlocal c = a + b\nprint(c)\n"
+        );
+    }
+
+    #[test]
+    fn test_indent_style() {
+        let code = "if a then\nprint(a)\nend\n";
+        let styles = LuaCodeStyle {
+            indent: crate::LuaIndent::Tab,
+            ..Default::default()
+        };
+        assert_eq!(
+            reformat_lua_code(code, &styles),
+            "if a then\n\tprint(a)\nend\n"
+        );
+    }
+
+    #[test]
+    fn test_end_of_line() {
+        let code = "local a = 1\r\nlocal b = 2\r\n";
+        let styles = LuaCodeStyle::default();
+        assert_eq!(reformat_lua_code(code, &styles), code);
+
+        let styles = LuaCodeStyle {
+            end_of_line: crate::LuaEndOfLine::LF,
+            ..Default::default()
+        };
+        assert_eq!(
+            reformat_lua_code(code, &styles),
+            "local a = 1\nlocal b = 2\n"
+        );
+    }
+
+    #[test]
+    fn test_max_line_width() {
+        let code = "call(first, second, third)\n";
+        let styles = LuaCodeStyle {
+            max_line_width: 20,
+            ..Default::default()
+        };
+        assert_eq!(
+            reformat_lua_code(code, &styles),
+            "call(\n    first,\n    second,\n    third\n)\n"
+        );
+
+        let styles = LuaCodeStyle {
+            max_line_width: 0,
+            ..Default::default()
+        };
+        assert_eq!(reformat_lua_code(code, &styles), code);
+    }
+
+    #[test]
+    fn test_doc_comment_style() {
+        let code = "---@param a string|nil\n---@param bb integer\nlocal function f(a, bb) end\n";
+        let styles = LuaCodeStyle {
+            space_around_doc_union: true,
+            align_doc_tags: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            reformat_lua_code(code, &styles),
+            "---@param a string | nil\n---@param bb integer\nlocal function f(a, bb) end\n"
+        );
+    }
+
+    #[test]
+    fn test_reformat_node_with_indent() {
+        let code = "if a then\n  if b then\nprint(b,c)\n  end\nend\n";
+        let tree = LuaParser::parse(code, ParserConfig::default());
+        let inner_if = tree
+            .get_chunk_node()
+            .descendants::<LuaIfStat>()
+            .nth(1)
+            .unwrap();
+        let formatted =
+            reformat_node_with_indent(&LuaAst::LuaIfStat(inner_if), "  ", &LuaCodeStyle::default());
+        assert_eq!(formatted, "if b then\n      print(b, c)\n  end");
     }
 }
@@ -0,0 +1,26 @@
+use emmylua_parser::{LuaSyntaxKind, LuaSyntaxToken, LuaTokenKind};
+
+/// Whether the token is followed by the body of a block or bracket, which is indented one
+/// level deeper than the line of the token
+pub fn is_block_open(token: &LuaSyntaxToken) -> bool {
+    match token.kind().into() {
+        LuaTokenKind::TkThen
+        | LuaTokenKind::TkDo
+        | LuaTokenKind::TkRepeat
+        | LuaTokenKind::TkElse
+        | LuaTokenKind::TkLeftBrace
+        | LuaTokenKind::TkLeftParen => true,
+        LuaTokenKind::TkRightParen => token
+            .parent()
+            .is_some_and(|parent| parent.kind() == LuaSyntaxKind::ParamList.into()),
+        _ => false,
+    }
+}
+
+/// The leading whitespace of the line that contains the byte `offset` of `text`
+pub fn get_line_indent(text: &str, offset: usize) -> &str {
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[line_start..];
+    let indent_len = line.len() - line.trim_start_matches([' ', '\t']).len();
+    &line[..indent_len]
+}
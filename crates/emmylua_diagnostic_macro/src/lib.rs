@@ -2,7 +2,10 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{Data, DeriveInput, Fields, Ident, parse_macro_input, spanned::Spanned};
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Ident, Lit, Meta, MetaNameValue,
+    parse_macro_input, spanned::Spanned,
+};
 
 // Convert enum variant names to kebab-case
 fn to_kebab_case(ident: &Ident) -> String {
@@ -19,6 +22,26 @@ fn to_kebab_case(ident: &Ident) -> String {
     result
 }
 
+// Join the `///` lines of a variant into one line
+fn get_doc_comment(attrs: &[Attribute]) -> String {
+    let mut lines = Vec::new();
+    for attr in attrs {
+        if !attr.path().is_ident("doc") {
+            continue;
+        }
+        if let Meta::NameValue(MetaNameValue {
+            value: Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }),
+            ..
+        }) = &attr.meta
+        {
+            lines.push(lit.value().trim().to_string());
+        }
+    }
+    lines.join(" ")
+}
+
 #[proc_macro_derive(LuaDiagnosticMacro)]
 pub fn lua_diagnostic_macro(input: TokenStream) -> TokenStream {
     // Parse the input TokenStream into DeriveInput
@@ -38,6 +61,7 @@ pub fn lua_diagnostic_macro(input: TokenStream) -> TokenStream {
     // Generate get_name / FromStr / Display / all based on variants
     let mut variant_idents = Vec::new();
     let mut variant_strings = Vec::new();
+    let mut variant_descriptions = Vec::new();
 
     for variant in variants.iter() {
         let variant_ident = &variant.ident;
@@ -46,6 +70,7 @@ pub fn lua_diagnostic_macro(input: TokenStream) -> TokenStream {
             let kebab_case_string = to_kebab_case(variant_ident);
             variant_idents.push(variant_ident);
             variant_strings.push(kebab_case_string);
+            variant_descriptions.push(get_doc_comment(&variant.attrs));
         } else {
             // Only unit variants are supported
             return syn::Error::new(variant.ident.span(), "Only unit variants supported")
@@ -64,6 +89,17 @@ pub fn lua_diagnostic_macro(input: TokenStream) -> TokenStream {
             }
         });
 
+    // Build match arms for get_description()
+    let get_description_arms =
+        variant_idents
+            .iter()
+            .zip(variant_descriptions.iter())
+            .map(|(ident, description)| {
+                quote! {
+                    #name::#ident => #description
+                }
+            });
+
     // Build match arms for FromStr
     let from_str_arms = variant_idents
         .iter()
@@ -89,6 +125,13 @@ pub fn lua_diagnostic_macro(input: TokenStream) -> TokenStream {
                 }
             }
 
+            // Return the doc comment of the variant
+            pub fn get_description(&self) -> &str {
+                match self {
+                    #(#get_description_arms),*,
+                }
+            }
+
             // Return all variants
             pub fn all() -> Vec<#name> {
                 vec![
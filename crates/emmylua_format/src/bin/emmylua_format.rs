@@ -0,0 +1,12 @@
+use clap::Parser;
+use emmylua_format::{cmd_args::CmdArgs, run_format};
+use mimalloc::MiMalloc;
+use std::error::Error;
+
+#[global_allocator]
+static GLOBAL: MiMalloc = MiMalloc;
+
+fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
+    let cmd_args = CmdArgs::parse();
+    run_format(cmd_args)
+}
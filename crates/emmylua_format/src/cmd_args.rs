@@ -0,0 +1,43 @@
+#[cfg(feature = "cli")]
+use clap::Parser;
+
+use std::path::PathBuf;
+
+#[allow(unused)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(Parser))]
+#[cfg_attr(feature = "cli", command(version))]
+pub struct CmdArgs {
+    /// Configuration file paths.
+    /// If not provided, both ".emmyrc.json" and ".luarc.json" will be searched in the workspace
+    /// directory
+    #[cfg_attr(feature = "cli", arg(short, long, value_delimiter = ','))]
+    pub config: Option<Vec<PathBuf>>,
+
+    /// Path to the workspace directory
+    #[cfg_attr(feature = "cli", arg(num_args = 1..))]
+    pub workspace: Vec<PathBuf>,
+
+    /// Comma separated list of ignore patterns.
+    /// Patterns must follow glob syntax
+    #[cfg_attr(feature = "cli", arg(short, long, value_delimiter = ','))]
+    pub ignore: Option<Vec<String>>,
+
+    /// Path to a JSON file with the code style options.
+    /// If not provided, the default style is used
+    #[cfg_attr(feature = "cli", arg(short, long))]
+    pub style: Option<PathBuf>,
+
+    /// Do not write any files, print a diff of every file that is not formatted
+    /// and exit with a nonzero code if there is one
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub check: bool,
+
+    /// Do not write any files, print a diff of every file that is not formatted
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub diff: bool,
+
+    /// Verbose output
+    #[cfg_attr(feature = "cli", arg(long))]
+    pub verbose: bool,
+}
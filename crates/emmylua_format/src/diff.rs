@@ -0,0 +1,10 @@
+use similar::TextDiff;
+
+/// Renders the changes between the original and the formatted text as a unified diff
+pub fn unified_diff(path: &str, original: &str, formatted: &str) -> String {
+    TextDiff::from_lines(original, formatted)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
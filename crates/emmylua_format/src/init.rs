@@ -0,0 +1,26 @@
+use emmylua_code_style::LuaCodeStyle;
+use std::{error::Error, path::PathBuf};
+
+pub fn load_style(
+    style_path: Option<PathBuf>,
+) -> Result<LuaCodeStyle, Box<dyn Error + Sync + Send>> {
+    let Some(style_path) = style_path else {
+        return Ok(LuaCodeStyle::default());
+    };
+
+    let content = std::fs::read_to_string(&style_path).map_err(|err| {
+        format!(
+            "Failed to read style file \"{}\": {}",
+            style_path.display(),
+            err
+        )
+    })?;
+    let style = serde_json::from_str(&content).map_err(|err| {
+        format!(
+            "Failed to parse style file \"{}\": {}",
+            style_path.display(),
+            err
+        )
+    })?;
+    Ok(style)
+}
@@ -0,0 +1,232 @@
+pub mod cmd_args;
+mod diff;
+mod init;
+
+pub use cmd_args::*;
+use emmylua_code_style::{LuaCodeStyle, reformat_lua_code};
+use emmylua_parser::{LuaParser, ParserConfig};
+use std::{
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use emmylua_check::init::{collect_files, load_emmyrc, setup_logger};
+use emmylua_code_analysis::{LuaFileInfo, write_file_with_encoding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FormatMode {
+    /// Write the formatted text back to the files
+    Write,
+    /// Print a diff of the files that are not formatted
+    Diff,
+}
+
+pub fn run_format(cmd_args: CmdArgs) -> Result<(), Box<dyn Error + Sync + Send>> {
+    setup_logger(cmd_args.verbose);
+
+    let cwd = std::env::current_dir()?;
+    let mut workspaces: Vec<_> = cmd_args
+        .workspace
+        .into_iter()
+        .map(|workspace| {
+            if workspace.is_absolute() {
+                workspace
+            } else {
+                cwd.join(workspace)
+            }
+        })
+        .collect();
+    let main_path = workspaces
+        .first()
+        .ok_or("Failed to load workspace")?
+        .clone();
+
+    let emmyrc = load_emmyrc(&main_path, cmd_args.config);
+    for root in &emmyrc.workspace.workspace_roots {
+        workspaces.push(PathBuf::from_str(root).unwrap());
+    }
+    let styles = init::load_style(cmd_args.style)?;
+    let files: Vec<_> = collect_files(&workspaces, &emmyrc, cmd_args.ignore)
+        .into_iter()
+        .filter(|file| !file.path.ends_with(".editorconfig"))
+        .collect();
+
+    let mode = if cmd_args.check || cmd_args.diff {
+        FormatMode::Diff
+    } else {
+        FormatMode::Write
+    };
+    let unformatted = format_files(
+        &files,
+        &styles,
+        mode,
+        &main_path,
+        &emmyrc.workspace.encoding,
+        &mut std::io::stdout().lock(),
+    )?;
+
+    if cmd_args.check && unformatted > 0 {
+        return Err(format!("{} of {} files are not formatted", unformatted, files.len()).into());
+    }
+
+    if mode == FormatMode::Write {
+        eprintln!("Formatted {} of {} files", unformatted, files.len());
+    } else {
+        eprintln!("{} of {} files are not formatted", unformatted, files.len());
+    }
+    Ok(())
+}
+
+/// Formats the files and writes them back, or prints their diff to `out`. Returns the
+/// number of files that were not formatted.
+fn format_files(
+    files: &[LuaFileInfo],
+    styles: &LuaCodeStyle,
+    mode: FormatMode,
+    main_path: &Path,
+    encoding: &str,
+    out: &mut impl Write,
+) -> Result<usize, Box<dyn Error + Sync + Send>> {
+    let mut unformatted = 0;
+    for file in files {
+        let Some(formatted) = format_file(&file.content, styles) else {
+            log::warn!("Skipping \"{}\": it has syntax errors", file.path);
+            continue;
+        };
+        if formatted == file.content {
+            continue;
+        }
+
+        unformatted += 1;
+        match mode {
+            FormatMode::Write => {
+                log::info!("Formatting \"{}\"", file.path);
+                write_file_with_encoding(Path::new(&file.path), &formatted, encoding)?;
+            }
+            FormatMode::Diff => {
+                let path = relative_path(main_path, Path::new(&file.path));
+                write!(
+                    out,
+                    "{}",
+                    diff::unified_diff(&path, &file.content, &formatted)
+                )?;
+            }
+        }
+    }
+
+    Ok(unformatted)
+}
+
+/// Formats the text of a file, or returns `None` when it cannot be parsed
+fn format_file(content: &str, styles: &LuaCodeStyle) -> Option<String> {
+    let tree = LuaParser::parse(content, ParserConfig::default());
+    if !tree.get_errors().is_empty() {
+        return None;
+    }
+    Some(reformat_lua_code(content, styles))
+}
+
+fn relative_path(main_path: &Path, path: &Path) -> String {
+    path.strip_prefix(main_path)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_file(dir: &Path, name: &str, bytes: &[u8]) -> LuaFileInfo {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        LuaFileInfo {
+            path: path.to_string_lossy().to_string(),
+            content: String::from_utf8_lossy(bytes)
+                .trim_start_matches('\u{feff}')
+                .to_string(),
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("emmylua_format_{}_{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_diff_mode() {
+        let dir = temp_dir("diff");
+        let files = vec![
+            create_file(&dir, "a.lua", b"local a  =  1\n"),
+            create_file(&dir, "b.lua", b"local b = 2\n"),
+        ];
+        let mut out = Vec::new();
+        let unformatted = format_files(
+            &files,
+            &LuaCodeStyle::default(),
+            FormatMode::Diff,
+            &dir,
+            "utf-8",
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(unformatted, 1);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("--- a/a.lua\n+++ b/a.lua\n"));
+        assert!(out.contains("-local a  =  1\n+local a = 1\n"));
+        assert!(!out.contains("b.lua"));
+        // nothing is written in diff mode
+        assert_eq!(
+            std::fs::read(dir.join("a.lua")).unwrap(),
+            b"local a  =  1\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_mode_keeps_bom() {
+        let dir = temp_dir("write");
+        let files = vec![create_file(&dir, "a.lua", b"\xEF\xBB\xBFlocal a  =  1\n")];
+        let mut out = Vec::new();
+        let unformatted = format_files(
+            &files,
+            &LuaCodeStyle::default(),
+            FormatMode::Write,
+            &dir,
+            "utf-8",
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(unformatted, 1);
+        assert!(out.is_empty());
+        assert_eq!(
+            std::fs::read(dir.join("a.lua")).unwrap(),
+            b"\xEF\xBB\xBFlocal a = 1\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_syntax_error_is_skipped() {
+        let dir = temp_dir("skip");
+        let files = vec![create_file(&dir, "a.lua", b"local a  =  \n")];
+        let mut out = Vec::new();
+        let unformatted = format_files(
+            &files,
+            &LuaCodeStyle::default(),
+            FormatMode::Diff,
+            &dir,
+            "utf-8",
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(unformatted, 0);
+        assert!(out.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
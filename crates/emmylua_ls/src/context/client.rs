@@ -140,6 +140,11 @@ impl ClientProxy {
         self.send_notification("textDocument/publishDiagnostics", params);
     }
 
+    pub fn refresh_workspace_diagnostics(&self) {
+        let request_id = self.next_id();
+        self.send_request_no_wait(request_id, "workspace/diagnostic/refresh", ());
+    }
+
     pub async fn apply_edit(
         &self,
         params: ApplyWorkspaceEditParams,
@@ -2,6 +2,7 @@ use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use emmylua_code_analysis::{EmmyLuaAnalysis, FileId, Profile};
 use log::{debug, info};
+use lsp_types::ClientCapabilities;
 use tokio::sync::{Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
 
@@ -13,6 +14,18 @@ pub struct FileDiagnostic {
     status_bar: Arc<StatusBar>,
     diagnostic_tokens: Arc<Mutex<HashMap<FileId, CancellationToken>>>,
     workspace_diagnostic_token: Arc<Mutex<Option<CancellationToken>>>,
+    /// The client pulls the diagnostics itself, so they are never pushed
+    pull_mode: bool,
+    refresh_support: bool,
+}
+
+/// Clients which support `textDocument/diagnostic` pull the diagnostics, older clients
+/// get them pushed with `textDocument/publishDiagnostics`
+pub fn is_pull_diagnostic_supported(client_capabilities: &ClientCapabilities) -> bool {
+    client_capabilities
+        .text_document
+        .as_ref()
+        .is_some_and(|text_document| text_document.diagnostic.is_some())
 }
 
 impl FileDiagnostic {
@@ -20,17 +33,32 @@ impl FileDiagnostic {
         analysis: Arc<RwLock<EmmyLuaAnalysis>>,
         status_bar: Arc<StatusBar>,
         client: Arc<ClientProxy>,
+        client_capabilities: &ClientCapabilities,
     ) -> Self {
+        let refresh_support = client_capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.diagnostic.as_ref())
+            .and_then(|diagnostic| diagnostic.refresh_support)
+            .unwrap_or(false);
         Self {
             analysis,
             client,
             diagnostic_tokens: Arc::new(Mutex::new(HashMap::new())),
             workspace_diagnostic_token: Arc::new(Mutex::new(None)),
             status_bar,
+            pull_mode: is_pull_diagnostic_supported(client_capabilities),
+            refresh_support,
         }
     }
 
     pub async fn add_diagnostic_task(&self, file_id: FileId, interval: u64) {
+        // the client pulls the diagnostics of the edited file, and of the files that
+        // depend on it, by itself
+        if self.pull_mode {
+            return;
+        }
+
         let mut tokens = self.diagnostic_tokens.lock().await;
 
         if let Some(token) = tokens.get(&file_id) {
@@ -79,6 +107,11 @@ impl FileDiagnostic {
 
     // todo add message show
     pub async fn add_files_diagnostic_task(&self, file_ids: Vec<FileId>, interval: u64) {
+        if self.pull_mode {
+            self.add_refresh_task(interval).await;
+            return;
+        }
+
         for file_id in file_ids {
             self.add_diagnostic_task(file_id, interval).await;
         }
@@ -100,6 +133,11 @@ impl FileDiagnostic {
         interval: u64,
         silent: bool,
     ) {
+        if self.pull_mode {
+            self.add_refresh_task(interval).await;
+            return;
+        }
+
         let mut token = self.workspace_diagnostic_token.lock().await;
         if let Some(token) = token.as_ref() {
             token.cancel();
@@ -125,6 +163,35 @@ impl FileDiagnostic {
         });
     }
 
+    /// Asks the client to pull the diagnostics again, for changes the client does not
+    /// know about, like files changed on disk or a new config
+    async fn add_refresh_task(&self, interval: u64) {
+        if !self.refresh_support {
+            return;
+        }
+
+        let mut token = self.workspace_diagnostic_token.lock().await;
+        if let Some(token) = token.as_ref() {
+            token.cancel();
+        }
+
+        let cancel_token = CancellationToken::new();
+        token.replace(cancel_token.clone());
+        drop(token);
+
+        let client = self.client.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_millis(interval)) => {
+                    client.refresh_workspace_diagnostics();
+                }
+                _ = cancel_token.cancelled() => {
+                    debug!("cancel diagnostic refresh");
+                }
+            }
+        });
+    }
+
     #[allow(unused)]
     pub async fn cancel_all(&self) {
         let mut tokens = self.diagnostic_tokens.lock().await;
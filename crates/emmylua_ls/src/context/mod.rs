@@ -8,7 +8,7 @@ mod workspace_manager;
 pub use client::ClientProxy;
 pub use client_id::{ClientId, get_client_id};
 use emmylua_code_analysis::EmmyLuaAnalysis;
-pub use file_diagnostic::FileDiagnostic;
+pub use file_diagnostic::{FileDiagnostic, is_pull_diagnostic_supported};
 use lsp_server::{Connection, ErrorCode, Message, RequestId, Response};
 use lsp_types::ClientCapabilities;
 pub use snapshot::ServerContextSnapshot;
@@ -21,6 +21,8 @@ pub use workspace_manager::WorkspaceFileMatcher;
 pub use workspace_manager::WorkspaceManager;
 pub use workspace_manager::load_emmy_config;
 
+use crate::handlers::SemanticTokensCache;
+
 pub struct ServerContext {
     #[allow(unused)]
     conn: Connection,
@@ -31,6 +33,7 @@ pub struct ServerContext {
     workspace_manager: Arc<RwLock<WorkspaceManager>>,
     status_bar: Arc<StatusBar>,
     client_capabilities: Arc<ClientCapabilities>,
+    semantic_tokens_cache: Arc<Mutex<SemanticTokensCache>>,
 }
 
 impl ServerContext {
@@ -46,6 +49,7 @@ impl ServerContext {
             analysis.clone(),
             status_bar.clone(),
             client.clone(),
+            &client_capabilities,
         ));
         let workspace_manager = Arc::new(RwLock::new(WorkspaceManager::new(
             analysis.clone(),
@@ -63,6 +67,7 @@ impl ServerContext {
             workspace_manager,
             status_bar,
             client_capabilities,
+            semantic_tokens_cache: Arc::new(Mutex::new(SemanticTokensCache::default())),
         }
     }
 
@@ -74,6 +79,7 @@ impl ServerContext {
             workspace_manager: self.workspace_manager.clone(),
             status_bar: self.status_bar.clone(),
             client_capabilities: self.client_capabilities.clone(),
+            semantic_tokens_cache: self.semantic_tokens_cache.clone(),
         }
     }
 
@@ -1,9 +1,11 @@
 use lsp_types::ClientCapabilities;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 use emmylua_code_analysis::EmmyLuaAnalysis;
 
+use crate::handlers::SemanticTokensCache;
+
 use super::{
     client::ClientProxy, file_diagnostic::FileDiagnostic, status_bar::StatusBar,
     workspace_manager::WorkspaceManager,
@@ -17,4 +19,5 @@ pub struct ServerContextSnapshot {
     pub workspace_manager: Arc<RwLock<WorkspaceManager>>,
     pub status_bar: Arc<StatusBar>,
     pub client_capabilities: Arc<ClientCapabilities>,
+    pub semantic_tokens_cache: Arc<Mutex<SemanticTokensCache>>,
 }
@@ -18,6 +18,7 @@ pub enum ProgressTask {
     DiagnoseWorkspace = 1,
     #[allow(dead_code)]
     RefreshIndex = 2,
+    GenerateDoc = 3,
 }
 
 impl ProgressTask {
@@ -30,6 +31,7 @@ impl ProgressTask {
             ProgressTask::LoadWorkspace => "Load workspace",
             ProgressTask::DiagnoseWorkspace => "Diagnose workspace",
             ProgressTask::RefreshIndex => "Refresh index",
+            ProgressTask::GenerateDoc => "Generate annotations",
         }
     }
 }
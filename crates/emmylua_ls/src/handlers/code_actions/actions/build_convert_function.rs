@@ -0,0 +1,296 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use emmylua_code_analysis::{
+    FileId, LuaCompilation, LuaMemberId, LuaSemanticDeclId, SemanticDeclLevel, SemanticModel,
+};
+use emmylua_parser::{
+    LuaAstNode, LuaAstToken, LuaCallExpr, LuaClosureExpr, LuaExpr, LuaFuncStat, LuaIndexExpr,
+    LuaLocalFuncStat, LuaLocalStat, LuaNameExpr, LuaSyntaxKind, LuaTokenKind, LuaVarExpr,
+};
+use lsp_types::{CodeAction, CodeActionKind, CodeActionOrCommand, Range, TextEdit, WorkspaceEdit};
+use rowan::TextRange;
+
+use super::build_generate_doc::get_function_at_header;
+
+/// Converts `function M.f(self, a)` to `function M:f(a)` and back, with the calls which
+/// pass the table itself
+pub fn build_convert_method(
+    semantic_model: &SemanticModel,
+    compilation: &LuaCompilation,
+    actions: &mut Vec<CodeActionOrCommand>,
+    range: Range,
+) -> Option<()> {
+    let closure = get_function_at_header(semantic_model, range)?;
+    let func_stat = closure.get_parent::<LuaFuncStat>()?;
+    let Some(LuaVarExpr::IndexExpr(func_name)) = func_stat.get_func_name() else {
+        return None;
+    };
+    let index_token = func_name.get_index_token()?;
+    let to_colon = index_token.is_dot();
+    if !to_colon && !index_token.is_colon() {
+        return None;
+    }
+
+    let document = semantic_model.get_document();
+    // `Uri` has interior mutability, so the edits are kept by file
+    let mut changes: HashMap<FileId, Vec<TextEdit>> = HashMap::new();
+    let mut text_edits = vec![TextEdit {
+        range: document.to_lsp_range(index_token.get_range())?,
+        new_text: if to_colon { ":" } else { "." }.to_string(),
+    }];
+    let params_list = closure.get_params_list()?;
+    let params = params_list.get_params().collect::<Vec<_>>();
+    if to_colon {
+        let first = params.first()?;
+        if first.get_name_token()?.get_name_text() != "self" {
+            return None;
+        }
+        let end = match params.get(1) {
+            Some(second) => second.get_position(),
+            None => first.get_range().end(),
+        };
+        text_edits.push(TextEdit {
+            range: document.to_lsp_range(TextRange::new(first.get_position(), end))?,
+            new_text: String::new(),
+        });
+    } else {
+        let l_paren = params_list.token_by_kind(LuaTokenKind::TkLeftParen)?;
+        let offset = l_paren.get_range().end();
+        text_edits.push(TextEdit {
+            range: document.to_lsp_range(TextRange::empty(offset))?,
+            new_text: if params.is_empty() { "self" } else { "self, " }.to_string(),
+        });
+    }
+    changes.insert(semantic_model.get_file_id(), text_edits);
+
+    if let Some(LuaSemanticDeclId::Member(member_id)) = semantic_model.find_decl(
+        closure.syntax().clone().into(),
+        SemanticDeclLevel::default(),
+    ) {
+        add_call_edits(
+            semantic_model,
+            compilation,
+            member_id,
+            to_colon,
+            &mut changes,
+        );
+    }
+
+    let vfs = semantic_model.get_db().get_vfs();
+    let title = if to_colon {
+        t!("Convert to method with `:`")
+    } else {
+        t!("Convert to function with `.`")
+    };
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: title.to_string(),
+        kind: Some(CodeActionKind::REFACTOR_REWRITE),
+        edit: Some(WorkspaceEdit {
+            changes: Some(
+                changes
+                    .into_iter()
+                    .filter_map(|(file_id, text_edits)| Some((vfs.get_uri(&file_id)?, text_edits)))
+                    .collect(),
+            ),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+
+    Some(())
+}
+
+/// Converts `local function f` to `local f = function` and back
+pub fn build_convert_local_function(
+    semantic_model: &SemanticModel,
+    actions: &mut Vec<CodeActionOrCommand>,
+    range: Range,
+) -> Option<()> {
+    let closure = get_function_at_header(semantic_model, range)?;
+    let parent = closure.syntax().parent()?;
+    let (name, title) = if let Some(local_func_stat) = LuaLocalFuncStat::cast(parent.clone()) {
+        let name = local_func_stat.get_local_name()?.get_name_token()?;
+        // the function can only call itself when it is declared before its body
+        if is_name_used(&closure, name.get_name_text()) {
+            return None;
+        }
+        (name, t!("Convert to local variable"))
+    } else {
+        let local_stat = LuaLocalStat::cast(parent.clone())?;
+        let mut local_names = local_stat.get_local_name_list();
+        let local_name = local_names.next()?;
+        if local_names.next().is_some()
+            || local_stat.get_value_exprs().count() != 1
+            || local_name.get_attrib().is_some()
+        {
+            return None;
+        }
+        let name = local_name.get_name_token()?;
+        // in `local function f` these would be the function itself
+        if is_name_used(&closure, name.get_name_text()) {
+            return None;
+        }
+        (name, t!("Convert to local function"))
+    };
+
+    let params_list = closure.get_params_list()?;
+    let new_text = if parent.kind() == LuaSyntaxKind::LocalFuncStat.into() {
+        format!("local {} = function", name.get_name_text())
+    } else {
+        format!("local function {}", name.get_name_text())
+    };
+    let document = semantic_model.get_document();
+    let text_edit = TextEdit {
+        range: document.to_lsp_range(TextRange::new(
+            parent.text_range().start(),
+            params_list.get_position(),
+        ))?,
+        new_text,
+    };
+
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: title.to_string(),
+        kind: Some(CodeActionKind::REFACTOR_REWRITE),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(document.get_uri(), vec![text_edit])])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+
+    Some(())
+}
+
+fn is_name_used(closure: &LuaClosureExpr, name: &str) -> bool {
+    closure
+        .descendants::<LuaNameExpr>()
+        .any(|name_expr| name_expr.get_name_text().is_some_and(|text| text == name))
+}
+
+/// Rewrites `t.f(t, a)` to `t:f(a)`, or `t:f(a)` to `t.f(t, a)`. The other calls mean the
+/// same either way, so they are kept.
+fn add_call_edits(
+    semantic_model: &SemanticModel,
+    compilation: &LuaCompilation,
+    member_id: LuaMemberId,
+    to_colon: bool,
+    changes: &mut HashMap<FileId, Vec<TextEdit>>,
+) -> Option<()> {
+    let db = semantic_model.get_db();
+    let member = db.get_member_index().get_member(&member_id)?;
+    let index_refs = db
+        .get_reference_index()
+        .get_index_references(member.get_key())?;
+
+    let semantic_id = LuaSemanticDeclId::Member(member_id);
+    let mut semantic_models = HashMap::new();
+    for index_ref in index_refs {
+        let semantic_model = match semantic_models.entry(index_ref.file_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match compilation.get_semantic_model(index_ref.file_id) {
+                Some(semantic_model) => entry.insert(semantic_model),
+                None => continue,
+            },
+        };
+        let root = semantic_model.get_root();
+        let Some(node) = index_ref.value.to_node_from_root(root.syntax()) else {
+            continue;
+        };
+        if !semantic_model.is_reference_to(
+            node.clone(),
+            semantic_id.clone(),
+            SemanticDeclLevel::default(),
+        ) {
+            continue;
+        }
+        let Some(index_expr) = LuaIndexExpr::cast(node) else {
+            continue;
+        };
+        let Some(call_expr) = index_expr.get_parent::<LuaCallExpr>() else {
+            continue;
+        };
+        if call_expr
+            .get_prefix_expr()
+            .is_none_or(|prefix| prefix.syntax() != index_expr.syntax())
+        {
+            continue;
+        }
+
+        if let Some(text_edits) = get_call_edits(semantic_model, &call_expr, &index_expr, to_colon)
+        {
+            changes
+                .entry(index_ref.file_id)
+                .or_default()
+                .extend(text_edits);
+        }
+    }
+
+    Some(())
+}
+
+fn get_call_edits(
+    semantic_model: &SemanticModel,
+    call_expr: &LuaCallExpr,
+    index_expr: &LuaIndexExpr,
+    to_colon: bool,
+) -> Option<Vec<TextEdit>> {
+    let document = semantic_model.get_document();
+    let index_token = index_expr.get_index_token()?;
+    // only a name can be repeated as the first argument without evaluating it twice
+    let LuaExpr::NameExpr(prefix) = index_expr.get_prefix_expr()? else {
+        return None;
+    };
+    let prefix_name = prefix.get_name_text()?;
+    let args_list = call_expr.get_args_list()?;
+    let args = args_list.get_args().collect::<Vec<_>>();
+
+    let mut text_edits = Vec::new();
+    if to_colon {
+        if call_expr.is_colon_call() {
+            return None;
+        }
+        let Some(LuaExpr::NameExpr(first)) = args.first() else {
+            return None;
+        };
+        if first.get_name_text()? != prefix_name {
+            return None;
+        }
+        let end = match args.get(1) {
+            Some(second) => second.get_position(),
+            None => first.get_range().end(),
+        };
+        text_edits.push(TextEdit {
+            range: document.to_lsp_range(TextRange::new(first.get_position(), end))?,
+            new_text: String::new(),
+        });
+    } else {
+        if !call_expr.is_colon_call() {
+            return None;
+        }
+        match args_list.token_by_kind(LuaTokenKind::TkLeftParen) {
+            Some(l_paren) => {
+                let separator = if args.is_empty() { "" } else { ", " };
+                text_edits.push(TextEdit {
+                    range: document.to_lsp_range(TextRange::empty(l_paren.get_range().end()))?,
+                    new_text: format!("{}{}", prefix_name, separator),
+                });
+            }
+            // `t:f "a"` and `t:f {}`
+            None => {
+                let args_text = document.get_text_slice(args_list.get_range());
+                let range =
+                    TextRange::new(index_expr.get_range().end(), args_list.get_range().end());
+                text_edits.push(TextEdit {
+                    range: document.to_lsp_range(range)?,
+                    new_text: format!("({}, {})", prefix_name, args_text),
+                });
+            }
+        }
+    }
+    text_edits.push(TextEdit {
+        range: document.to_lsp_range(index_token.get_range())?,
+        new_text: if to_colon { ":" } else { "." }.to_string(),
+    });
+
+    Some(text_edits)
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::handlers::command::make_auto_doc_tag_command;
-use emmylua_code_analysis::SemanticModel;
+use emmylua_code_analysis::{SemanticModel, get_unused_fix};
 use emmylua_parser::{LuaAstNode, LuaExpr};
 use lsp_types::{CodeAction, CodeActionKind, CodeActionOrCommand, Range, TextEdit, WorkspaceEdit};
 use rowan::{NodeOrToken, TokenAtOffset};
@@ -79,3 +79,32 @@ pub fn build_add_doc_tag(
 
     Some(())
 }
+
+pub fn build_unused_fix(
+    semantic_model: &SemanticModel,
+    actions: &mut Vec<CodeActionOrCommand>,
+    range: Range,
+) -> Option<()> {
+    let document = semantic_model.get_document();
+    let (edit_range, new_text) = get_unused_fix(
+        semantic_model.get_db(),
+        semantic_model.get_file_id(),
+        document.to_rowan_range(range)?,
+    )?;
+    let text_edit = TextEdit {
+        range: document.to_lsp_range(edit_range)?,
+        new_text,
+    };
+
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: t!("Prefix with an underscore").to_string(),
+        kind: Some(CodeActionKind::QUICKFIX),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(document.get_uri(), vec![text_edit])])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+
+    Some(())
+}
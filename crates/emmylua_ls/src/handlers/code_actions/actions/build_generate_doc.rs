@@ -0,0 +1,429 @@
+use std::collections::{HashMap, HashSet, hash_map::Entry};
+
+use emmylua_code_analysis::{
+    DbIndex, FileId, InFiled, LuaCompilation, LuaSemanticDeclId, LuaSignatureId, LuaType,
+    RenderLevel, SemanticDeclLevel, SemanticModel, TypeOps, VariadicType, get_closure_expr_comment,
+    humanize_type,
+};
+use emmylua_parser::{
+    LuaAstNode, LuaCallExpr, LuaClosureExpr, LuaDocTagParam, LuaDocTagReturn, LuaExpr, LuaStat,
+    LuaSyntaxKind, LuaSyntaxNode,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Position, Range, TextEdit, WorkspaceEdit,
+};
+use rowan::{TextRange, TokenAtOffset};
+
+use crate::handlers::command::make_generate_doc_command;
+
+/// Writes the missing `---@param` and `---@return` annotations of the function at the
+/// cursor, with the types inferred from its calls and its body
+pub fn build_generate_doc(
+    semantic_model: &SemanticModel,
+    compilation: &LuaCompilation,
+    actions: &mut Vec<CodeActionOrCommand>,
+    range: Range,
+) -> Option<()> {
+    let closure = get_function_at_header(semantic_model, range)?;
+    let text_edit =
+        generate_function_doc(semantic_model, compilation, &closure, &mut HashMap::new())?;
+    let document = semantic_model.get_document();
+
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: t!("Generate annotations").to_string(),
+        kind: Some(CodeActionKind::REFACTOR_REWRITE),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(document.get_uri(), vec![text_edit])])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+
+    if is_global_function(semantic_model, &closure) {
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: t!("Generate annotations for all global functions").to_string(),
+            kind: Some(CodeActionKind::REFACTOR_REWRITE),
+            command: Some(make_generate_doc_command(&t!(
+                "Generate annotations for all global functions"
+            ))),
+            ..Default::default()
+        }));
+    }
+
+    Some(())
+}
+
+/// The function whose header, the part before its body, holds the cursor
+pub(super) fn get_function_at_header(
+    semantic_model: &SemanticModel,
+    range: Range,
+) -> Option<LuaClosureExpr> {
+    let document = semantic_model.get_document();
+    let offset = document.get_offset(range.start.line as usize, range.start.character as usize)?;
+    let root = semantic_model.get_root();
+    if offset > root.syntax().text_range().end() {
+        return None;
+    }
+    let token = match root.syntax().token_at_offset(offset) {
+        TokenAtOffset::Single(token) => token,
+        TokenAtOffset::Between(left, _) => left,
+        TokenAtOffset::None => return None,
+    };
+
+    // only on the header of the function, not in its body
+    let closure = token.parent_ancestors().find_map(|node| {
+        if LuaStat::can_cast(node.kind().into()) {
+            node.children().find_map(LuaClosureExpr::cast)
+        } else {
+            LuaClosureExpr::cast(node)
+        }
+    })?;
+    if closure
+        .get_block()
+        .is_some_and(|block| block.get_position() <= offset)
+    {
+        return None;
+    }
+
+    Some(closure)
+}
+
+/// The annotations of every global function in the file which lacks some. The semantic
+/// models of the files which call the functions are kept in `semantic_models`, so they
+/// are built once for all the files.
+pub fn generate_global_docs<'a>(
+    compilation: &'a LuaCompilation,
+    file_id: FileId,
+    semantic_models: &mut HashMap<FileId, SemanticModel<'a>>,
+) -> Vec<TextEdit> {
+    let Some(semantic_model) = compilation.get_semantic_model(file_id) else {
+        return Vec::new();
+    };
+
+    semantic_model
+        .get_root()
+        .descendants::<LuaClosureExpr>()
+        .filter(|closure| is_global_function(&semantic_model, closure))
+        .filter_map(|closure| {
+            generate_function_doc(&semantic_model, compilation, &closure, semantic_models)
+        })
+        .collect()
+}
+
+fn generate_function_doc<'a>(
+    semantic_model: &SemanticModel,
+    compilation: &'a LuaCompilation,
+    closure: &LuaClosureExpr,
+    semantic_models: &mut HashMap<FileId, SemanticModel<'a>>,
+) -> Option<TextEdit> {
+    let stat = get_function_stat(closure)?;
+    // the same comment the `MissingGlobalDoc` and `IncompleteSignatureDoc` checks read
+    let comment = get_closure_expr_comment(closure);
+    let doc_param_names = comment
+        .iter()
+        .flat_map(|comment| comment.children::<LuaDocTagParam>())
+        .filter_map(|param| {
+            if param.is_vararg() {
+                return Some("...".to_string());
+            }
+            Some(param.get_name_token()?.get_name_text().to_string())
+        })
+        .collect::<HashSet<_>>();
+    let has_doc_return = comment
+        .iter()
+        .any(|comment| comment.children::<LuaDocTagReturn>().next().is_some());
+
+    let db = semantic_model.get_db();
+    let file_id = semantic_model.get_file_id();
+    let signature = db
+        .get_signature_index()
+        .get(&LuaSignatureId::from_closure(file_id, closure))?;
+    let param_types = infer_param_types(
+        semantic_model,
+        compilation,
+        closure,
+        &signature.params,
+        signature.is_colon_define,
+        semantic_models,
+    );
+
+    let mut lines = Vec::new();
+    for (param, typ) in signature.params.iter().zip(param_types) {
+        if param == "_" || doc_param_names.contains(param) {
+            continue;
+        }
+        lines.push(format!(
+            "---@param {} {}",
+            param,
+            render_type(semantic_model, typ)
+        ));
+    }
+    if !has_doc_return {
+        for return_doc in &signature.return_docs {
+            // the inferred returns are a single multi value
+            match &return_doc.type_ref {
+                LuaType::Variadic(variadic) => match variadic.as_ref() {
+                    VariadicType::Multi(types) => {
+                        for typ in types {
+                            let typ = render_type(semantic_model, Some(typ.clone()));
+                            lines.push(format!("---@return {}", typ));
+                        }
+                    }
+                    VariadicType::Base(typ) => {
+                        let typ = render_type(semantic_model, Some(typ.clone()));
+                        lines.push(format!("---@return {}...", typ));
+                    }
+                },
+                typ => {
+                    let typ = render_type(semantic_model, Some(typ.clone()));
+                    lines.push(format!("---@return {}", typ));
+                }
+            }
+        }
+    }
+    if lines.is_empty() {
+        return None;
+    }
+
+    // the annotations go right above the statement, after the existing comment
+    let document = semantic_model.get_document();
+    let position = stat.get_position();
+    let line = document.get_line(position)?;
+    let line_start = document.get_line_range(line)?.start();
+    let indent = document.get_text_slice(TextRange::new(line_start, position));
+    if !indent.trim().is_empty() {
+        return None;
+    }
+    let new_text = lines
+        .iter()
+        .map(|line| format!("{}{}\n", indent, line))
+        .collect::<String>();
+
+    Some(TextEdit {
+        range: Range::new(Position::new(line as u32, 0), Position::new(line as u32, 0)),
+        new_text,
+    })
+}
+
+/// The statement which defines the function and owns its comment
+fn get_function_stat(closure: &LuaClosureExpr) -> Option<LuaStat> {
+    let parent = closure.syntax().parent()?;
+    match parent.kind().into() {
+        LuaSyntaxKind::FuncStat
+        | LuaSyntaxKind::LocalFuncStat
+        | LuaSyntaxKind::LocalStat
+        | LuaSyntaxKind::AssignStat => LuaStat::cast(parent),
+        _ => None,
+    }
+}
+
+fn is_global_function(semantic_model: &SemanticModel, closure: &LuaClosureExpr) -> bool {
+    let Some(LuaSemanticDeclId::LuaDecl(decl_id)) = semantic_model.find_decl(
+        closure.syntax().clone().into(),
+        SemanticDeclLevel::default(),
+    ) else {
+        return false;
+    };
+
+    get_function_stat(closure).is_some()
+        && semantic_model
+            .get_db()
+            .get_decl_index()
+            .get_decl(&decl_id)
+            .is_some_and(|decl| decl.is_global())
+}
+
+/// The union of the argument types at every call of the function
+fn infer_param_types<'a>(
+    semantic_model: &SemanticModel,
+    compilation: &'a LuaCompilation,
+    closure: &LuaClosureExpr,
+    params: &[String],
+    is_colon_define: bool,
+    semantic_models: &mut HashMap<FileId, SemanticModel<'a>>,
+) -> Vec<Option<LuaType>> {
+    let mut param_types = vec![None; params.len()];
+
+    let db = semantic_model.get_db();
+    let semantic_decl = semantic_model.find_decl(
+        closure.syntax().clone().into(),
+        SemanticDeclLevel::default(),
+    );
+    for reference in get_function_references(semantic_model, semantic_decl.clone()) {
+        let semantic_model = match semantic_models.entry(reference.file_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match compilation.get_semantic_model(reference.file_id) {
+                Some(semantic_model) => entry.insert(semantic_model),
+                None => continue,
+            },
+        };
+        // the index references are by key, keep the ones of this member
+        if let Some(LuaSemanticDeclId::Member(member_id)) = &semantic_decl {
+            let is_reference = find_node(semantic_model, reference.value).is_some_and(|node| {
+                semantic_model.is_reference_to(
+                    node,
+                    LuaSemanticDeclId::Member(*member_id),
+                    SemanticDeclLevel::default(),
+                )
+            });
+            if !is_reference {
+                continue;
+            }
+        }
+        let Some(arg_types) =
+            infer_call_arg_types(semantic_model, reference.value, is_colon_define)
+        else {
+            continue;
+        };
+
+        for (i, param_type) in param_types.iter_mut().enumerate() {
+            if params[i] == "..." {
+                continue;
+            }
+            // a missing argument is nil
+            let Some(arg_type) = arg_types.get(i).cloned().unwrap_or(Some(LuaType::Nil)) else {
+                continue;
+            };
+            let arg_type = decay_type(db, arg_type);
+            *param_type = Some(match param_type.take() {
+                Some(typ) => TypeOps::Union.apply(db, &typ, &arg_type),
+                None => arg_type,
+            });
+        }
+    }
+
+    param_types
+}
+
+fn get_function_references(
+    semantic_model: &SemanticModel,
+    semantic_decl: Option<LuaSemanticDeclId>,
+) -> Vec<InFiled<TextRange>> {
+    let db = semantic_model.get_db();
+    let reference_index = db.get_reference_index();
+    let mut references = Vec::new();
+    match semantic_decl {
+        Some(LuaSemanticDeclId::LuaDecl(decl_id)) => {
+            let Some(decl) = db.get_decl_index().get_decl(&decl_id) else {
+                return references;
+            };
+            if decl.is_local() {
+                if let Some(decl_refs) =
+                    reference_index.get_decl_references(&decl_id.file_id, &decl_id)
+                {
+                    for cell in &decl_refs.cells {
+                        references.push(InFiled::new(decl_id.file_id, cell.range));
+                    }
+                }
+            } else if let Some(global_refs) = reference_index.get_global_references(decl.get_name())
+            {
+                for global_ref in global_refs {
+                    references.push(InFiled::new(
+                        global_ref.file_id,
+                        global_ref.value.get_range(),
+                    ));
+                }
+            }
+        }
+        Some(LuaSemanticDeclId::Member(member_id)) => {
+            let Some(member) = db.get_member_index().get_member(&member_id) else {
+                return references;
+            };
+            if let Some(index_refs) = reference_index.get_index_references(member.get_key()) {
+                for index_ref in index_refs {
+                    references.push(InFiled::new(index_ref.file_id, index_ref.value.get_range()));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    references
+}
+
+/// The argument types of the call whose prefix is at `range`, by parameter
+fn infer_call_arg_types(
+    semantic_model: &SemanticModel,
+    range: TextRange,
+    is_colon_define: bool,
+) -> Option<Vec<Option<LuaType>>> {
+    let node = find_node(semantic_model, range)?;
+    let call_expr = node.parent().and_then(LuaCallExpr::cast)?;
+    if call_expr.get_prefix_expr()?.syntax() != &node {
+        return None;
+    }
+
+    let mut arg_types = Vec::new();
+    let mut args = call_expr
+        .get_args_list()?
+        .get_args()
+        .collect::<Vec<LuaExpr>>();
+    match (call_expr.is_colon_call(), is_colon_define) {
+        // `t:f()` of `function t.f(self)` passes `t` as the first parameter
+        (true, false) => {
+            let LuaExpr::IndexExpr(index_expr) = call_expr.get_prefix_expr()? else {
+                return None;
+            };
+            arg_types.push(infer_arg_type(
+                semantic_model,
+                index_expr.get_prefix_expr()?,
+            ));
+        }
+        // `t.f(t)` of `function t:f()` passes `self` first
+        (false, true) => {
+            if args.is_empty() {
+                return None;
+            }
+            args.remove(0);
+        }
+        _ => {}
+    }
+    for arg in args {
+        arg_types.push(infer_arg_type(semantic_model, arg));
+    }
+
+    Some(arg_types)
+}
+
+fn infer_arg_type(semantic_model: &SemanticModel, arg: LuaExpr) -> Option<LuaType> {
+    match semantic_model.infer_expr(arg).ok()? {
+        // the values of a call in the last argument are not counted
+        LuaType::Variadic(_) => None,
+        typ => Some(typ),
+    }
+}
+
+fn find_node(semantic_model: &SemanticModel, range: TextRange) -> Option<LuaSyntaxNode> {
+    let root = semantic_model.get_root();
+    let node = match root.syntax().covering_element(range) {
+        rowan::NodeOrToken::Node(node) => node,
+        rowan::NodeOrToken::Token(token) => token.parent()?,
+    };
+    node.ancestors().find(|node| node.text_range() == range)
+}
+
+/// The literal types of the values are too narrow for a parameter
+fn decay_type(db: &DbIndex, typ: LuaType) -> LuaType {
+    match typ {
+        LuaType::FloatConst(_) => LuaType::Number,
+        LuaType::IntegerConst(_) | LuaType::DocIntegerConst(_) => LuaType::Integer,
+        LuaType::StringConst(_) | LuaType::DocStringConst(_) => LuaType::String,
+        LuaType::BooleanConst(_) | LuaType::DocBooleanConst(_) => LuaType::Boolean,
+        LuaType::TableConst(_) => LuaType::Table,
+        LuaType::Union(union) => union
+            .into_vec()
+            .into_iter()
+            .map(|typ| decay_type(db, typ))
+            .reduce(|left, right| TypeOps::Union.apply(db, &left, &right))
+            .unwrap_or(LuaType::Unknown),
+        typ => typ,
+    }
+}
+
+fn render_type(semantic_model: &SemanticModel, typ: Option<LuaType>) -> String {
+    let db = semantic_model.get_db();
+    match typ.map(|typ| decay_type(db, typ)) {
+        None | Some(LuaType::Unknown) => "any".to_string(),
+        Some(typ) => humanize_type(db, &typ, RenderLevel::Simple),
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+use emmylua_code_analysis::{
+    LuaDecl, LuaDeclId, LuaSemanticDeclId, SemanticDeclLevel, SemanticModel,
+};
+use emmylua_parser::{
+    LuaAstNode, LuaCallExpr, LuaClosureExpr, LuaCommentOwner, LuaExpr, LuaLocalFuncStat,
+    LuaLocalName, LuaLocalStat, LuaNameExpr, LuaStat, LuaSyntaxKind, LuaSyntaxNode, LuaSyntaxToken,
+    LuaTokenKind,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Position, Range, TextEdit, WorkspaceEdit,
+};
+use rowan::{TextRange, TextSize, TokenAtOffset};
+
+/// Replaces the uses of a local which is assigned once with its value, and removes the
+/// local
+pub fn build_inline_variable(
+    semantic_model: &SemanticModel,
+    actions: &mut Vec<CodeActionOrCommand>,
+    range: Range,
+) -> Option<()> {
+    let token = get_name_token(semantic_model, range.start)?;
+    let decl = get_local_decl(semantic_model, &token)?;
+    if decl.is_param() {
+        return None;
+    }
+
+    let root = semantic_model.get_root();
+    let local_name = root
+        .syntax()
+        .covering_element(decl.get_range())
+        .ancestors()
+        .find_map(LuaLocalName::cast)?;
+    // `<close>` runs code when the local goes out of scope
+    if local_name
+        .get_attrib()
+        .is_some_and(|attrib| attrib.syntax().text().to_string().contains("close"))
+    {
+        return None;
+    }
+    let local_stat = local_name.syntax().parent().and_then(LuaLocalStat::cast)?;
+    if local_stat.get_local_name_list().count() != 1 || !local_stat.get_comments().is_empty() {
+        return None;
+    }
+    let mut values = local_stat.get_value_exprs();
+    let value = values.next()?;
+    if values.next().is_some() || !is_side_effect_free(semantic_model, &value) {
+        return None;
+    }
+
+    let uses = get_uses(semantic_model, &decl, local_name.get_range())?;
+    let decl_closure = get_closure(local_stat.syntax());
+    for use_node in &uses {
+        // the value would be evaluated later, when the closure is called
+        if get_closure(use_node) != decl_closure
+            || !resolves_the_same(semantic_model, &value, use_node.text_range().start())
+        {
+            return None;
+        }
+    }
+
+    let document = semantic_model.get_document();
+    let value_text = document.get_text_slice(value.get_range());
+    let mut text_edits = Vec::new();
+    for use_node in &uses {
+        let new_text = if needs_parens(&value, use_node) {
+            format!("({})", value_text)
+        } else {
+            value_text.to_string()
+        };
+        text_edits.push(TextEdit {
+            range: document.to_lsp_range(use_node.text_range())?,
+            new_text,
+        });
+    }
+    text_edits.push(TextEdit {
+        range: document.to_lsp_range(get_stat_line_range(semantic_model, &local_stat))?,
+        new_text: String::new(),
+    });
+
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: t!("Inline variable").to_string(),
+        kind: Some(CodeActionKind::REFACTOR_INLINE),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(document.get_uri(), text_edits)])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+
+    Some(())
+}
+
+/// Replaces a call of a local function which only returns an expression with that
+/// expression, with the arguments in place of the parameters
+pub fn build_inline_function(
+    semantic_model: &SemanticModel,
+    actions: &mut Vec<CodeActionOrCommand>,
+    range: Range,
+) -> Option<()> {
+    let token = get_name_token(semantic_model, range.start)?;
+    let name_expr = token.parent().and_then(LuaNameExpr::cast)?;
+    let call_expr = name_expr.syntax().parent().and_then(LuaCallExpr::cast)?;
+    if call_expr.get_prefix_expr()?.get_range() != name_expr.get_range()
+        || call_expr.is_colon_call()
+        || call_expr.syntax().parent()?.kind() == LuaSyntaxKind::CallExprStat.into()
+    {
+        return None;
+    }
+
+    let decl = get_local_decl(semantic_model, &token)?;
+    let closure = get_local_function(semantic_model, &decl)?;
+    get_uses(semantic_model, &decl, decl.get_range())?;
+
+    let mut params = Vec::new();
+    for param in closure.get_params_list()?.get_params() {
+        if param.is_dots() {
+            return None;
+        }
+        params.push(param.get_name_token()?.get_name_text().to_string());
+    }
+    let mut stats = closure.get_block()?.get_stats();
+    let Some(LuaStat::ReturnStat(return_stat)) = stats.next() else {
+        return None;
+    };
+    let mut return_exprs = return_stat.get_expr_list();
+    let return_expr = return_exprs.next()?;
+    if stats.next().is_some()
+        || return_exprs.next().is_some()
+        || return_expr
+            .syntax()
+            .descendants()
+            .any(|node| LuaClosureExpr::can_cast(node.kind().into()))
+    {
+        return None;
+    }
+
+    let args = call_expr.get_args_list()?.get_args().collect::<Vec<_>>();
+    if args.len() > params.len() {
+        return None;
+    }
+    // a call or `...` as the last argument can fill several parameters
+    if args.len() < params.len() && args.last().is_some_and(is_multi_value) {
+        return None;
+    }
+    let effect_args = args
+        .iter()
+        .filter(|arg| !is_side_effect_free(semantic_model, arg))
+        .count();
+    // the calls of the returned expression could run before the argument once it is inlined
+    let has_call = return_expr
+        .syntax()
+        .descendants()
+        .any(|node| LuaCallExpr::can_cast(node.kind().into()));
+    if effect_args > 1 || (effect_args == 1 && has_call) {
+        return None;
+    }
+
+    // the parameter uses in the returned expression, and the names which must mean the
+    // same thing at the call
+    let db = semantic_model.get_db();
+    let file_id = semantic_model.get_file_id();
+    let params_range = closure.get_params_list()?.get_range();
+    let mut param_uses: Vec<(usize, LuaNameExpr)> = Vec::new();
+    for name_expr in get_name_exprs(&return_expr) {
+        let decl_id = db
+            .get_reference_index()
+            .get_var_reference_decl(&file_id, name_expr.get_range());
+        let param_index = decl_id
+            .filter(|decl_id| params_range.contains(decl_id.position))
+            .and_then(|_| {
+                let name = name_expr.get_name_text()?;
+                params.iter().position(|param| *param == name)
+            });
+        match param_index {
+            Some(index) => param_uses.push((index, name_expr)),
+            None => {
+                let name = name_expr.get_name_text()?;
+                if get_visible_decl(semantic_model, &name, call_expr.get_position()) != decl_id {
+                    return None;
+                }
+            }
+        }
+    }
+    for (i, arg) in args.iter().enumerate() {
+        let use_count = param_uses.iter().filter(|(index, _)| *index == i).count();
+        if use_count != 1 && !is_side_effect_free(semantic_model, arg) {
+            return None;
+        }
+    }
+
+    let document = semantic_model.get_document();
+    let return_range = return_expr.get_range();
+    let mut text = document.get_text_slice(return_range).to_string();
+    param_uses.sort_by_key(|(_, name_expr)| name_expr.get_position());
+    for (index, name_expr) in param_uses.iter().rev() {
+        let arg_text = match args.get(*index) {
+            Some(arg) if needs_parens(arg, name_expr.syntax()) => {
+                format!("({})", document.get_text_slice(arg.get_range()))
+            }
+            Some(arg) => document.get_text_slice(arg.get_range()).to_string(),
+            None => "nil".to_string(),
+        };
+        let start = usize::from(name_expr.get_position() - return_range.start());
+        let end = start + usize::from(name_expr.get_range().len());
+        text.replace_range(start..end, &arg_text);
+    }
+    if needs_parens(&return_expr, call_expr.syntax()) {
+        text = format!("({})", text);
+    }
+
+    let text_edit = TextEdit {
+        range: document.to_lsp_range(call_expr.get_range())?,
+        new_text: text,
+    };
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: t!("Inline function").to_string(),
+        kind: Some(CodeActionKind::REFACTOR_INLINE),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(document.get_uri(), vec![text_edit])])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+
+    Some(())
+}
+
+fn get_name_token(semantic_model: &SemanticModel, position: Position) -> Option<LuaSyntaxToken> {
+    let document = semantic_model.get_document();
+    let offset = document.get_offset(position.line as usize, position.character as usize)?;
+    let root = semantic_model.get_root();
+    if offset > root.syntax().text_range().end() {
+        return None;
+    }
+
+    let token = match root.syntax().token_at_offset(offset) {
+        TokenAtOffset::Single(token) => token,
+        TokenAtOffset::Between(left, right) => {
+            if left.kind() == LuaTokenKind::TkName.into() {
+                left
+            } else {
+                right
+            }
+        }
+        TokenAtOffset::None => return None,
+    };
+    if token.kind() != LuaTokenKind::TkName.into() {
+        return None;
+    }
+
+    Some(token)
+}
+
+fn get_local_decl(semantic_model: &SemanticModel, token: &LuaSyntaxToken) -> Option<LuaDecl> {
+    let LuaSemanticDeclId::LuaDecl(decl_id) =
+        semantic_model.find_decl(token.clone().into(), SemanticDeclLevel::NoTrace)?
+    else {
+        return None;
+    };
+    let decl = semantic_model
+        .get_db()
+        .get_decl_index()
+        .get_decl(&decl_id)?
+        .clone();
+    if !decl.is_local() {
+        return None;
+    }
+
+    Some(decl)
+}
+
+/// The closure of `local function f` or `local f = function`
+fn get_local_function(semantic_model: &SemanticModel, decl: &LuaDecl) -> Option<LuaClosureExpr> {
+    let root = semantic_model.get_root();
+    let local_name = root
+        .syntax()
+        .covering_element(decl.get_range())
+        .ancestors()
+        .find_map(LuaLocalName::cast)?;
+    let parent = local_name.syntax().parent()?;
+    if let Some(local_func_stat) = LuaLocalFuncStat::cast(parent.clone()) {
+        return local_func_stat.get_closure();
+    }
+
+    let local_stat = LuaLocalStat::cast(parent)?;
+    if local_stat.get_local_name_list().count() != 1 {
+        return None;
+    }
+    match local_stat.get_value_exprs().next()? {
+        LuaExpr::ClosureExpr(closure) => Some(closure),
+        _ => None,
+    }
+}
+
+/// The name expressions which read the local, `None` when it is assigned again
+fn get_uses(
+    semantic_model: &SemanticModel,
+    decl: &LuaDecl,
+    decl_range: TextRange,
+) -> Option<Vec<LuaSyntaxNode>> {
+    let decl_id = decl.get_id();
+    let decl_refs = semantic_model
+        .get_db()
+        .get_reference_index()
+        .get_decl_references(&decl_id.file_id, &decl_id);
+    let root = semantic_model.get_root();
+    let mut uses = Vec::new();
+    for cell in decl_refs
+        .map(|decl_refs| decl_refs.cells.iter())
+        .into_iter()
+        .flatten()
+    {
+        if decl_range.contains_range(cell.range) {
+            continue;
+        }
+        if cell.is_write {
+            return None;
+        }
+
+        let name_expr = root
+            .syntax()
+            .covering_element(cell.range)
+            .ancestors()
+            .find_map(LuaNameExpr::cast)?;
+        uses.push(name_expr.syntax().clone());
+    }
+
+    Some(uses)
+}
+
+fn get_closure(node: &LuaSyntaxNode) -> Option<LuaSyntaxNode> {
+    node.ancestors()
+        .find(|node| LuaClosureExpr::can_cast(node.kind().into()))
+}
+
+fn get_name_exprs(expr: &LuaExpr) -> Vec<LuaNameExpr> {
+    let mut name_exprs = expr.descendants::<LuaNameExpr>().collect::<Vec<_>>();
+    match expr {
+        LuaExpr::NameExpr(name_expr) if !name_exprs.contains(name_expr) => {
+            name_exprs.push(name_expr.clone());
+        }
+        _ => {}
+    }
+    name_exprs
+}
+
+fn get_visible_decl(
+    semantic_model: &SemanticModel,
+    name: &str,
+    position: TextSize,
+) -> Option<LuaDeclId> {
+    semantic_model
+        .get_db()
+        .get_decl_index()
+        .get_decl_tree(&semantic_model.get_file_id())?
+        .find_local_decl(name, position)
+        .map(|decl| decl.get_id())
+}
+
+/// Whether the names in `expr` mean the same thing at `position`
+fn resolves_the_same(semantic_model: &SemanticModel, expr: &LuaExpr, position: TextSize) -> bool {
+    let db = semantic_model.get_db();
+    let file_id = semantic_model.get_file_id();
+    get_name_exprs(expr).iter().all(|name_expr| {
+        let Some(name) = name_expr.get_name_text() else {
+            return false;
+        };
+        let decl_id = db
+            .get_reference_index()
+            .get_var_reference_decl(&file_id, name_expr.get_range());
+        get_visible_decl(semantic_model, &name, position) == decl_id
+    })
+}
+
+/// Literals and locals which are never assigned again, which give the same value wherever
+/// they are read. A global can be assigned anywhere, and `#t` or `a + b` can change when a
+/// table is changed or by metamethods.
+fn is_side_effect_free(semantic_model: &SemanticModel, expr: &LuaExpr) -> bool {
+    match expr {
+        LuaExpr::LiteralExpr(_) => !is_multi_value(expr),
+        LuaExpr::NameExpr(name_expr) => {
+            let db = semantic_model.get_db();
+            let file_id = semantic_model.get_file_id();
+            db.get_reference_index()
+                .get_var_reference_decl(&file_id, name_expr.get_range())
+                .is_some_and(|decl_id| {
+                    db.get_reference_index()
+                        .get_decl_references(&file_id, &decl_id)
+                        .is_none_or(|decl_refs| decl_refs.cells.iter().all(|cell| !cell.is_write))
+                })
+        }
+        LuaExpr::ParenExpr(paren_expr) => paren_expr
+            .get_expr()
+            .is_some_and(|expr| is_side_effect_free(semantic_model, &expr)),
+        _ => false,
+    }
+}
+
+fn is_multi_value(expr: &LuaExpr) -> bool {
+    match expr {
+        LuaExpr::CallExpr(_) => true,
+        LuaExpr::LiteralExpr(literal) => literal
+            .syntax()
+            .first_token()
+            .is_some_and(|token| token.kind() == LuaTokenKind::TkDots.into()),
+        _ => false,
+    }
+}
+
+/// Whether `expr` has to be put in parentheses to replace `node`
+fn needs_parens(expr: &LuaExpr, node: &LuaSyntaxNode) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let parent_kind: LuaSyntaxKind = parent.kind().into();
+    let is_prefix = matches!(
+        parent_kind,
+        LuaSyntaxKind::IndexExpr | LuaSyntaxKind::CallExpr
+    ) && parent.first_child().as_ref() == Some(node);
+    match expr {
+        LuaExpr::NameExpr(_) | LuaExpr::ParenExpr(_) | LuaExpr::IndexExpr(_) => false,
+        LuaExpr::CallExpr(_) => false,
+        LuaExpr::BinaryExpr(_) | LuaExpr::UnaryExpr(_) => {
+            is_prefix
+                || matches!(
+                    parent_kind,
+                    LuaSyntaxKind::BinaryExpr | LuaSyntaxKind::UnaryExpr
+                )
+        }
+        _ => is_prefix,
+    }
+}
+
+/// The lines of the statement, when nothing else is on them
+fn get_stat_line_range(semantic_model: &SemanticModel, stat: &LuaLocalStat) -> TextRange {
+    let document = semantic_model.get_document();
+    let text = document.get_text();
+    let range = stat.get_range();
+    let start = usize::from(range.start());
+    let end = usize::from(range.end());
+
+    let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i + 1);
+    if !text[line_start..start].trim().is_empty() || !text[end..line_end].trim().is_empty() {
+        return range;
+    }
+
+    TextRange::new(
+        TextSize::new(line_start as u32),
+        TextSize::new(line_end as u32),
+    )
+}
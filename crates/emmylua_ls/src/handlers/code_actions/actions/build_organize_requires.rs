@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use emmylua_code_analysis::{LuaDeclId, SemanticModel, get_duplicate_requires, is_decl_used};
+use emmylua_parser::{
+    LuaAstNode, LuaCommentOwner, LuaExpr, LuaLiteralToken, LuaLocalStat, LuaStat,
+};
+use lsp_types::{CodeAction, CodeActionKind, CodeActionOrCommand, TextEdit, WorkspaceEdit};
+use rowan::{TextRange, TextSize};
+
+struct RequireItem {
+    decl_id: LuaDeclId,
+    name: String,
+    module_path: String,
+    call_range: TextRange,
+    /// The lines of the statement and its comments
+    range: TextRange,
+}
+
+impl RequireItem {
+    /// `local _ = require("x")` is kept for the side effects of loading the module
+    fn is_side_effect(&self) -> bool {
+        self.name.starts_with('_')
+    }
+}
+
+/// Sorts the requires at the top of the file, removes the unused ones and merges the
+/// ones of the same module. The requires kept for their side effects stay in place, the
+/// ones between them are sorted.
+pub fn build_organize_requires(
+    semantic_model: &SemanticModel,
+    actions: &mut Vec<CodeActionOrCommand>,
+) -> Option<()> {
+    let items = collect_require_items(semantic_model)?;
+    let region = TextRange::new(items.first()?.range.start(), items.last()?.range.end());
+    let document = semantic_model.get_document();
+    let db = semantic_model.get_db();
+    let file_id = semantic_model.get_file_id();
+    let decl_tree = db.get_decl_index().get_decl_tree(&file_id)?;
+    // the first require of a module, for the ones the `DuplicateRequire` check reports
+    let first_requires = get_duplicate_requires(semantic_model)
+        .into_iter()
+        .map(|(call_expr, first)| (call_expr.get_range(), first.get_range()))
+        .collect::<HashMap<_, _>>();
+    let get_first_require = |item: &RequireItem| {
+        first_requires
+            .get(&item.call_range)
+            .copied()
+            .unwrap_or(item.call_range)
+    };
+
+    let mut text_edits = Vec::new();
+    let mut kept: Vec<&RequireItem> = Vec::new();
+    for item in &items {
+        let decl = db.get_decl_index().get_decl(&item.decl_id)?;
+        if !item.is_side_effect() && !is_decl_used(decl, db.get_reference_index()) {
+            continue;
+        }
+
+        // the uses of a duplicate are renamed to the first local of the module, when that
+        // one is visible at all of them
+        if let Some(first) = kept
+            .iter()
+            .find(|kept_item| get_first_require(kept_item) == get_first_require(item))
+        {
+            let references = db
+                .get_reference_index()
+                .get_decl_references(&file_id, &item.decl_id)
+                .map(|decl_refs| decl_refs.cells.clone())
+                .unwrap_or_default();
+            let can_merge = references.iter().all(|cell| {
+                decl_tree
+                    .find_local_decl(&first.name, cell.range.start())
+                    .is_some_and(|decl| {
+                        decl.get_id() == first.decl_id
+                            || (first.name == item.name && decl.get_id() == item.decl_id)
+                    })
+            });
+            if can_merge {
+                if first.name != item.name {
+                    for cell in references {
+                        text_edits.push(TextEdit {
+                            range: document.to_lsp_range(cell.range)?,
+                            new_text: first.name.clone(),
+                        });
+                    }
+                }
+                continue;
+            }
+        }
+
+        kept.push(item);
+    }
+
+    let mut parts = Vec::new();
+    let mut section = Vec::new();
+    for item in kept {
+        if !item.is_side_effect() {
+            section.push(item);
+            continue;
+        }
+        if !section.is_empty() {
+            parts.push(sort_requires(semantic_model, std::mem::take(&mut section)));
+        }
+        parts.push(document.get_text_slice(item.range).trim_end().to_string());
+    }
+    if !section.is_empty() {
+        parts.push(sort_requires(semantic_model, section));
+    }
+
+    let mut new_text = parts.join("\n");
+    let old_text = document.get_text_slice(region);
+    if !new_text.is_empty() && old_text.ends_with('\n') {
+        new_text.push('\n');
+    }
+    if new_text == old_text && text_edits.is_empty() {
+        return None;
+    }
+
+    text_edits.push(TextEdit {
+        range: document.to_lsp_range(region)?,
+        new_text,
+    });
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: t!("Organize requires").to_string(),
+        kind: Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(document.get_uri(), text_edits)])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+
+    Some(())
+}
+
+/// The text of the requires sorted by module, in library, workspace and unresolved groups
+/// when `groupRequires` is set
+fn sort_requires(semantic_model: &SemanticModel, mut items: Vec<&RequireItem>) -> String {
+    items.sort_by(|a, b| a.module_path.cmp(&b.module_path));
+    let mut groups = vec![items];
+    if semantic_model.get_emmyrc().code_action.group_requires {
+        let module_index = semantic_model.get_db().get_module_index();
+        let mut library = Vec::new();
+        let mut workspace = Vec::new();
+        let mut unresolved = Vec::new();
+        for item in groups.remove(0) {
+            match module_index.find_module(&item.module_path) {
+                Some(module_info) if module_info.workspace_id.is_main() => workspace.push(item),
+                Some(_) => library.push(item),
+                None => unresolved.push(item),
+            }
+        }
+        groups = vec![library, workspace, unresolved];
+    }
+
+    let document = semantic_model.get_document();
+    groups
+        .iter()
+        .filter(|group| !group.is_empty())
+        .map(|group| {
+            group
+                .iter()
+                .map(|item| document.get_text_slice(item.range).trim_end())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// The `local x = require("x")` statements at the top of the file, up to the first other
+/// statement
+fn collect_require_items(semantic_model: &SemanticModel) -> Option<Vec<RequireItem>> {
+    let document = semantic_model.get_document();
+    let text = document.get_text();
+    let mut items: Vec<RequireItem> = Vec::new();
+    for stat in semantic_model.get_root().get_block()?.get_stats() {
+        let LuaStat::LocalStat(local_stat) = stat else {
+            break;
+        };
+        let Some(mut item) = get_require_item(semantic_model, &local_stat) else {
+            break;
+        };
+
+        // the statement and its comments have to be alone on their lines
+        let start = usize::from(item.range.start());
+        let end = usize::from(item.range.end());
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i + 1);
+        if !text[line_start..start].trim().is_empty() || !text[end..line_end].trim().is_empty() {
+            break;
+        }
+        if let Some(last) = items.last() {
+            let gap = TextRange::new(last.range.end(), TextSize::new(line_start as u32));
+            if !document.get_text_slice(gap).trim().is_empty() {
+                break;
+            }
+        }
+
+        item.range = TextRange::new(
+            TextSize::new(line_start as u32),
+            TextSize::new(line_end as u32),
+        );
+        items.push(item);
+    }
+
+    if items.is_empty() {
+        return None;
+    }
+
+    Some(items)
+}
+
+fn get_require_item(
+    semantic_model: &SemanticModel,
+    local_stat: &LuaLocalStat,
+) -> Option<RequireItem> {
+    let mut local_names = local_stat.get_local_name_list();
+    let local_name = local_names.next()?;
+    let mut values = local_stat.get_value_exprs();
+    let LuaExpr::CallExpr(call_expr) = values.next()? else {
+        return None;
+    };
+    if local_names.next().is_some() || values.next().is_some() || !call_expr.is_require() {
+        return None;
+    }
+    let LuaExpr::LiteralExpr(literal_expr) = call_expr.get_args_list()?.get_args().next()? else {
+        return None;
+    };
+    let Some(LuaLiteralToken::String(module_path)) = literal_expr.get_literal() else {
+        return None;
+    };
+
+    let mut range = local_stat.get_range();
+    for comment in local_stat.get_comments() {
+        range = range.cover(comment.get_range());
+    }
+
+    Some(RequireItem {
+        decl_id: LuaDeclId::new(semantic_model.get_file_id(), local_name.get_position()),
+        name: local_name.get_name_token()?.get_name_text().to_string(),
+        module_path: module_path.get_value(),
+        call_range: call_expr.get_range(),
+        range,
+    })
+}
@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+
+use emmylua_code_analysis::{LuaDocument, SemanticModel};
+use emmylua_parser::{
+    BinaryOperator, LuaAstNode, LuaBinaryExpr, LuaBlock, LuaExpr, LuaLiteralExpr, LuaNameExpr,
+    LuaStat, LuaSyntaxKind, LuaSyntaxNode, LuaTokenKind,
+};
+use lsp_types::{CodeAction, CodeActionKind, CodeActionOrCommand, Range, TextEdit, WorkspaceEdit};
+use rowan::{TextRange, TextSize};
+
+/// Moves the selected expression into a local declared before the statement using it
+pub fn build_extract_local(
+    semantic_model: &SemanticModel,
+    actions: &mut Vec<CodeActionOrCommand>,
+    range: Range,
+) -> Option<()> {
+    let document = semantic_model.get_document();
+    let selection = trim_selection(&document, document.to_rowan_range(range)?)?;
+    let root = semantic_model.get_root();
+    let expr = root
+        .syntax()
+        .covering_element(selection)
+        .ancestors()
+        .filter_map(LuaExpr::cast)
+        .find(|expr| expr.get_range() == selection)?;
+    if !can_extract_expr(&expr) {
+        return None;
+    }
+
+    let stat = find_extract_stat(&expr)?;
+    let name = get_unique_name(semantic_model, "value");
+    let indent = get_indent(&document, stat.get_range().start());
+    let declaration = TextEdit {
+        range: document.to_lsp_range(TextRange::empty(stat.get_range().start()))?,
+        new_text: format!(
+            "local {} = {}\n{}",
+            name,
+            document.get_text_slice(selection),
+            indent
+        ),
+    };
+    let replacement = TextEdit {
+        range: document.to_lsp_range(selection)?,
+        new_text: name,
+    };
+
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: t!("Extract to local variable").to_string(),
+        kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                document.get_uri(),
+                vec![declaration, replacement],
+            )])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+
+    Some(())
+}
+
+/// Moves the selected statements into a local function. The locals declared before the
+/// selection become its parameters, and the locals the selection declares or assigns
+/// which are used after it become its return values.
+pub fn build_extract_function(
+    semantic_model: &SemanticModel,
+    actions: &mut Vec<CodeActionOrCommand>,
+    range: Range,
+) -> Option<()> {
+    let document = semantic_model.get_document();
+    let selection = trim_selection(&document, document.to_rowan_range(range)?)?;
+    let stats = find_selected_stats(semantic_model, selection)?;
+    if stats.iter().any(|stat| has_control_flow(stat.syntax())) {
+        return None;
+    }
+
+    let (params, new_locals, assigned_locals) =
+        collect_extract_variables(semantic_model, &stats, selection)?;
+    let name = get_unique_name(semantic_model, "extracted");
+    let indent = get_indent(&document, selection.start());
+    let indent_unit = if indent.contains('\t') { "\t" } else { "    " };
+
+    let mut function = format!("local function {}({})\n", name, params.join(", "));
+    for (i, line) in document.get_text_slice(selection).split('\n').enumerate() {
+        let line = if i == 0 {
+            line
+        } else if let Some(line) = line.strip_prefix(indent.as_str()) {
+            line
+        } else if line.trim().is_empty() {
+            ""
+        } else {
+            // a line inside a multiline string, or indented less than the selection
+            function.push_str(line);
+            function.push('\n');
+            continue;
+        };
+        if line.trim().is_empty() {
+            function.push('\n');
+        } else {
+            function.push_str(&format!("{}{}{}\n", indent, indent_unit, line));
+        }
+    }
+
+    let returns = new_locals
+        .iter()
+        .chain(assigned_locals.iter())
+        .cloned()
+        .collect::<Vec<_>>();
+    if !returns.is_empty() {
+        function.push_str(&format!(
+            "{}{}return {}\n",
+            indent,
+            indent_unit,
+            returns.join(", ")
+        ));
+    }
+    function.push_str(&format!("{}end\n{}", indent, indent));
+
+    let call = format!("{}({})", name, params.join(", "));
+    let call = match (new_locals.is_empty(), assigned_locals.is_empty()) {
+        (true, true) => call,
+        (false, true) => format!("local {} = {}", new_locals.join(", "), call),
+        (true, false) => format!("{} = {}", assigned_locals.join(", "), call),
+        (false, false) => format!(
+            "local {}\n{}{} = {}",
+            new_locals.join(", "),
+            indent,
+            returns.join(", "),
+            call
+        ),
+    };
+
+    let text_edit = TextEdit {
+        range: document.to_lsp_range(selection)?,
+        new_text: function + &call,
+    };
+    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+        title: t!("Extract to function").to_string(),
+        kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(document.get_uri(), vec![text_edit])])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }));
+
+    Some(())
+}
+
+fn trim_selection(document: &LuaDocument, range: TextRange) -> Option<TextRange> {
+    let text = document.get_text_slice(range);
+    let start = range.start() + TextSize::of(&text[..text.len() - text.trim_start().len()]);
+    let end = range.end() - TextSize::of(&text[text.trim_end().len()..]);
+    if start >= end {
+        return None;
+    }
+
+    Some(TextRange::new(start, end))
+}
+
+fn can_extract_expr(expr: &LuaExpr) -> bool {
+    let Some(parent) = expr.syntax().parent() else {
+        return false;
+    };
+    match parent.kind().into() {
+        // the callee of a call statement, or the name of a function
+        LuaSyntaxKind::CallExprStat | LuaSyntaxKind::FuncStat => return false,
+        // a variable which is assigned
+        LuaSyntaxKind::AssignStat => {
+            let is_assigned = parent
+                .children_with_tokens()
+                .find(|it| it.kind() == LuaTokenKind::TkAssign.into())
+                .is_some_and(|assign| expr.get_range().end() <= assign.text_range().start());
+            if is_assigned {
+                return false;
+            }
+        }
+        _ => {}
+    }
+
+    // a call or `...` at the end of a list passes all its values, a local keeps one
+    let is_multi_value = match expr {
+        LuaExpr::CallExpr(_) => true,
+        LuaExpr::LiteralExpr(literal) => is_dots(literal),
+        _ => false,
+    };
+    if is_multi_value {
+        let list_item = match parent.kind().into() {
+            LuaSyntaxKind::TableFieldValue => parent.clone(),
+            LuaSyntaxKind::CallArgList
+            | LuaSyntaxKind::ReturnStat
+            | LuaSyntaxKind::LocalStat
+            | LuaSyntaxKind::AssignStat => expr.syntax().clone(),
+            _ => return true,
+        };
+        if list_item.next_sibling().is_none() {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn is_dots(literal: &LuaLiteralExpr) -> bool {
+    literal
+        .syntax()
+        .first_token()
+        .is_some_and(|token| token.kind() == LuaTokenKind::TkDots.into())
+}
+
+/// The statement to declare the local before, when the expression is evaluated exactly
+/// once each time the statement runs
+fn find_extract_stat(expr: &LuaExpr) -> Option<LuaStat> {
+    let range = expr.get_range();
+    for node in expr.syntax().ancestors().skip(1) {
+        match node.kind().into() {
+            // conditions which are evaluated more than once, or only sometimes
+            LuaSyntaxKind::WhileStat
+            | LuaSyntaxKind::RepeatStat
+            | LuaSyntaxKind::ElseIfClauseStat => return None,
+            LuaSyntaxKind::BinaryExpr => {
+                let binary_expr = LuaBinaryExpr::cast(node.clone())?;
+                let op = binary_expr.get_op_token()?.get_op();
+                let (_, right) = binary_expr.get_exprs()?;
+                if matches!(op, BinaryOperator::OpAnd | BinaryOperator::OpOr)
+                    && right.get_range().contains_range(range)
+                {
+                    return None;
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(stat) = LuaStat::cast(node) {
+            return Some(stat);
+        }
+    }
+
+    None
+}
+
+/// The statements of a block which the selection exactly covers
+fn find_selected_stats(
+    semantic_model: &SemanticModel,
+    selection: TextRange,
+) -> Option<Vec<LuaStat>> {
+    let root = semantic_model.get_root();
+    let block = root
+        .syntax()
+        .covering_element(selection)
+        .ancestors()
+        .find_map(LuaBlock::cast)?;
+    let stats = block
+        .get_stats()
+        .filter(|stat| {
+            stat.get_range()
+                .intersect(selection)
+                .is_some_and(|it| !it.is_empty())
+        })
+        .collect::<Vec<_>>();
+    let first = stats.first()?;
+    let last = stats.last()?;
+    if first.get_range().start() != selection.start() || last.get_range().end() != selection.end() {
+        return None;
+    }
+
+    Some(stats)
+}
+
+/// Whether the statement returns, jumps or uses the varargs of the function it is in,
+/// which would change what it does inside of a new function
+fn has_control_flow(stat: &LuaSyntaxNode) -> bool {
+    for token in stat
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+    {
+        let kind: LuaTokenKind = token.kind().into();
+        if !matches!(
+            kind,
+            LuaTokenKind::TkReturn
+                | LuaTokenKind::TkBreak
+                | LuaTokenKind::TkGoto
+                | LuaTokenKind::TkDbColon
+                | LuaTokenKind::TkDots
+        ) {
+            continue;
+        }
+
+        let mut is_contained = false;
+        for node in token.parent_ancestors() {
+            match node.kind().into() {
+                LuaSyntaxKind::ClosureExpr => is_contained = true,
+                LuaSyntaxKind::WhileStat
+                | LuaSyntaxKind::RepeatStat
+                | LuaSyntaxKind::ForStat
+                | LuaSyntaxKind::ForRangeStat => is_contained = kind == LuaTokenKind::TkBreak,
+                _ => {}
+            }
+            if is_contained || &node == stat {
+                break;
+            }
+        }
+        if !is_contained {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Returns the parameters, the new locals to return and the assigned locals to return
+fn collect_extract_variables(
+    semantic_model: &SemanticModel,
+    stats: &[LuaStat],
+    selection: TextRange,
+) -> Option<(Vec<String>, Vec<String>, Vec<String>)> {
+    let db = semantic_model.get_db();
+    let file_id = semantic_model.get_file_id();
+    let reference_index = db.get_reference_index();
+    let decl_index = db.get_decl_index();
+
+    let mut params = Vec::new();
+    let mut assigned_locals = Vec::new();
+    for name_expr in stats
+        .iter()
+        .flat_map(|stat| stat.descendants::<LuaNameExpr>())
+    {
+        let Some(decl_id) = reference_index.get_var_reference_decl(&file_id, name_expr.get_range())
+        else {
+            continue;
+        };
+        let Some(decl) = decl_index.get_decl(&decl_id) else {
+            continue;
+        };
+        if !decl.is_local() || selection.contains_range(decl.get_range()) {
+            continue;
+        }
+
+        let name = decl.get_name().to_string();
+        if !params.contains(&name) {
+            params.push(name.clone());
+        }
+        let is_write = reference_index
+            .get_decl_references(&file_id, &decl_id)
+            .is_some_and(|decl_refs| {
+                decl_refs
+                    .cells
+                    .iter()
+                    .any(|cell| cell.is_write && cell.range == name_expr.get_range())
+            });
+        if is_write && !assigned_locals.contains(&name) {
+            assigned_locals.push(name);
+        }
+    }
+
+    let mut new_locals = reference_index
+        .get_local_reference(&file_id)?
+        .get_decl_references_map()
+        .iter()
+        .filter(|(decl_id, decl_refs)| {
+            selection.contains(decl_id.position)
+                && decl_refs
+                    .cells
+                    .iter()
+                    .any(|cell| cell.range.start() >= selection.end())
+        })
+        .filter_map(|(decl_id, _)| decl_index.get_decl(decl_id))
+        .map(|decl| (decl.get_position(), decl.get_name().to_string()))
+        .collect::<Vec<_>>();
+    new_locals.sort();
+
+    Some((
+        params,
+        new_locals.into_iter().map(|(_, name)| name).collect(),
+        assigned_locals,
+    ))
+}
+
+/// The whitespace at the start of the line of `offset`
+fn get_indent(document: &LuaDocument, offset: TextSize) -> String {
+    let Some(line_range) = document
+        .get_line(offset)
+        .and_then(|line| document.get_line_range(line))
+    else {
+        return String::new();
+    };
+
+    document
+        .get_text_slice(TextRange::new(
+            line_range.start(),
+            offset.max(line_range.start()),
+        ))
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+fn get_unique_name(semantic_model: &SemanticModel, base: &str) -> String {
+    let names = semantic_model
+        .get_root()
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|token| token.kind() == LuaTokenKind::TkName.into())
+        .map(|token| token.text().to_string())
+        .collect::<Vec<_>>();
+
+    let mut name = base.to_string();
+    let mut i = 1;
+    while names.contains(&name) {
+        name = format!("{}{}", base, i);
+        i += 1;
+    }
+    name
+}
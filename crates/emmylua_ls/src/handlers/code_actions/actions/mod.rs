@@ -1,5 +1,15 @@
+mod build_convert_function;
 mod build_disable_code;
 mod build_fix_code;
+mod build_generate_doc;
+mod build_inline;
+mod build_organize_requires;
+mod build_refactor;
 
+pub use build_convert_function::*;
 pub use build_disable_code::*;
 pub use build_fix_code::*;
+pub use build_generate_doc::*;
+pub use build_inline::*;
+pub use build_organize_requires::*;
+pub use build_refactor::*;
@@ -1,13 +1,16 @@
 use std::str::FromStr;
 
-use emmylua_code_analysis::{DiagnosticCode, FileId, SemanticModel};
+use emmylua_code_analysis::{DiagnosticCode, FileId, LuaCompilation, SemanticModel};
 use lsp_types::{
     CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionResponse, Diagnostic,
     NumberOrString, Range, WorkspaceEdit,
 };
 
 use super::actions::{
-    build_add_doc_tag, build_disable_file_changes, build_disable_next_line_changes,
+    build_add_doc_tag, build_convert_local_function, build_convert_method,
+    build_disable_file_changes, build_disable_next_line_changes, build_extract_function,
+    build_extract_local, build_generate_doc, build_inline_function, build_inline_variable,
+    build_organize_requires, build_unused_fix,
 };
 use crate::handlers::{
     code_actions::actions::build_need_check_nil,
@@ -15,12 +18,20 @@ use crate::handlers::{
 };
 
 pub fn build_actions(
+    compilation: &LuaCompilation,
     semantic_model: &SemanticModel,
+    range: Range,
     diagnostics: Vec<Diagnostic>,
+    only: Option<&[CodeActionKind]>,
 ) -> Option<CodeActionResponse> {
     let mut actions = Vec::new();
     let file_id = semantic_model.get_file_id();
-    for diagnostic in diagnostics {
+    let quick_fix_diagnostics = if is_kind_requested(only, &CodeActionKind::QUICKFIX) {
+        diagnostics
+    } else {
+        Vec::new()
+    };
+    for diagnostic in quick_fix_diagnostics {
         if diagnostic.source.is_none() {
             continue;
         }
@@ -53,6 +64,23 @@ pub fn build_actions(
         }
     }
 
+    if is_kind_requested(only, &CodeActionKind::REFACTOR_EXTRACT) {
+        build_extract_local(semantic_model, &mut actions, range);
+        build_extract_function(semantic_model, &mut actions, range);
+    }
+    if is_kind_requested(only, &CodeActionKind::REFACTOR_INLINE) {
+        build_inline_variable(semantic_model, &mut actions, range);
+        build_inline_function(semantic_model, &mut actions, range);
+    }
+    if is_kind_requested(only, &CodeActionKind::REFACTOR_REWRITE) {
+        build_generate_doc(semantic_model, compilation, &mut actions, range);
+        build_convert_method(semantic_model, compilation, &mut actions, range);
+        build_convert_local_function(semantic_model, &mut actions, range);
+    }
+    if is_kind_requested(only, &CodeActionKind::SOURCE_ORGANIZE_IMPORTS) {
+        build_organize_requires(semantic_model, &mut actions);
+    }
+
     if actions.is_empty() {
         return None;
     }
@@ -60,6 +88,20 @@ pub fn build_actions(
     Some(actions)
 }
 
+/// Whether the client asked for the actions of `kind`, `refactor` also asks for
+/// `refactor.extract` and the other kinds below it
+fn is_kind_requested(only: Option<&[CodeActionKind]>, kind: &CodeActionKind) -> bool {
+    let Some(only) = only else {
+        return true;
+    };
+
+    only.iter().any(|requested| {
+        kind.as_str()
+            .strip_prefix(requested.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
 #[allow(unused_variables)]
 fn add_fix_code_action(
     semantic_model: &SemanticModel,
@@ -72,6 +114,7 @@ fn add_fix_code_action(
     match diagnostic_code {
         DiagnosticCode::NeedCheckNil => build_need_check_nil(semantic_model, actions, range, data),
         DiagnosticCode::UnknownDocTag => build_add_doc_tag(semantic_model, actions, range, data),
+        DiagnosticCode::Unused => build_unused_fix(semantic_model, actions, range),
         _ => Some(()),
     }
 }
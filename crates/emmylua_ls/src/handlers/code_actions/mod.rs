@@ -1,11 +1,12 @@
 mod actions;
 mod build_actions;
 
+pub use actions::generate_global_docs;
 use build_actions::build_actions;
 use emmylua_code_analysis::{EmmyLuaAnalysis, FileId};
 use lsp_types::{
-    ClientCapabilities, CodeActionParams, CodeActionProviderCapability, CodeActionResponse,
-    Diagnostic, ServerCapabilities,
+    ClientCapabilities, CodeActionKind, CodeActionOptions, CodeActionParams,
+    CodeActionProviderCapability, CodeActionResponse, Diagnostic, Range, ServerCapabilities,
 };
 use tokio_util::sync::CancellationToken;
 
@@ -23,23 +24,62 @@ pub async fn on_code_action_handler(
     let diagnostics = params.context.diagnostics;
     let analysis = context.analysis.read().await;
     let file_id = analysis.get_file_id(&uri)?;
-    code_action(&analysis, file_id, diagnostics)
+    code_action(
+        &analysis,
+        file_id,
+        params.range,
+        diagnostics,
+        params.context.only.as_deref(),
+    )
 }
 
 pub fn code_action(
     analysis: &EmmyLuaAnalysis,
     file_id: FileId,
+    range: Range,
     diagnostics: Vec<Diagnostic>,
+    only: Option<&[CodeActionKind]>,
 ) -> Option<CodeActionResponse> {
-    let mut semantic_model = analysis.compilation.get_semantic_model(file_id)?;
+    let semantic_model = analysis.compilation.get_semantic_model(file_id)?;
 
-    build_actions(&mut semantic_model, diagnostics)
+    build_actions(
+        &analysis.compilation,
+        &semantic_model,
+        range,
+        diagnostics,
+        only,
+    )
 }
 
 pub struct CodeActionsCapabilities;
 
 impl RegisterCapabilities for CodeActionsCapabilities {
-    fn register_capabilities(server_capabilities: &mut ServerCapabilities, _: &ClientCapabilities) {
-        server_capabilities.code_action_provider = Some(CodeActionProviderCapability::Simple(true));
+    fn register_capabilities(
+        server_capabilities: &mut ServerCapabilities,
+        client_capabilities: &ClientCapabilities,
+    ) {
+        // the kinds can only be sent to clients which support code action literals
+        let supports_literals = client_capabilities
+            .text_document
+            .as_ref()
+            .and_then(|text_document| text_document.code_action.as_ref())
+            .is_some_and(|code_action| code_action.code_action_literal_support.is_some());
+        if !supports_literals {
+            server_capabilities.code_action_provider =
+                Some(CodeActionProviderCapability::Simple(true));
+            return;
+        }
+
+        server_capabilities.code_action_provider =
+            Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                code_action_kinds: Some(vec![
+                    CodeActionKind::QUICKFIX,
+                    CodeActionKind::REFACTOR_EXTRACT,
+                    CodeActionKind::REFACTOR_INLINE,
+                    CodeActionKind::REFACTOR_REWRITE,
+                    CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+                ]),
+                ..Default::default()
+            }));
     }
 }
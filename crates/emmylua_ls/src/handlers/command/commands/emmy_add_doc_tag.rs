@@ -4,6 +4,7 @@ use emmylua_code_analysis::load_configs_raw;
 use lsp_types::Command;
 use serde_json::Value;
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 
 use crate::context::{ServerContextSnapshot, WorkspaceManager};
 
@@ -14,7 +15,11 @@ pub struct AddDocTagCommand;
 impl CommandSpec for AddDocTagCommand {
     const COMMAND: &str = "emmy.add.doctag";
 
-    async fn handle(context: ServerContextSnapshot, args: Vec<Value>) -> Option<()> {
+    async fn handle(
+        context: ServerContextSnapshot,
+        args: Vec<Value>,
+        _: CancellationToken,
+    ) -> Option<()> {
         let tag_name: String = serde_json::from_value(args.get(0)?.clone()).ok()?;
         add_doc_tag(context.workspace_manager, tag_name).await;
         Some(())
@@ -4,6 +4,7 @@ use emmylua_code_analysis::FileId;
 use emmylua_parser::{LuaAstNode, LuaExpr, LuaStat};
 use lsp_types::{ApplyWorkspaceEditParams, Command, Position, TextEdit, WorkspaceEdit};
 use serde_json::Value;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
     context::ServerContextSnapshot,
@@ -17,7 +18,11 @@ pub struct AutoRequireCommand;
 impl CommandSpec for AutoRequireCommand {
     const COMMAND: &str = "emmy.auto.require";
 
-    async fn handle(context: ServerContextSnapshot, args: Vec<Value>) -> Option<()> {
+    async fn handle(
+        context: ServerContextSnapshot,
+        args: Vec<Value>,
+        _: CancellationToken,
+    ) -> Option<()> {
         let add_to: FileId = serde_json::from_value(args.get(0)?.clone()).ok()?;
         let need_require_file_id: FileId = serde_json::from_value(args.get(1)?.clone()).ok()?;
         let position: Position = serde_json::from_value(args.get(2)?.clone()).ok()?;
@@ -5,6 +5,7 @@ use lsp_types::{Command, Range};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 
 use crate::context::{ServerContextSnapshot, WorkspaceManager};
 
@@ -23,7 +24,11 @@ pub struct DisableCodeCommand;
 impl CommandSpec for DisableCodeCommand {
     const COMMAND: &str = "emmy.disable.code";
 
-    async fn handle(context: ServerContextSnapshot, args: Vec<Value>) -> Option<()> {
+    async fn handle(
+        context: ServerContextSnapshot,
+        args: Vec<Value>,
+        _: CancellationToken,
+    ) -> Option<()> {
         let action: DisableAction = serde_json::from_value(args.get(0)?.clone()).ok()?;
         let code: DiagnosticCode = serde_json::from_value(args.get(3)?.clone()).ok()?;
 
@@ -1,4 +1,5 @@
 use serde_json::Value;
+use tokio_util::sync::CancellationToken;
 
 use crate::context::ServerContextSnapshot;
 
@@ -10,7 +11,11 @@ impl CommandSpec for FixFormatCommand {
     const COMMAND: &str = "emmy.fix.format";
 
     #[allow(unused)]
-    async fn handle(context: ServerContextSnapshot, args: Vec<Value>) -> Option<()> {
+    async fn handle(
+        context: ServerContextSnapshot,
+        args: Vec<Value>,
+        _: CancellationToken,
+    ) -> Option<()> {
         Some(())
     }
 }
@@ -0,0 +1,107 @@
+use std::{collections::HashMap, time::Duration};
+
+use lsp_types::{ApplyWorkspaceEditParams, Command, WorkspaceEdit};
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    context::{ProgressTask, ServerContextSnapshot},
+    handlers::code_actions::generate_global_docs,
+    util::time_cancel_token,
+};
+
+use super::CommandSpec;
+
+pub struct GenerateDocCommand;
+
+impl CommandSpec for GenerateDocCommand {
+    const COMMAND: &str = "emmy.generate.doc";
+
+    async fn handle(
+        context: ServerContextSnapshot,
+        _: Vec<Value>,
+        cancel_token: CancellationToken,
+    ) -> Option<()> {
+        let client_id = context
+            .workspace_manager
+            .read()
+            .await
+            .client_config
+            .client_id;
+        let analysis = context.analysis.read().await;
+        let mut file_ids = analysis
+            .compilation
+            .get_db()
+            .get_module_index()
+            .get_main_workspace_file_ids();
+        file_ids.sort();
+
+        let status_bar = &context.status_bar;
+        status_bar.create_progress_task(client_id, ProgressTask::GenerateDoc);
+        // the semantic models of the files which call the functions are shared by all files
+        let mut semantic_models = HashMap::new();
+        let mut changes = Vec::new();
+        let mut last_percentage = 0;
+        for (i, file_id) in file_ids.iter().enumerate() {
+            if cancel_token.is_cancelled() {
+                status_bar.finish_progress_task(client_id, ProgressTask::GenerateDoc, None);
+                return None;
+            }
+            let percentage = (i * 100 / file_ids.len()) as u32;
+            if percentage != last_percentage {
+                last_percentage = percentage;
+                status_bar.update_progress_task(
+                    client_id,
+                    ProgressTask::GenerateDoc,
+                    Some(percentage),
+                    None,
+                );
+            }
+
+            let text_edits =
+                generate_global_docs(&analysis.compilation, *file_id, &mut semantic_models);
+            if text_edits.is_empty() {
+                continue;
+            }
+            if let Some(uri) = analysis.get_uri(*file_id) {
+                changes.push((uri, text_edits));
+            }
+        }
+        status_bar.finish_progress_task(client_id, ProgressTask::GenerateDoc, None);
+        drop(semantic_models);
+        drop(analysis);
+        if changes.is_empty() {
+            return Some(());
+        }
+
+        let client = context.client;
+        let apply_cancel_token = time_cancel_token(Duration::from_secs(10));
+        let apply_edit_params = ApplyWorkspaceEditParams {
+            label: Some(t!("Generate annotations for all global functions").to_string()),
+            edit: WorkspaceEdit {
+                changes: Some(changes.into_iter().collect()),
+                document_changes: None,
+                change_annotations: None,
+            },
+        };
+
+        tokio::spawn(async move {
+            let res = client
+                .apply_edit(apply_edit_params, apply_cancel_token)
+                .await;
+            if let Some(res) = res.filter(|res| !res.applied) {
+                log::error!("Failed to apply edit: {:?}", res.failure_reason);
+            }
+        });
+
+        Some(())
+    }
+}
+
+pub fn make_generate_doc_command(title: &str) -> Command {
+    Command {
+        title: title.to_string(),
+        command: GenerateDocCommand::COMMAND.to_string(),
+        arguments: None,
+    }
+}
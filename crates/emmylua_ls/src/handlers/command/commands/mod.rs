@@ -4,7 +4,9 @@ use emmy_add_doc_tag::AddDocTagCommand;
 use emmy_auto_require::AutoRequireCommand;
 use emmy_disable_code::DisableCodeCommand;
 use emmy_fix_format::FixFormatCommand;
+use emmy_generate_doc::GenerateDocCommand;
 use serde_json::Value;
+use tokio_util::sync::CancellationToken;
 
 use crate::context::ServerContextSnapshot;
 
@@ -12,15 +14,21 @@ mod emmy_add_doc_tag;
 mod emmy_auto_require;
 mod emmy_disable_code;
 mod emmy_fix_format;
+mod emmy_generate_doc;
 
 pub use emmy_add_doc_tag::make_auto_doc_tag_command;
 pub use emmy_auto_require::make_auto_require;
 pub use emmy_disable_code::{DisableAction, make_disable_code_command};
+pub use emmy_generate_doc::make_generate_doc_command;
 
 pub trait CommandSpec {
     const COMMAND: &str;
 
-    async fn handle(context: ServerContextSnapshot, args: Vec<Value>) -> Option<()>;
+    async fn handle(
+        context: ServerContextSnapshot,
+        args: Vec<Value>,
+        cancel_token: CancellationToken,
+    ) -> Option<()>;
 }
 
 static COMMANDS: LazyLock<Vec<String>> = LazyLock::new(|| {
@@ -29,6 +37,7 @@ static COMMANDS: LazyLock<Vec<String>> = LazyLock::new(|| {
         DisableCodeCommand::COMMAND.to_string(),
         FixFormatCommand::COMMAND.to_string(),
         AddDocTagCommand::COMMAND.to_string(),
+        GenerateDocCommand::COMMAND.to_string(),
     ]
 });
 
@@ -40,12 +49,20 @@ pub async fn dispatch_command(
     context: ServerContextSnapshot,
     command_name: &str,
     args: Vec<Value>,
+    cancel_token: CancellationToken,
 ) -> Option<()> {
     match command_name {
-        AutoRequireCommand::COMMAND => AutoRequireCommand::handle(context, args).await,
-        DisableCodeCommand::COMMAND => DisableCodeCommand::handle(context, args).await,
-        FixFormatCommand::COMMAND => FixFormatCommand::handle(context, args).await,
-        AddDocTagCommand::COMMAND => AddDocTagCommand::handle(context, args).await,
+        AutoRequireCommand::COMMAND => {
+            AutoRequireCommand::handle(context, args, cancel_token).await
+        }
+        DisableCodeCommand::COMMAND => {
+            DisableCodeCommand::handle(context, args, cancel_token).await
+        }
+        FixFormatCommand::COMMAND => FixFormatCommand::handle(context, args, cancel_token).await,
+        AddDocTagCommand::COMMAND => AddDocTagCommand::handle(context, args, cancel_token).await,
+        GenerateDocCommand::COMMAND => {
+            GenerateDocCommand::handle(context, args, cancel_token).await
+        }
         _ => Some(()),
     }
 }
@@ -15,11 +15,11 @@ use super::RegisterCapabilities;
 pub async fn on_execute_command_handler(
     context: ServerContextSnapshot,
     params: ExecuteCommandParams,
-    _: CancellationToken,
+    cancel_token: CancellationToken,
 ) -> Option<Value> {
     let args = params.arguments;
     let command_name = params.command.as_str();
-    commands::dispatch_command(context, command_name, args).await;
+    commands::dispatch_command(context, command_name, args, cancel_token).await;
     Some(Value::Null)
 }
 
@@ -0,0 +1,91 @@
+use emmylua_code_analysis::{
+    EmmyLuaAnalysis, FileId, LuaDeclId, LuaSemanticDeclId, SemanticDeclLevel, SemanticModel,
+};
+use lsp_types::{
+    ClientCapabilities, DeclarationCapability, Location, Position, ServerCapabilities,
+    request::{GotoDeclarationParams, GotoDeclarationResponse},
+};
+use tokio_util::sync::CancellationToken;
+
+use super::RegisterCapabilities;
+use crate::{context::ServerContextSnapshot, util::find_token_at_position};
+
+pub async fn on_declaration_handler(
+    context: ServerContextSnapshot,
+    params: GotoDeclarationParams,
+    _: CancellationToken,
+) -> Option<GotoDeclarationResponse> {
+    let uri = params.text_document_position_params.text_document.uri;
+    let analysis = context.analysis.read().await;
+    let file_id = analysis.get_file_id(&uri)?;
+    let position = params.text_document_position_params.position;
+
+    declaration(&analysis, file_id, position)
+}
+
+/// Goes to where a name is declared: the `local` statement, the parameter or the
+/// declarations of a global, without following what was assigned to it
+pub fn declaration(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    position: Position,
+) -> Option<GotoDeclarationResponse> {
+    let semantic_model = analysis.compilation.get_semantic_model(file_id)?;
+    let token = find_token_at_position(&semantic_model, position)?;
+
+    let mut locations = Vec::new();
+    match semantic_model.find_decl(token.into(), SemanticDeclLevel::NoTrace)? {
+        LuaSemanticDeclId::LuaDecl(decl_id) => {
+            let decl = semantic_model
+                .get_db()
+                .get_decl_index()
+                .get_decl(&decl_id)?;
+            if decl.is_global() {
+                let global_decl_ids = semantic_model
+                    .get_db()
+                    .get_global_index()
+                    .get_global_decl_ids(decl.get_name())?;
+                for global_decl_id in global_decl_ids {
+                    locations.extend(get_decl_location(&semantic_model, global_decl_id));
+                }
+            } else {
+                locations.extend(get_decl_location(&semantic_model, &decl_id));
+            }
+        }
+        LuaSemanticDeclId::Member(member_id) => {
+            let document = semantic_model.get_document_by_file_id(member_id.file_id)?;
+            locations.extend(document.to_lsp_location(member_id.get_syntax_id().get_range()));
+        }
+        LuaSemanticDeclId::TypeDecl(type_decl_id) => {
+            let type_decl = semantic_model
+                .get_db()
+                .get_type_index()
+                .get_type_decl(&type_decl_id)?;
+            for location in type_decl.get_locations() {
+                let document = semantic_model.get_document_by_file_id(location.file_id)?;
+                locations.extend(document.to_lsp_location(location.range));
+            }
+        }
+        _ => {}
+    }
+
+    match locations.len() {
+        0 => None,
+        1 => Some(GotoDeclarationResponse::Scalar(locations.remove(0))),
+        _ => Some(GotoDeclarationResponse::Array(locations)),
+    }
+}
+
+fn get_decl_location(semantic_model: &SemanticModel, decl_id: &LuaDeclId) -> Option<Location> {
+    let decl = semantic_model.get_db().get_decl_index().get_decl(decl_id)?;
+    let document = semantic_model.get_document_by_file_id(decl_id.file_id)?;
+    document.to_lsp_location(decl.get_range())
+}
+
+pub struct DeclarationCapabilities;
+
+impl RegisterCapabilities for DeclarationCapabilities {
+    fn register_capabilities(server_capabilities: &mut ServerCapabilities, _: &ClientCapabilities) {
+        server_capabilities.declaration_provider = Some(DeclarationCapability::Simple(true));
+    }
+}
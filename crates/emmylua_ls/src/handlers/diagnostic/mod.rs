@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use emmylua_code_analysis::{EmmyLuaAnalysis, FileId};
+use lsp_types::{
+    ClientCapabilities, Diagnostic, DiagnosticOptions, DiagnosticServerCapabilities,
+    DocumentDiagnosticParams, DocumentDiagnosticReport, DocumentDiagnosticReportResult,
+    FullDocumentDiagnosticReport, RelatedFullDocumentDiagnosticReport,
+    RelatedUnchangedDocumentDiagnosticReport, ServerCapabilities,
+    UnchangedDocumentDiagnosticReport, WorkDoneProgressOptions, WorkspaceDiagnosticParams,
+    WorkspaceDiagnosticReport, WorkspaceDiagnosticReportResult, WorkspaceDocumentDiagnosticReport,
+    WorkspaceFullDocumentDiagnosticReport, WorkspaceUnchangedDocumentDiagnosticReport,
+};
+use tokio_util::sync::CancellationToken;
+
+use super::RegisterCapabilities;
+use crate::context::{ServerContextSnapshot, is_pull_diagnostic_supported};
+
+pub async fn on_document_diagnostic_handler(
+    context: ServerContextSnapshot,
+    params: DocumentDiagnosticParams,
+    cancel_token: CancellationToken,
+) -> Option<DocumentDiagnosticReportResult> {
+    let uri = params.text_document.uri;
+    let analysis = context.analysis.read().await;
+    let report = match analysis.get_file_id(&uri) {
+        Some(file_id) => document_diagnostic(
+            &analysis,
+            file_id,
+            params.previous_result_id.as_deref(),
+            cancel_token,
+        )?,
+        None => PullDiagnosticReport::Full {
+            result_id: None,
+            items: Vec::new(),
+        },
+    };
+
+    let report = match report {
+        PullDiagnosticReport::Full { result_id, items } => {
+            DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport { result_id, items },
+            })
+        }
+        PullDiagnosticReport::Unchanged { result_id } => {
+            DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                related_documents: None,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                    result_id,
+                },
+            })
+        }
+    };
+    Some(DocumentDiagnosticReportResult::Report(report))
+}
+
+pub async fn on_workspace_diagnostic_handler(
+    context: ServerContextSnapshot,
+    params: WorkspaceDiagnosticParams,
+    cancel_token: CancellationToken,
+) -> Option<WorkspaceDiagnosticReportResult> {
+    let analysis = context.analysis.read().await;
+    let previous_result_ids = params
+        .previous_result_ids
+        .into_iter()
+        .filter_map(|previous| Some((analysis.get_file_id(&previous.uri)?, previous.value)))
+        .collect::<HashMap<_, _>>();
+    let mut file_ids = analysis
+        .compilation
+        .get_db()
+        .get_module_index()
+        .get_main_workspace_file_ids();
+    file_ids.sort();
+
+    let mut items = Vec::new();
+    for file_id in file_ids {
+        let Some(uri) = analysis.get_uri(file_id) else {
+            continue;
+        };
+
+        let previous_result_id = previous_result_ids.get(&file_id).map(String::as_str);
+        let item = match workspace_file_diagnostic(
+            &analysis,
+            file_id,
+            previous_result_id,
+            cancel_token.clone(),
+        )? {
+            PullDiagnosticReport::Full { result_id, items } => {
+                WorkspaceDocumentDiagnosticReport::Full(WorkspaceFullDocumentDiagnosticReport {
+                    uri,
+                    version: None,
+                    full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                        result_id,
+                        items,
+                    },
+                })
+            }
+            PullDiagnosticReport::Unchanged { result_id } => {
+                WorkspaceDocumentDiagnosticReport::Unchanged(
+                    WorkspaceUnchangedDocumentDiagnosticReport {
+                        uri,
+                        version: None,
+                        unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                            result_id,
+                        },
+                    },
+                )
+            }
+        };
+        items.push(item);
+    }
+
+    Some(WorkspaceDiagnosticReportResult::Report(
+        WorkspaceDiagnosticReport { items },
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PullDiagnosticReport {
+    Full {
+        result_id: Option<String>,
+        items: Vec<Diagnostic>,
+    },
+    Unchanged {
+        result_id: String,
+    },
+}
+
+/// Diagnoses a file for a document pull, returns `None` when the request was cancelled.
+///
+/// The result id is `<config revision>:<text hash>:<diagnostics hash>`, where the text hash
+/// covers the file and the files it requires. Open documents can
+/// depend on any other file, so they are always diagnosed again, and the client is told
+/// the diagnostics are unchanged when the result id is the same.
+pub fn document_diagnostic(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    previous_result_id: Option<&str>,
+    cancel_token: CancellationToken,
+) -> Option<PullDiagnosticReport> {
+    let Some(items) = analysis.diagnose_file(file_id, cancel_token.clone()) else {
+        if cancel_token.is_cancelled() {
+            return None;
+        }
+        // diagnostics are disabled or the file is not in the main workspace
+        return Some(PullDiagnosticReport::Full {
+            result_id: None,
+            items: Vec::new(),
+        });
+    };
+
+    let result_id = format!(
+        "{}:{:x}",
+        get_file_key(analysis, file_id),
+        hash_diagnostics(&items)
+    );
+    if previous_result_id == Some(result_id.as_str()) {
+        return Some(PullDiagnosticReport::Unchanged { result_id });
+    }
+    Some(PullDiagnosticReport::Full {
+        result_id: Some(result_id),
+        items,
+    })
+}
+
+/// Diagnoses a file for a workspace pull. A file is not diagnosed again while the config,
+/// its text and the text of the files it requires did not change since the previous
+/// result. Changes of globals declared in files it does not require are picked up when it
+/// is opened.
+pub fn workspace_file_diagnostic(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    previous_result_id: Option<&str>,
+    cancel_token: CancellationToken,
+) -> Option<PullDiagnosticReport> {
+    let file_key = get_file_key(analysis, file_id);
+    match previous_result_id {
+        Some(result_id) if result_id.rsplit_once(':').map(|(key, _)| key) == Some(&file_key) => {
+            Some(PullDiagnosticReport::Unchanged {
+                result_id: result_id.to_string(),
+            })
+        }
+        _ => document_diagnostic(analysis, file_id, previous_result_id, cancel_token),
+    }
+}
+
+/// The config revision and a hash of the text of the file and of every file it requires,
+/// directly or through other files
+fn get_file_key(analysis: &EmmyLuaAnalysis, file_id: FileId) -> String {
+    let db = analysis.compilation.get_db();
+    let vfs = db.get_vfs();
+    let mut dependencies = db
+        .get_file_dependencies_index()
+        .get_file_dependencies()
+        .collect_file_dependencies(file_id);
+    dependencies.sort_by_key(|dependency| dependency.id);
+
+    let mut hasher = DefaultHasher::new();
+    vfs.get_file_content(&file_id).hash(&mut hasher);
+    for dependency in dependencies {
+        dependency.hash(&mut hasher);
+        vfs.get_file_content(&dependency).hash(&mut hasher);
+    }
+    format!("{}:{:x}", analysis.get_config_revision(), hasher.finish())
+}
+
+/// Hashes the diagnostics independent of their order, some checkers report them in hash
+/// map order
+fn hash_diagnostics(diagnostics: &[Diagnostic]) -> u64 {
+    let mut diagnostics = diagnostics
+        .iter()
+        .filter_map(|diagnostic| serde_json::to_string(diagnostic).ok())
+        .collect::<Vec<_>>();
+    diagnostics.sort();
+
+    let mut hasher = DefaultHasher::new();
+    diagnostics.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub struct DiagnosticCapabilities;
+
+impl RegisterCapabilities for DiagnosticCapabilities {
+    fn register_capabilities(
+        server_capabilities: &mut ServerCapabilities,
+        client_capabilities: &ClientCapabilities,
+    ) {
+        if !is_pull_diagnostic_supported(client_capabilities) {
+            return;
+        }
+
+        server_capabilities.diagnostic_provider =
+            Some(DiagnosticServerCapabilities::Options(DiagnosticOptions {
+                identifier: Some("EmmyLua".to_string()),
+                inter_file_dependencies: true,
+                workspace_diagnostics: true,
+                work_done_progress_options: WorkDoneProgressOptions::default(),
+            }));
+    }
+}
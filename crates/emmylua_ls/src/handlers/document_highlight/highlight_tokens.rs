@@ -124,6 +124,19 @@ fn highlight_keywords(
 ) -> Option<()> {
     let document = semantic_model.get_document();
     let parent_node = token.parent()?;
+    // the `end` of a function statement belongs to its closure, `function` to the statement
+    let parent_node = match parent_node.parent() {
+        Some(stat)
+            if parent_node.kind() == LuaSyntaxKind::ClosureExpr.into()
+                && matches!(
+                    stat.kind().into(),
+                    LuaSyntaxKind::LocalFuncStat | LuaSyntaxKind::FuncStat
+                ) =>
+        {
+            stat
+        }
+        _ => parent_node,
+    };
     match parent_node.kind().into() {
         LuaSyntaxKind::LocalFuncStat | LuaSyntaxKind::FuncStat => {
             highlight_node_keywords(&document, parent_node.clone(), result);
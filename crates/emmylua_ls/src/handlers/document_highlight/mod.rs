@@ -1,14 +1,14 @@
 mod highlight_tokens;
 
-use emmylua_parser::{LuaAstNode, LuaTokenKind};
+use emmylua_code_analysis::{EmmyLuaAnalysis, FileId};
 use highlight_tokens::highlight_tokens;
 use lsp_types::{
-    ClientCapabilities, DocumentHighlight, DocumentHighlightParams, OneOf, ServerCapabilities,
+    ClientCapabilities, DocumentHighlight, DocumentHighlightParams, OneOf, Position,
+    ServerCapabilities,
 };
-use rowan::TokenAtOffset;
 use tokio_util::sync::CancellationToken;
 
-use crate::context::ServerContextSnapshot;
+use crate::{context::ServerContextSnapshot, util::find_token_at_position};
 
 use super::RegisterCapabilities;
 
@@ -21,32 +21,20 @@ pub async fn on_document_highlight_handler(
     let analysis = context.analysis.read().await;
     let file_id = analysis.get_file_id(&uri)?;
     let position = params.text_document_position_params.position;
-    let mut semantic_model = analysis.compilation.get_semantic_model(file_id)?;
-    let root = semantic_model.get_root();
-    let position_offset = {
-        let document = semantic_model.get_document();
-        document.get_offset(position.line as usize, position.character as usize)?
-    };
-
-    if position_offset > root.syntax().text_range().end() {
-        return None;
-    }
 
-    let token = match root.syntax().token_at_offset(position_offset) {
-        TokenAtOffset::Single(token) => token,
-        TokenAtOffset::Between(left, right) => {
-            if left.kind() == LuaTokenKind::TkName.into() {
-                left
-            } else {
-                right
-            }
-        }
-        TokenAtOffset::None => {
-            return None;
-        }
-    };
-
-    highlight_tokens(&mut semantic_model, token)
+    document_highlight(&analysis, file_id, position)
+}
+
+/// The occurrences of the name at `position`, or the keywords of the block statement of the
+/// keyword at `position`, such as `function` and its `end`
+pub fn document_highlight(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    position: Position,
+) -> Option<Vec<DocumentHighlight>> {
+    let semantic_model = analysis.compilation.get_semantic_model(file_id)?;
+    let token = find_token_at_position(&semantic_model, position)?;
+    highlight_tokens(&semantic_model, token)
 }
 
 pub struct DocumentHighlightCapabilities;
@@ -1,8 +1,12 @@
-use emmylua_code_analysis::{FormattingOptions, range_format_code};
+use emmylua_code_analysis::{EmmyLuaAnalysis, FileId, LuaDocument};
+use emmylua_code_style::{
+    LuaCodeStyle, LuaEndOfLine, LuaIndent, get_line_indent, reformat_node_with_indent,
+};
+use emmylua_parser::{LuaAst, LuaAstNode, LuaBlock, LuaStat};
 use lsp_types::{
-    ClientCapabilities, DocumentRangeFormattingParams, OneOf, Position, Range, ServerCapabilities,
-    TextEdit,
+    ClientCapabilities, DocumentRangeFormattingParams, OneOf, Range, ServerCapabilities, TextEdit,
 };
+use rowan::{TextRange, TextSize};
 use tokio_util::sync::CancellationToken;
 
 use crate::context::ServerContextSnapshot;
@@ -15,66 +19,116 @@ pub async fn on_range_formatting_handler(
     _: CancellationToken,
 ) -> Option<Vec<TextEdit>> {
     let uri = params.text_document.uri;
-    let request_range = params.range;
     let analysis = context.analysis.read().await;
     let config_manager = context.workspace_manager.read().await;
     let client_id = config_manager.client_config.client_id;
     let file_id = analysis.get_file_id(&uri)?;
-    let syntax_tree = analysis
-        .compilation
-        .get_db()
-        .get_vfs()
-        .get_syntax_tree(&file_id)?;
 
+    let styles = LuaCodeStyle {
+        indent: if params.options.insert_spaces {
+            LuaIndent::Space(params.options.tab_size as usize)
+        } else {
+            LuaIndent::Tab
+        },
+        end_of_line: if client_id.is_intellij() || client_id.is_other() {
+            LuaEndOfLine::LF
+        } else {
+            LuaEndOfLine::Auto
+        },
+        ..Default::default()
+    };
+
+    range_format(&analysis, file_id, params.range, &styles)
+}
+
+/// Formats each statement of the innermost block that overlaps `range` on its own, keeping
+/// the indentation of its first line, and returns one edit per statement that changes
+pub fn range_format(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    range: Range,
+    styles: &LuaCodeStyle,
+) -> Option<Vec<TextEdit>> {
+    let vfs = analysis.compilation.get_db().get_vfs();
+    let syntax_tree = vfs.get_syntax_tree(&file_id)?;
     if syntax_tree.has_syntax_errors() {
         return None;
     }
-    let emmyrc = analysis.get_emmyrc();
-    let document = analysis
-        .compilation
-        .get_db()
-        .get_vfs()
-        .get_document(&file_id)?;
+
+    let document = vfs.get_document(&file_id)?;
     let text = document.get_text();
-    let file_path = document.get_file_path();
-    let normalized_path = file_path.to_string_lossy().to_string().replace("\\", "/");
-    let formatting_options = FormattingOptions {
-        indent_size: params.options.tab_size,
-        use_tabs: !params.options.insert_spaces,
-        insert_final_newline: params.options.insert_final_newline.unwrap_or(true),
-        non_standard_symbol: !emmyrc.runtime.nonstandard_symbol.is_empty(),
-    };
-    let formatted_result = range_format_code(
-        text,
-        &normalized_path,
-        request_range.start.line as i32,
-        0,
-        request_range.end.line as i32 + 1,
-        0,
-        formatting_options,
-    )?;
-
-    let mut formatted_text = formatted_result.text;
-
-    if client_id.is_intellij() || client_id.is_other() {
-        formatted_text = formatted_text.replace("\r\n", "\n");
-    }
+    let text_range = document.to_rowan_range(range)?;
+    let root = syntax_tree.get_chunk_node();
+    let covering = root.syntax().covering_element(text_range);
+    let block = match covering {
+        rowan::NodeOrToken::Node(node) => node.ancestors().find_map(LuaBlock::cast),
+        rowan::NodeOrToken::Token(token) => token.parent_ancestors().find_map(LuaBlock::cast),
+    }?;
 
-    let text_edit = TextEdit {
-        range: Range {
-            start: Position {
-                line: formatted_result.start_line as u32,
-                character: formatted_result.start_col as u32,
-            },
-            end: Position {
-                line: formatted_result.end_line as u32 + 1,
-                character: 0,
-            },
+    // the source may use `\r\n` even when the statements to format are single lines
+    let styles = LuaCodeStyle {
+        end_of_line: match styles.end_of_line {
+            LuaEndOfLine::Auto if text.contains("\r\n") => LuaEndOfLine::CRLF,
+            LuaEndOfLine::Auto => LuaEndOfLine::LF,
+            end_of_line => end_of_line,
         },
-        new_text: formatted_text,
+        ..styles.clone()
     };
 
-    Some(vec![text_edit])
+    let mut edits = Vec::new();
+    for stat in block.get_stats() {
+        let stat_range = stat.get_range();
+        if !is_overlapped(stat_range, text_range) {
+            continue;
+        }
+
+        let base_indent = get_line_indent(text, stat_range.start().into());
+        let node = LuaAst::cast(stat.syntax().clone())?;
+        let formatted = reformat_node_with_indent(&node, base_indent, &styles);
+        if let Some(edit) = get_minimal_edit(&document, &stat, &formatted) {
+            edits.push(edit);
+        }
+    }
+
+    Some(edits)
+}
+
+fn is_overlapped(stat_range: TextRange, range: TextRange) -> bool {
+    if range.is_empty() {
+        stat_range.contains_inclusive(range.start())
+    } else {
+        stat_range.intersect(range).is_some_and(|it| !it.is_empty())
+    }
+}
+
+/// Replaces only the part of the statement between the first and the last changed character
+fn get_minimal_edit(document: &LuaDocument, stat: &LuaStat, formatted: &str) -> Option<TextEdit> {
+    let stat_range = stat.get_range();
+    let original = document.get_text_slice(stat_range);
+    if original == formatted {
+        return None;
+    }
+
+    let prefix_len = original
+        .char_indices()
+        .zip(formatted.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((i, _), _)| i)
+        .unwrap_or(original.len().min(formatted.len()));
+    let suffix_len = original[prefix_len..]
+        .chars()
+        .rev()
+        .zip(formatted[prefix_len..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+
+    let start = stat_range.start() + TextSize::from(prefix_len as u32);
+    let end = stat_range.end() - TextSize::from(suffix_len as u32);
+    Some(TextEdit {
+        range: document.to_lsp_range(TextRange::new(start, end))?,
+        new_text: formatted[prefix_len..formatted.len() - suffix_len].to_string(),
+    })
 }
 
 pub struct DocumentRangeFormatting;
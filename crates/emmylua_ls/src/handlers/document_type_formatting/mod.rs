@@ -0,0 +1,210 @@
+use emmylua_code_analysis::{EmmyLuaAnalysis, FileId, LuaDocument};
+use emmylua_code_style::{LuaIndent, get_line_indent, is_block_open};
+use emmylua_parser::{LuaAstNode, LuaSyntaxKind, LuaSyntaxNode, LuaSyntaxToken, LuaTokenKind};
+use lsp_types::{
+    ClientCapabilities, DocumentOnTypeFormattingOptions, DocumentOnTypeFormattingParams, Position,
+    ServerCapabilities, TextEdit,
+};
+use rowan::{TextRange, TextSize};
+use tokio_util::sync::CancellationToken;
+
+use crate::context::ServerContextSnapshot;
+
+use super::RegisterCapabilities;
+
+pub async fn on_type_formatting_handler(
+    context: ServerContextSnapshot,
+    params: DocumentOnTypeFormattingParams,
+    _: CancellationToken,
+) -> Option<Vec<TextEdit>> {
+    let uri = params.text_document_position.text_document.uri;
+    let position = params.text_document_position.position;
+    let analysis = context.analysis.read().await;
+    let file_id = analysis.get_file_id(&uri)?;
+    let indent = if params.options.insert_spaces {
+        LuaIndent::Space(params.options.tab_size as usize)
+    } else {
+        LuaIndent::Tab
+    };
+
+    type_formatting(&analysis, file_id, position, &params.ch, &indent)
+}
+
+/// Re-indents the line of `position` after `ch` was typed: a new line gets the indentation of
+/// the block it is in, and a line starting with a closing keyword or bracket is aligned with
+/// the line that opened it.
+pub fn type_formatting(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    position: Position,
+    ch: &str,
+    indent: &LuaIndent,
+) -> Option<Vec<TextEdit>> {
+    let vfs = analysis.compilation.get_db().get_vfs();
+    let document = vfs.get_document(&file_id)?;
+    let root = vfs.get_syntax_tree(&file_id)?.get_chunk_node();
+    let line = position.line as usize;
+    let line_start = document.get_offset(line, 0)?;
+
+    let expected_indent = if ch == "\n" {
+        get_new_line_indent(&document, root.syntax(), line_start, indent)?
+    } else {
+        let offset = document.get_offset(line, position.character as usize)?;
+        let token = root.syntax().token_at_offset(offset).left_biased()?;
+        if token.text_range().end() != offset || !is_line_start(&document, &token) {
+            return None;
+        }
+        get_closer_indent(&document, &token)?
+    };
+
+    let current_indent = get_line_indent(document.get_text(), line_start.into());
+    if current_indent == expected_indent {
+        return Some(vec![]);
+    }
+
+    let indent_end = line_start + TextSize::from(current_indent.len() as u32);
+    Some(vec![TextEdit {
+        range: document.to_lsp_range(TextRange::new(line_start, indent_end))?,
+        new_text: expected_indent,
+    }])
+}
+
+fn get_new_line_indent(
+    document: &LuaDocument,
+    root: &LuaSyntaxNode,
+    line_start: TextSize,
+    indent: &LuaIndent,
+) -> Option<String> {
+    // the rest of the line may start with the keyword that closes the block
+    let next_token = root
+        .token_at_offset(line_start)
+        .right_biased()
+        .and_then(|token| {
+            if is_trivia(&token) {
+                next_significant_token(&token)
+            } else {
+                Some(token)
+            }
+        });
+    let closer_indent = next_token
+        .filter(|token| {
+            is_line_start(document, token)
+                && document.get_line(token.text_range().start()) == document.get_line(line_start)
+        })
+        .and_then(|token| get_closer_indent(document, &token));
+    if closer_indent.is_some() {
+        return closer_indent;
+    }
+
+    let prev_token = root
+        .token_at_offset(line_start)
+        .left_biased()
+        .and_then(|token| {
+            if is_trivia(&token) || token.text_range().start() >= line_start {
+                prev_significant_token(&token)
+            } else {
+                Some(token)
+            }
+        });
+    let Some(prev_token) = prev_token else {
+        return Some(String::new());
+    };
+
+    let prev_indent = get_line_indent(document.get_text(), prev_token.text_range().start().into());
+    if is_block_open(&prev_token) {
+        Some(format!("{}{}", prev_indent, indent.indent_text(1)))
+    } else {
+        Some(prev_indent.to_string())
+    }
+}
+
+/// The indentation of the line that opened the block or bracket `token` closes
+fn get_closer_indent(document: &LuaDocument, token: &LuaSyntaxToken) -> Option<String> {
+    let parent = token.parent()?;
+    let owner = match token.kind().into() {
+        LuaTokenKind::TkEnd
+        | LuaTokenKind::TkUntil
+        | LuaTokenKind::TkRightBrace
+        | LuaTokenKind::TkRightParen => parent,
+        LuaTokenKind::TkElse | LuaTokenKind::TkElseIf => {
+            if matches!(
+                parent.kind().into(),
+                LuaSyntaxKind::ElseClauseStat | LuaSyntaxKind::ElseIfClauseStat
+            ) {
+                parent.parent()?
+            } else {
+                parent
+            }
+        }
+        _ => return None,
+    };
+
+    let owner_start = owner.text_range().start();
+    if document.get_line(owner_start) == document.get_line(token.text_range().start()) {
+        return None;
+    }
+    Some(get_line_indent(document.get_text(), owner_start.into()).to_string())
+}
+
+fn is_line_start(document: &LuaDocument, token: &LuaSyntaxToken) -> bool {
+    let start = token.text_range().start();
+    let Some(line_range) = document
+        .get_line(start)
+        .and_then(|line| document.get_line_range(line))
+    else {
+        return false;
+    };
+    document
+        .get_text_slice(TextRange::new(line_range.start(), start))
+        .chars()
+        .all(|c| c == ' ' || c == '\t')
+}
+
+/// Whitespace, line endings and comments, which do not affect the block structure
+fn is_trivia(token: &LuaSyntaxToken) -> bool {
+    matches!(
+        token.kind().into(),
+        LuaTokenKind::TkWhitespace | LuaTokenKind::TkEndOfLine
+    ) || token
+        .parent_ancestors()
+        .any(|node| node.kind() == LuaSyntaxKind::Comment.into())
+}
+
+fn prev_significant_token(token: &LuaSyntaxToken) -> Option<LuaSyntaxToken> {
+    let mut current = token.prev_token();
+    while let Some(token) = current {
+        if !is_trivia(&token) {
+            return Some(token);
+        }
+        current = token.prev_token();
+    }
+    None
+}
+
+fn next_significant_token(token: &LuaSyntaxToken) -> Option<LuaSyntaxToken> {
+    let mut current = token.next_token();
+    while let Some(token) = current {
+        if !is_trivia(&token) {
+            return Some(token);
+        }
+        current = token.next_token();
+    }
+    None
+}
+
+pub struct DocumentTypeFormatting;
+
+impl RegisterCapabilities for DocumentTypeFormatting {
+    fn register_capabilities(server_capabilities: &mut ServerCapabilities, _: &ClientCapabilities) {
+        server_capabilities.document_on_type_formatting_provider =
+            Some(DocumentOnTypeFormattingOptions {
+                first_trigger_character: "\n".to_string(),
+                more_trigger_character: Some(
+                    ["d", "l", "e", "f", "}", ")"]
+                        .iter()
+                        .map(|ch| ch.to_string())
+                        .collect(),
+                ),
+            });
+    }
+}
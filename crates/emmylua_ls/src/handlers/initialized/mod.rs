@@ -13,7 +13,7 @@ use crate::{
     },
     handlers::{
         initialized::collect_files::calculate_include_and_exclude,
-        text_document::register_files_watch,
+        text_document::register_files_watch, type_hierarchy::register_type_hierarchy,
     },
     logger::init_logger,
 };
@@ -97,6 +97,7 @@ pub async fn initialized_handler(
         log::info!("workspace manager initialized");
     }
     register_files_watch(context.clone(), &params.capabilities).await;
+    register_type_hierarchy(&context.client, &params.capabilities);
     Some(())
 }
 
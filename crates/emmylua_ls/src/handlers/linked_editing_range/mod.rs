@@ -0,0 +1,124 @@
+use emmylua_code_analysis::{
+    EmmyLuaAnalysis, FileId, LuaDeclId, LuaSemanticDeclId, SemanticDeclLevel, SemanticModel,
+};
+use emmylua_parser::{LuaSyntaxToken, LuaTokenKind};
+use lsp_types::{
+    ClientCapabilities, LinkedEditingRangeParams, LinkedEditingRangeServerCapabilities,
+    LinkedEditingRanges, Position, Range, ServerCapabilities,
+};
+use rowan::{TextRange, TextSize};
+use tokio_util::sync::CancellationToken;
+
+use super::RegisterCapabilities;
+use crate::{context::ServerContextSnapshot, util::find_token_at_position};
+
+pub async fn on_linked_editing_range_handler(
+    context: ServerContextSnapshot,
+    params: LinkedEditingRangeParams,
+    _: CancellationToken,
+) -> Option<LinkedEditingRanges> {
+    let uri = params.text_document_position_params.text_document.uri;
+    let analysis = context.analysis.read().await;
+    let file_id = analysis.get_file_id(&uri)?;
+    let position = params.text_document_position_params.position;
+
+    linked_editing_range(&analysis, file_id, position)
+}
+
+/// The ranges of a local name. Linked ranges must hold the same text, so a block keyword and
+/// its `end` are not linked, document highlight shows those pairs.
+pub fn linked_editing_range(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    position: Position,
+) -> Option<LinkedEditingRanges> {
+    let semantic_model = analysis.compilation.get_semantic_model(file_id)?;
+    let token = find_token_at_position(&semantic_model, position)?;
+    if token.kind() != LuaTokenKind::TkName.into() {
+        return None;
+    }
+    let ranges = get_local_name_ranges(&semantic_model, &token)?;
+
+    let document = semantic_model.get_document();
+    let mut ranges = ranges
+        .into_iter()
+        .map(|range| document.to_lsp_range(range))
+        .collect::<Option<Vec<Range>>>()?;
+    ranges.sort_by_key(|range| (range.start.line, range.start.character));
+    ranges.dedup();
+    if ranges.len() < 2 {
+        return None;
+    }
+
+    Some(LinkedEditingRanges {
+        ranges,
+        word_pattern: None,
+    })
+}
+
+/// The declaration of a local and all its references, which are all in the scope of the
+/// local
+fn get_local_name_ranges(
+    semantic_model: &SemanticModel,
+    token: &LuaSyntaxToken,
+) -> Option<Vec<TextRange>> {
+    let LuaSemanticDeclId::LuaDecl(decl_id) =
+        semantic_model.find_decl(token.clone().into(), SemanticDeclLevel::NoTrace)?
+    else {
+        return None;
+    };
+    let decl = semantic_model
+        .get_db()
+        .get_decl_index()
+        .get_decl(&decl_id)?;
+    if !decl.is_local() {
+        return None;
+    }
+
+    let name = token.text();
+    let mut ranges = vec![get_name_range(semantic_model, decl.get_range(), name)?];
+    let decl_refs = get_decl_references(semantic_model, &decl_id)?;
+    for decl_ref in decl_refs {
+        // every range has to hold the same text, or the edits would not be mirrored
+        ranges.push(get_name_range(semantic_model, decl_ref, name)?);
+    }
+
+    Some(ranges)
+}
+
+fn get_decl_references(
+    semantic_model: &SemanticModel,
+    decl_id: &LuaDeclId,
+) -> Option<Vec<TextRange>> {
+    let decl_refs = semantic_model
+        .get_db()
+        .get_reference_index()
+        .get_decl_references(&decl_id.file_id, decl_id)?;
+
+    Some(decl_refs.cells.iter().map(|cell| cell.range).collect())
+}
+
+/// The range of the name at the start of `range`, a local name can be followed by its
+/// attribute
+fn get_name_range(
+    semantic_model: &SemanticModel,
+    range: TextRange,
+    name: &str,
+) -> Option<TextRange> {
+    let name_range = TextRange::at(range.start(), TextSize::of(name));
+    let document = semantic_model.get_document();
+    if name_range.end() > range.end() || document.get_text_slice(name_range) != name {
+        return None;
+    }
+
+    Some(name_range)
+}
+
+pub struct LinkedEditingRangeCapabilities;
+
+impl RegisterCapabilities for LinkedEditingRangeCapabilities {
+    fn register_capabilities(server_capabilities: &mut ServerCapabilities, _: &ClientCapabilities) {
+        server_capabilities.linked_editing_range_provider =
+            Some(LinkedEditingRangeServerCapabilities::Simple(true));
+    }
+}
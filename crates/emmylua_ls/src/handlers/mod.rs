@@ -4,7 +4,9 @@ mod code_lens;
 mod command;
 mod completion;
 mod configuration;
+mod declaration;
 mod definition;
+mod diagnostic;
 mod document_color;
 mod document_formatting;
 mod document_highlight;
@@ -12,6 +14,7 @@ mod document_link;
 mod document_range_formatting;
 mod document_selection_range;
 mod document_symbol;
+mod document_type_formatting;
 mod emmy_annotator;
 mod fold_range;
 mod hover;
@@ -19,6 +22,7 @@ mod implementation;
 mod initialized;
 mod inlay_hint;
 mod inline_values;
+mod linked_editing_range;
 mod notification_handler;
 mod references;
 mod rename;
@@ -27,6 +31,8 @@ mod response_handler;
 mod semantic_token;
 mod signature_helper;
 mod text_document;
+mod type_definition;
+mod type_hierarchy;
 mod workspace;
 mod workspace_symbol;
 
@@ -40,6 +46,7 @@ use lsp_types::{ClientCapabilities, ServerCapabilities};
 pub use notification_handler::on_notification_handler;
 pub use request_handler::on_req_handler;
 pub use response_handler::on_response_handler;
+pub use semantic_token::SemanticTokensCache;
 
 pub trait RegisterCapabilities {
     fn register_capabilities(
@@ -93,6 +100,12 @@ pub fn server_capabilities(client_capabilities: &ClientCapabilities) -> ServerCa
     register::<completion::CompletionCapabilities>(&mut server_capabilities, client_capabilities);
     register::<inlay_hint::InlayHintCapabilities>(&mut server_capabilities, client_capabilities);
     register::<definition::DefinitionCapabilities>(&mut server_capabilities, client_capabilities);
+    register::<type_definition::TypeDefinitionCapabilities>(
+        &mut server_capabilities,
+        client_capabilities,
+    );
+    register::<declaration::DeclarationCapabilities>(&mut server_capabilities, client_capabilities);
+    register::<diagnostic::DiagnosticCapabilities>(&mut server_capabilities, client_capabilities);
     register::<implementation::ImplementationCapabilities>(
         &mut server_capabilities,
         client_capabilities,
@@ -132,10 +145,14 @@ pub fn server_capabilities(client_capabilities: &ClientCapabilities) -> ServerCa
         client_capabilities,
     );
     register::<workspace::WorkspaceCapabilities>(&mut server_capabilities, client_capabilities);
-    // register::<document_type_formatting::DocumentTypeFormatting>(
-    //     &mut server_capabilities,
-    //     client_capabilities,
-    // );
+    register::<document_type_formatting::DocumentTypeFormatting>(
+        &mut server_capabilities,
+        client_capabilities,
+    );
+    register::<linked_editing_range::LinkedEditingRangeCapabilities>(
+        &mut server_capabilities,
+        client_capabilities,
+    );
 
     server_capabilities
 }
@@ -5,12 +5,15 @@ use lsp_server::{Request, RequestId, Response};
 use lsp_types::request::{
     CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare,
     CodeActionRequest, CodeLensRequest, CodeLensResolve, ColorPresentationRequest, Completion,
-    DocumentColor, DocumentHighlightRequest, DocumentLinkRequest, DocumentLinkResolve,
-    DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest, Formatting, GotoDefinition,
-    GotoImplementation, HoverRequest, InlayHintRequest, InlayHintResolveRequest,
-    InlineValueRequest, PrepareRenameRequest, RangeFormatting, References, Rename,
-    ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullRequest, SignatureHelpRequest,
-    WorkspaceSymbolRequest,
+    DocumentColor, DocumentDiagnosticRequest, DocumentHighlightRequest, DocumentLinkRequest,
+    DocumentLinkResolve, DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest, Formatting,
+    GotoDeclaration, GotoDefinition, GotoImplementation, GotoTypeDefinition, HoverRequest,
+    InlayHintRequest, InlayHintResolveRequest, InlineValueRequest, LinkedEditingRange,
+    OnTypeFormatting, PrepareRenameRequest, RangeFormatting, References, Rename,
+    ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullDeltaRequest,
+    SemanticTokensFullRequest, SemanticTokensRangeRequest, SignatureHelpRequest,
+    TypeHierarchyPrepare, TypeHierarchySubtypes, TypeHierarchySupertypes,
+    WorkspaceDiagnosticRequest, WorkspaceSymbolRequest,
 };
 use serde::{Serialize, de::DeserializeOwned};
 use tokio_util::sync::CancellationToken;
@@ -25,7 +28,9 @@ use super::{
     code_lens::{on_code_lens_handler, on_resolve_code_lens_handler},
     command::on_execute_command_handler,
     completion::{on_completion_handler, on_completion_resolve_handler},
+    declaration::on_declaration_handler,
     definition::on_goto_definition_handler,
+    diagnostic::{on_document_diagnostic_handler, on_workspace_diagnostic_handler},
     document_color::{on_document_color, on_document_color_presentation},
     document_formatting::on_formatting_handler,
     document_highlight::on_document_highlight_handler,
@@ -33,16 +38,24 @@ use super::{
     document_range_formatting::on_range_formatting_handler,
     document_selection_range::on_document_selection_range_handle,
     document_symbol::on_document_symbol,
+    document_type_formatting::on_type_formatting_handler,
     emmy_annotator::{EmmyAnnotatorRequest, on_emmy_annotator_handler},
     fold_range::on_folding_range_handler,
     hover::on_hover,
     implementation::on_implementation_handler,
     inlay_hint::{on_inlay_hint_handler, on_resolve_inlay_hint},
     inline_values::on_inline_values_handler,
+    linked_editing_range::on_linked_editing_range_handler,
     references::on_references_handler,
     rename::{on_prepare_rename_handler, on_rename_handler},
-    semantic_token::on_semantic_token_handler,
+    semantic_token::{
+        on_semantic_token_delta_handler, on_semantic_token_handler, on_semantic_token_range_handler,
+    },
     signature_helper::on_signature_helper_handler,
+    type_definition::on_type_definition_handler,
+    type_hierarchy::{
+        on_prepare_type_hierarchy_handler, on_subtypes_handler, on_supertypes_handler,
+    },
     workspace_symbol::on_workspace_symbol_handler,
 };
 
@@ -81,6 +94,14 @@ pub async fn on_req_handler(
         .await
         .on_parallel::<GotoImplementation, _, _>(on_implementation_handler)
         .await
+        .on_parallel::<GotoTypeDefinition, _, _>(on_type_definition_handler)
+        .await
+        .on_parallel::<GotoDeclaration, _, _>(on_declaration_handler)
+        .await
+        .on_parallel_optional::<DocumentDiagnosticRequest, _, _>(on_document_diagnostic_handler)
+        .await
+        .on_parallel_optional::<WorkspaceDiagnosticRequest, _, _>(on_workspace_diagnostic_handler)
+        .await
         .on_parallel::<References, _, _>(on_references_handler)
         .await
         .on_parallel::<Rename, _, _>(on_rename_handler)
@@ -95,8 +116,14 @@ pub async fn on_req_handler(
         .await
         .on_parallel::<DocumentHighlightRequest, _, _>(on_document_highlight_handler)
         .await
+        .on_parallel::<LinkedEditingRange, _, _>(on_linked_editing_range_handler)
+        .await
         .on_parallel::<SemanticTokensFullRequest, _, _>(on_semantic_token_handler)
         .await
+        .on_parallel::<SemanticTokensFullDeltaRequest, _, _>(on_semantic_token_delta_handler)
+        .await
+        .on_parallel::<SemanticTokensRangeRequest, _, _>(on_semantic_token_range_handler)
+        .await
         .on_parallel::<ExecuteCommand, _, _>(on_execute_command_handler)
         .await
         .on_parallel::<CodeActionRequest, _, _>(on_code_action_handler)
@@ -109,12 +136,20 @@ pub async fn on_req_handler(
         .await
         .on_parallel::<RangeFormatting, _, _>(on_range_formatting_handler)
         .await
+        .on_parallel::<OnTypeFormatting, _, _>(on_type_formatting_handler)
+        .await
         .on_parallel::<CallHierarchyPrepare, _, _>(on_prepare_call_hierarchy_handler)
         .await
         .on_parallel::<CallHierarchyIncomingCalls, _, _>(on_incoming_calls_handler)
         .await
         .on_parallel::<CallHierarchyOutgoingCalls, _, _>(on_outgoing_calls_handler)
         .await
+        .on_parallel::<TypeHierarchyPrepare, _, _>(on_prepare_type_hierarchy_handler)
+        .await
+        .on_parallel::<TypeHierarchySupertypes, _, _>(on_supertypes_handler)
+        .await
+        .on_parallel::<TypeHierarchySubtypes, _, _>(on_subtypes_handler)
+        .await
         .finish();
     Ok(())
 }
@@ -159,6 +194,33 @@ impl<'a> RequestDispatcher<'a> {
         self
     }
 
+    /// Like `on_parallel`, for handlers which return `None` when they were cancelled, the
+    /// client then gets a `RequestCanceled` error instead of a result
+    pub async fn on_parallel_optional<R, F, Fut>(&mut self, handler: F) -> &mut Self
+    where
+        R: lsp_types::request::Request + 'static,
+        R::Params: DeserializeOwned + Send + std::fmt::Debug + 'static,
+        R::Result: Serialize + 'static,
+        F: Fn(ServerContextSnapshot, R::Params, CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = Option<R::Result>> + Send + 'static,
+    {
+        let req = match &self.req {
+            Some(req) if req.method == R::METHOD => self.req.take().unwrap(),
+            _ => return self,
+        };
+
+        let snapshot = self.context.snapshot();
+        let id = req.id.clone();
+        let m: Result<(RequestId, R::Params), _> = req.extract(R::METHOD);
+        self.context
+            .task(id.clone(), |cancel_token| async move {
+                let result = handler(snapshot, m.unwrap().1, cancel_token).await?;
+                Some(Response::new_ok(id, result))
+            })
+            .await;
+        self
+    }
+
     pub fn finish(&mut self) {
         if let Some(req) = &self.req {
             error!("handler not found for request. [{}]", req.method);
@@ -16,13 +16,14 @@ use emmylua_parser::{
 };
 use emmylua_parser_desc::{CodeBlockHighlightKind, DescItem, DescItemKind};
 use lsp_types::{SemanticToken, SemanticTokenModifier, SemanticTokenType};
-use rowan::{NodeOrToken, TextRange, TextSize};
+use rowan::{NodeOrToken, TextRange, TextSize, WalkEvent};
 
 pub fn build_semantic_tokens(
     semantic_model: &SemanticModel,
     support_muliline_token: bool,
     client_id: ClientId,
     emmyrc: &Emmyrc,
+    range: Option<TextRange>,
 ) -> Option<Vec<SemanticToken>> {
     let root = semantic_model.get_root();
     let document = semantic_model.get_document();
@@ -31,9 +32,22 @@ pub fn build_semantic_tokens(
         support_muliline_token,
         SEMANTIC_TOKEN_TYPES.to_vec(),
         SEMANTIC_TOKEN_MODIFIERS.to_vec(),
+        range,
     );
 
-    for node_or_token in root.syntax().descendants_with_tokens() {
+    let mut preorder = root.syntax().preorder_with_tokens();
+    while let Some(event) = preorder.next() {
+        let WalkEvent::Enter(node_or_token) = event else {
+            continue;
+        };
+        // skip the nodes outside of the requested range
+        if !builder.is_in_range(node_or_token.text_range()) {
+            if node_or_token.as_node().is_some() {
+                preorder.skip_subtree();
+            }
+            continue;
+        }
+
         match node_or_token {
             NodeOrToken::Node(node) => {
                 build_node_semantic_token(semantic_model, &mut builder, node, emmyrc);
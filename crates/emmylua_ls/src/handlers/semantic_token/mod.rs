@@ -7,11 +7,15 @@ use crate::context::{ClientId, ServerContextSnapshot};
 use build_semantic_tokens::build_semantic_tokens;
 use emmylua_code_analysis::{EmmyLuaAnalysis, FileId};
 use lsp_types::{
-    ClientCapabilities, SemanticTokens, SemanticTokensFullOptions, SemanticTokensLegend,
-    SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
-    SemanticTokensServerCapabilities, ServerCapabilities,
+    ClientCapabilities, Range, SemanticTokens, SemanticTokensDeltaParams,
+    SemanticTokensFullDeltaResult, SemanticTokensFullOptions, SemanticTokensLegend,
+    SemanticTokensOptions, SemanticTokensParams, SemanticTokensRangeParams,
+    SemanticTokensRangeResult, SemanticTokensResult, SemanticTokensServerCapabilities,
+    ServerCapabilities,
+};
+pub use semantic_token_builder::{
+    SEMANTIC_TOKEN_MODIFIERS, SEMANTIC_TOKEN_TYPES, SemanticTokensCache,
 };
-pub use semantic_token_builder::{SEMANTIC_TOKEN_MODIFIERS, SEMANTIC_TOKEN_TYPES};
 use tokio_util::sync::CancellationToken;
 
 use super::RegisterCapabilities;
@@ -29,7 +33,58 @@ pub async fn on_semantic_token_handler(
     let client_id = workspace_manager.client_config.client_id;
     let _ = workspace_manager;
 
-    semantic_token(&analysis, file_id, &context.client_capabilities, client_id)
+    let mut result = semantic_token(&analysis, file_id, &context.client_capabilities, client_id)?;
+    if let SemanticTokensResult::Tokens(tokens) = &mut result {
+        let mut cache = context.semantic_tokens_cache.lock().await;
+        tokens.result_id = Some(cache.insert(uri, tokens.data.clone()));
+    }
+
+    Some(result)
+}
+
+pub async fn on_semantic_token_delta_handler(
+    context: ServerContextSnapshot,
+    params: SemanticTokensDeltaParams,
+    _: CancellationToken,
+) -> Option<SemanticTokensFullDeltaResult> {
+    let uri = params.text_document.uri;
+    let analysis = context.analysis.read().await;
+    let file_id = analysis.get_file_id(&uri)?;
+
+    let workspace_manager = context.workspace_manager.read().await;
+    let client_id = workspace_manager.client_config.client_id;
+    let _ = workspace_manager;
+
+    // the tokens are built before locking, so the requests of other documents do not wait
+    let SemanticTokensResult::Tokens(tokens) =
+        semantic_token(&analysis, file_id, &context.client_capabilities, client_id)?
+    else {
+        return None;
+    };
+    let mut cache = context.semantic_tokens_cache.lock().await;
+    Some(cache.delta(uri, &params.previous_result_id, tokens.data))
+}
+
+pub async fn on_semantic_token_range_handler(
+    context: ServerContextSnapshot,
+    params: SemanticTokensRangeParams,
+    _: CancellationToken,
+) -> Option<SemanticTokensRangeResult> {
+    let uri = params.text_document.uri;
+    let analysis = context.analysis.read().await;
+    let file_id = analysis.get_file_id(&uri)?;
+
+    let workspace_manager = context.workspace_manager.read().await;
+    let client_id = workspace_manager.client_config.client_id;
+    let _ = workspace_manager;
+
+    semantic_token_range(
+        &analysis,
+        file_id,
+        params.range,
+        &context.client_capabilities,
+        client_id,
+    )
 }
 
 pub fn semantic_token(
@@ -49,6 +104,7 @@ pub fn semantic_token(
         supports_multiline_tokens(client_capabilities),
         client_id,
         emmyrc,
+        None,
     )?;
 
     Some(SemanticTokensResult::Tokens(SemanticTokens {
@@ -57,6 +113,35 @@ pub fn semantic_token(
     }))
 }
 
+/// Builds the tokens of the visible part of a document only
+pub fn semantic_token_range(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    range: Range,
+    client_capabilities: &ClientCapabilities,
+    client_id: ClientId,
+) -> Option<SemanticTokensRangeResult> {
+    let semantic_model = analysis.compilation.get_semantic_model(file_id)?;
+    let emmyrc = semantic_model.get_emmyrc();
+    if !emmyrc.semantic_tokens.enable {
+        return None;
+    }
+
+    let range = semantic_model.get_document().to_rowan_range(range)?;
+    let result = build_semantic_tokens(
+        &semantic_model,
+        supports_multiline_tokens(client_capabilities),
+        client_id,
+        emmyrc,
+        Some(range),
+    )?;
+
+    Some(SemanticTokensRangeResult::Tokens(SemanticTokens {
+        result_id: None,
+        data: result,
+    }))
+}
+
 pub struct SemanticTokenCapabilities;
 
 impl RegisterCapabilities for SemanticTokenCapabilities {
@@ -70,7 +155,8 @@ impl RegisterCapabilities for SemanticTokenCapabilities {
                     token_modifiers: SEMANTIC_TOKEN_MODIFIERS.iter().cloned().collect(),
                     token_types: SEMANTIC_TOKEN_TYPES.iter().cloned().collect(),
                 },
-                full: Some(SemanticTokensFullOptions::Bool(true)),
+                full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                range: Some(true),
                 ..Default::default()
             }),
         );
@@ -1,6 +1,9 @@
 use emmylua_code_analysis::LuaDocument;
 use emmylua_parser::LuaSyntaxToken;
-use lsp_types::{SemanticToken, SemanticTokenModifier, SemanticTokenType};
+use lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensDelta,
+    SemanticTokensEdit, SemanticTokensFullDeltaResult, Uri,
+};
 use rowan::{TextRange, TextSize};
 use std::{
     collections::{HashMap, HashSet},
@@ -69,6 +72,7 @@ pub struct SemanticBuilder<'a> {
     modifier_to_id: HashMap<SemanticTokenModifier, u32>,
     data: HashMap<TextSize, SemanticTokenData>,
     string_special_range: HashSet<TextRange>,
+    range: Option<TextRange>,
 }
 
 impl<'a> SemanticBuilder<'a> {
@@ -77,6 +81,7 @@ impl<'a> SemanticBuilder<'a> {
         multi_line_support: bool,
         types: Vec<SemanticTokenType>,
         modifier: Vec<SemanticTokenModifier>,
+        range: Option<TextRange>,
     ) -> Self {
         let mut type_to_id = HashMap::new();
         for (i, ty) in types.into_iter().enumerate() {
@@ -94,12 +99,20 @@ impl<'a> SemanticBuilder<'a> {
             modifier_to_id,
             data: HashMap::new(),
             string_special_range: HashSet::new(),
+            range,
         }
     }
 
+    /// Whether the tokens in the range are requested, all tokens are when no range is set
+    pub fn is_in_range(&self, range: TextRange) -> bool {
+        self.range.is_none_or(|requested| {
+            requested.start() < range.end() && range.start() < requested.end()
+        })
+    }
+
     fn push_data(&mut self, range: TextRange, text: &str, typ: u32, modifiers: u32) -> Option<()> {
         let position = range.start();
-        if self.data.contains_key(&position) {
+        if self.data.contains_key(&position) || !self.is_in_range(range) {
             return Some(());
         }
 
@@ -184,6 +197,10 @@ impl<'a> SemanticBuilder<'a> {
         ty: SemanticTokenType,
         modifiers: Option<SemanticTokenModifier>,
     ) -> Option<()> {
+        if !self.is_in_range(TextRange::at(position, length.into())) {
+            return Some(());
+        }
+
         let lsp_position = self.document.to_lsp_position(position)?;
         let start_line = lsp_position.line;
         let start_col = lsp_position.character;
@@ -293,3 +310,90 @@ impl<'a> SemanticBuilder<'a> {
         self.string_special_range.contains(range)
     }
 }
+
+/// The last tokens sent for each document, so `semanticTokens/full/delta` only has to send
+/// the tokens which changed
+#[derive(Debug, Default)]
+pub struct SemanticTokensCache {
+    next_result_id: u64,
+    documents: HashMap<Uri, (String, Vec<SemanticToken>)>,
+}
+
+impl SemanticTokensCache {
+    /// Remembers the tokens sent for a document and returns their result id
+    pub fn insert(&mut self, uri: Uri, tokens: Vec<SemanticToken>) -> String {
+        self.next_result_id += 1;
+        let result_id = self.next_result_id.to_string();
+        self.documents.insert(uri, (result_id.clone(), tokens));
+        result_id
+    }
+
+    /// Returns the tokens of a document if they are the ones sent with `result_id`
+    pub fn get(&self, uri: &Uri, result_id: &str) -> Option<&Vec<SemanticToken>> {
+        let (cached_result_id, tokens) = self.documents.get(uri)?;
+        if cached_result_id != result_id {
+            return None;
+        }
+
+        Some(tokens)
+    }
+
+    pub fn remove(&mut self, uri: &Uri) {
+        self.documents.remove(uri);
+    }
+
+    /// Remembers the tokens of a document and returns the edits from the tokens sent with
+    /// `previous_result_id`, or all tokens when they are no longer cached
+    pub fn delta(
+        &mut self,
+        uri: Uri,
+        previous_result_id: &str,
+        tokens: Vec<SemanticToken>,
+    ) -> SemanticTokensFullDeltaResult {
+        let edits = self
+            .get(&uri, previous_result_id)
+            .map(|previous| diff_semantic_tokens(previous, &tokens));
+        let result_id = self.insert(uri, tokens.clone());
+        match edits {
+            Some(edits) => SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                result_id: Some(result_id),
+                edits,
+            }),
+            None => SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+                result_id: Some(result_id),
+                data: tokens,
+            }),
+        }
+    }
+}
+
+/// Builds a single edit replacing the tokens between the common prefix and suffix, the
+/// offsets count the integers of the encoded tokens, five per token
+pub fn diff_semantic_tokens(
+    old: &[SemanticToken],
+    new: &[SemanticToken],
+) -> Vec<SemanticTokensEdit> {
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let deleted = old.len() - prefix - suffix;
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return Vec::new();
+    }
+
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: (deleted * 5) as u32,
+        data: Some(inserted.to_vec()),
+    }]
+}
@@ -87,4 +87,55 @@ mod tests {
 
         Ok(())
     }
+
+    #[gtest]
+    fn test_prefix_unused() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_code_action(
+            r#"
+                local a = 1
+            "#,
+            vec![
+                VirtualCodeAction {
+                    title: "Prefix with an underscore".to_string()
+                },
+                VirtualCodeAction {
+                    title: "Disable current line diagnostic (unused)".to_string()
+                },
+                VirtualCodeAction {
+                    title: "Disable all diagnostics in current file (unused)".to_string()
+                },
+                VirtualCodeAction {
+                    title: "Disable all diagnostics in current project (unused)".to_string()
+                },
+            ]
+        ));
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_no_prefix_when_name_is_taken() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_code_action(
+            r#"
+                local a = 1
+                local _a = 2
+                return _a
+            "#,
+            vec![
+                VirtualCodeAction {
+                    title: "Disable current line diagnostic (unused)".to_string()
+                },
+                VirtualCodeAction {
+                    title: "Disable all diagnostics in current file (unused)".to_string()
+                },
+                VirtualCodeAction {
+                    title: "Disable all diagnostics in current project (unused)".to_string()
+                },
+            ]
+        ));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, check};
+    use googletest::prelude::*;
+
+    #[gtest]
+    fn test_convert_to_colon_method() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local M = {}
+            function M.<??>get<??>(self, key)
+                return self[key]
+            end
+            local a = M.get(M, "a")
+            local b = M:get("b")
+            local c = M.get({}, "c")
+            "#,
+            "Convert to method with `:`",
+            r#"
+            local M = {}
+            function M:get(key)
+                return self[key]
+            end
+            local a = M:get("a")
+            local b = M:get("b")
+            local c = M.get({}, "c")
+            "#,
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local M = {}
+            function M.<??>get<??>(key)
+                return key
+            end
+            "#,
+            "Convert to method with `:`",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_convert_to_dot_function() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local M = {}
+            function M:<??>size<??>()
+                return 0
+            end
+            local a = M:size()
+            local b = M:size "x"
+            "#,
+            "Convert to function with `.`",
+            r#"
+            local M = {}
+            function M.size(self)
+                return 0
+            end
+            local a = M.size(M)
+            local b = M.size(M, "x")
+            "#,
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_convert_local_function() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local function <??>add<??>(a, b)
+                return a + b
+            end
+            "#,
+            "Convert to local variable",
+            r#"
+            local add = function(a, b)
+                return a + b
+            end
+            "#,
+        ));
+        check!(ws.check_refactor(
+            r#"
+            local <??>add<??> = function(a, b)
+                return a + b
+            end
+            "#,
+            "Convert to local function",
+            r#"
+            local function add(a, b)
+                return a + b
+            end
+            "#,
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local function <??>fact<??>(n)
+                return n == 0 and 1 or n * fact(n - 1)
+            end
+            "#,
+            "Convert to local variable",
+        ));
+        Ok(())
+    }
+}
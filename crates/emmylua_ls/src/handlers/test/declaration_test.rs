@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, VirtualLocation, check};
+    use googletest::prelude::*;
+
+    #[gtest]
+    fn test_local() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_declaration(
+            r#"
+                local other = 1
+                local a = other
+                a = 2
+                print(<??>a)
+            "#,
+            vec![VirtualLocation {
+                file: "".to_string(),
+                line: 2,
+            }],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_param() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_declaration(
+            r#"
+                local function f(value)
+                    value = value or 1
+                    return val<??>ue
+                end
+            "#,
+            vec![VirtualLocation {
+                file: "".to_string(),
+                line: 1,
+            }],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_global() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        ws.def_file(
+            "config.lua",
+            r#"
+                Config = {}
+            "#,
+        );
+        check!(ws.check_declaration(
+            r#"
+                print(Con<??>fig)
+            "#,
+            vec![VirtualLocation {
+                file: "config.lua".to_string(),
+                line: 1,
+            }],
+        ));
+        Ok(())
+    }
+}
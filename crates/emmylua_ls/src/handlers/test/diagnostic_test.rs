@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::{
+        diagnostic::{PullDiagnosticReport, document_diagnostic, workspace_file_diagnostic},
+        test_lib::ProviderVirtualWorkspace,
+    };
+    use emmylua_code_analysis::FileId;
+    use googletest::prelude::*;
+    use tokio_util::sync::CancellationToken;
+
+    fn pull(
+        ws: &ProviderVirtualWorkspace,
+        file_id: FileId,
+        previous: Option<&str>,
+    ) -> PullDiagnosticReport {
+        document_diagnostic(&ws.analysis, file_id, previous, CancellationToken::new()).unwrap()
+    }
+
+    #[gtest]
+    fn test_unchanged_revision() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let file_id = ws.def_file("main.lua", "local _ = undefined_value");
+        let PullDiagnosticReport::Full { result_id, items } = pull(&ws, file_id, None) else {
+            return fail!("expected a full report");
+        };
+        verify_that!(items, not(is_empty()))?;
+        let result_id = result_id.or_fail()?;
+
+        let report = pull(&ws, file_id, Some(&result_id));
+        verify_eq!(report, PullDiagnosticReport::Unchanged { result_id })
+    }
+
+    #[gtest]
+    fn test_unchanged_diagnostics() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let file_id = ws.def_file("main.lua", "local _ = undefined_value");
+        let PullDiagnosticReport::Full { result_id, .. } = pull(&ws, file_id, None) else {
+            return fail!("expected a full report");
+        };
+        let result_id = result_id.or_fail()?;
+
+        ws.def_file("other.lua", "local a = 1\nprint(a)");
+        let PullDiagnosticReport::Unchanged {
+            result_id: new_result_id,
+        } = pull(&ws, file_id, Some(&result_id))
+        else {
+            return fail!("expected an unchanged report");
+        };
+        verify_eq!(new_result_id, result_id)
+    }
+
+    #[gtest]
+    fn test_changed_diagnostics() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let file_id = ws.def_file("main.lua", "local _ = undefined_value");
+        let PullDiagnosticReport::Full { result_id, .. } = pull(&ws, file_id, None) else {
+            return fail!("expected a full report");
+        };
+        let result_id = result_id.or_fail()?;
+
+        ws.def_file("other.lua", "undefined_value = 1");
+        let PullDiagnosticReport::Full { items, .. } = pull(&ws, file_id, Some(&result_id)) else {
+            return fail!("expected a full report");
+        };
+        verify_that!(items, is_empty())
+    }
+
+    #[gtest]
+    fn test_changed_config() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let file_id = ws.def_file("main.lua", "local _ = undefined_value");
+        let PullDiagnosticReport::Full { result_id, .. } = pull(&ws, file_id, None) else {
+            return fail!("expected a full report");
+        };
+        let result_id = result_id.or_fail()?;
+
+        let mut emmyrc = ws.analysis.emmyrc.as_ref().clone();
+        emmyrc
+            .diagnostics
+            .globals
+            .push("undefined_value".to_string());
+        ws.analysis.update_config(emmyrc.into());
+        let PullDiagnosticReport::Full { items, .. } = pull(&ws, file_id, Some(&result_id)) else {
+            return fail!("expected a full report");
+        };
+        verify_that!(items, is_empty())
+    }
+
+    #[gtest]
+    fn test_workspace_pull_keys_on_file() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let file_id = ws.def_file("main.lua", "local _ = undefined_value");
+        let PullDiagnosticReport::Full { result_id, .. } = pull(&ws, file_id, None) else {
+            return fail!("expected a full report");
+        };
+        let result_id = result_id.or_fail()?;
+
+        // `main.lua` does not require `other.lua`, its globals are picked up by the document
+        // pulls of the open files
+        ws.def_file("other.lua", "undefined_value = 1");
+        let report = workspace_file_diagnostic(
+            &ws.analysis,
+            file_id,
+            Some(&result_id),
+            CancellationToken::new(),
+        );
+        verify_eq!(
+            report,
+            Some(PullDiagnosticReport::Unchanged {
+                result_id: result_id.clone()
+            })
+        )?;
+
+        ws.def_file("main.lua", "local _ = undefined_value\nlocal _ = 1");
+        let report = workspace_file_diagnostic(
+            &ws.analysis,
+            file_id,
+            Some(&result_id),
+            CancellationToken::new(),
+        );
+        let Some(PullDiagnosticReport::Full { items, .. }) = report else {
+            return fail!("expected a full report");
+        };
+        verify_that!(items, is_empty())
+    }
+
+    #[gtest]
+    fn test_workspace_pull_keys_on_required_files() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        ws.def_file("lib.lua", "return { value = 1 }");
+        ws.def_file("mid.lua", "return require(\"lib\")");
+        let file_id = ws.def_file("main.lua", "local m = require(\"mid\")\nreturn m.other");
+        let PullDiagnosticReport::Full { result_id, .. } = pull(&ws, file_id, None) else {
+            return fail!("expected a full report");
+        };
+        let result_id = result_id.or_fail()?;
+
+        ws.def_file("lib.lua", "return { value = 1, other = 2 }");
+        let report = workspace_file_diagnostic(
+            &ws.analysis,
+            file_id,
+            Some(&result_id),
+            CancellationToken::new(),
+        );
+        let Some(PullDiagnosticReport::Full {
+            result_id: new_result_id,
+            ..
+        }) = report
+        else {
+            return fail!("expected a full report");
+        };
+        verify_ne!(new_result_id.or_fail()?, result_id)
+    }
+
+    #[gtest]
+    fn test_cancelled() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let file_id = ws.def_file("main.lua", "local _ = undefined_value");
+        let cancel_token = CancellationToken::new();
+        cancel_token.cancel();
+        verify_eq!(
+            document_diagnostic(&ws.analysis, file_id, None, cancel_token),
+            None
+        )
+    }
+}
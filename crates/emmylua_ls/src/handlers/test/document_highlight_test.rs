@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, check};
+    use googletest::prelude::*;
+
+    #[gtest]
+    fn test_function_end() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_document_highlight(
+            r#"
+                local function f()
+                    if true then
+                        return
+                    end
+                e<??>nd
+            "#,
+            vec![(1, 16), (1, 22), (5, 16)],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_if_end() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_document_highlight(
+            r#"
+                local function f()
+                    <??>if true then
+                        return
+                    end
+                end
+            "#,
+            vec![(2, 20), (2, 28), (4, 20)],
+        ));
+        Ok(())
+    }
+}
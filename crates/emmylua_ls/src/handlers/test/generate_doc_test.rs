@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::handlers::{
+        code_actions::generate_global_docs,
+        test_lib::{ProviderVirtualWorkspace, check},
+    };
+    use googletest::prelude::*;
+
+    #[gtest]
+    fn test_generate_doc() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local function <??>f<??>(a, b)
+                return a .. "!", true
+            end
+            f("x", 1)
+            f("y")
+            "#,
+            "Generate annotations",
+            r#"
+            ---@param a string
+            ---@param b integer?
+            ---@return string
+            ---@return boolean
+            local function f(a, b)
+                return a .. "!", true
+            end
+            f("x", 1)
+            f("y")
+            "#,
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_generate_doc_missing_only() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local t = {}
+
+            --- Adds two numbers
+            ---@param a number
+            function t.<??>add<??>(a, b)
+            end
+            t.add(1.5, 2)
+            "#,
+            "Generate annotations",
+            r#"
+            local t = {}
+
+            --- Adds two numbers
+            ---@param a number
+            ---@param b integer
+            function t.add(a, b)
+            end
+            t.add(1.5, 2)
+            "#,
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            ---@param a number
+            local function <??>f<??>(a)
+            end
+            "#,
+            "Generate annotations",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_generate_global_docs() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let file_id = ws.def(
+            r#"
+            function greet(name)
+                return "hello " .. name
+            end
+
+            local function helper(x)
+            end
+            greet("world")
+            "#,
+        );
+        let text_edits =
+            generate_global_docs(&ws.analysis.compilation, file_id, &mut HashMap::new());
+        let new_texts = text_edits
+            .iter()
+            .map(|text_edit| text_edit.new_text.as_str())
+            .collect::<Vec<_>>();
+        verify_eq!(
+            new_texts,
+            vec!["            ---@param name string\n            ---@return string\n"]
+        )
+    }
+
+    #[gtest]
+    fn test_generate_global_docs_shares_semantic_models() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let greet_id = ws.def_file(
+            "greet.lua",
+            r#"
+            function greet(name)
+            end
+            "#,
+        );
+        let wave_id = ws.def_file(
+            "wave.lua",
+            r#"
+            function wave(count)
+            end
+            "#,
+        );
+        let main_id = ws.def_file("main.lua", "greet('world')\nwave(1)");
+
+        let mut semantic_models = HashMap::new();
+        let compilation = &ws.analysis.compilation;
+        let new_texts = [greet_id, wave_id]
+            .into_iter()
+            .flat_map(|file_id| generate_global_docs(compilation, file_id, &mut semantic_models))
+            .map(|text_edit| text_edit.new_text)
+            .collect::<Vec<_>>();
+        verify_eq!(
+            new_texts,
+            vec![
+                "            ---@param name string\n".to_string(),
+                "            ---@param count integer\n".to_string(),
+            ]
+        )?;
+        // every file with a reference is loaded once for both functions
+        let mut file_ids = semantic_models.keys().copied().collect::<Vec<_>>();
+        file_ids.sort();
+        verify_eq!(file_ids, vec![greet_id, wave_id, main_id])
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, check};
+    use googletest::prelude::*;
+
+    #[gtest]
+    fn test_local() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_linked_editing_range(
+            r#"
+                local value <const> = 1
+                print(val<??>ue)
+                local other = value + 1
+            "#,
+            vec![(1, 22), (2, 22), (3, 30)],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_param() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_linked_editing_range(
+            r#"
+                local function f(<??>a)
+                    return a + 1
+                end
+                local a = 2
+            "#,
+            vec![(1, 33), (2, 27)],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_global() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_linked_editing_range(
+            r#"
+                value = 1
+                print(val<??>ue)
+            "#,
+            vec![],
+        ));
+        Ok(())
+    }
+
+    // a keyword and its `end` hold different text, so they cannot be edited together; the
+    // pairs are shown by document highlight instead
+    #[gtest]
+    fn test_function_end() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_linked_editing_range(
+            r#"
+                local function f()
+                    if true then
+                        return
+                    end
+                e<??>nd
+            "#,
+            vec![],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_if_end() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_linked_editing_range(
+            r#"
+                local function f()
+                    <??>if true then
+                        return
+                    end
+                end
+            "#,
+            vec![],
+        ));
+        Ok(())
+    }
+}
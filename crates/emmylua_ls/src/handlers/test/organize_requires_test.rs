@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, check};
+    use emmylua_code_analysis::file_path_to_uri;
+    use googletest::prelude::*;
+
+    #[gtest]
+    fn test_organize_requires() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"<??><??>local json = require("json")
+local unused = require("unused")
+---@type table
+local class = require("class")
+local dkjson = require("json")
+local _ = require("side_effect")
+
+print(json, class, dkjson)
+"#,
+            "Organize requires",
+            r#"---@type table
+local class = require("class")
+local json = require("json")
+local _ = require("side_effect")
+
+print(json, class, json)
+"#,
+        ));
+        check!(ws.check_no_refactor(
+            r#"<??><??>local a = require("a")
+local b = require("b")
+print(a, b)
+"#,
+            "Organize requires",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_organize_requires_group() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let mut emmyrc = ws.get_emmyrc();
+        emmyrc.code_action.group_requires = true;
+        ws.update_emmyrc(emmyrc);
+        ws.def_file("util.lua", "return {}");
+        let lib_root = ws
+            .virtual_url_generator
+            .base
+            .parent()
+            .or_fail()?
+            .join("lib_root");
+        ws.analysis.add_library_workspace(lib_root.clone());
+        let lib_uri = file_path_to_uri(&lib_root.join("json.lua")).or_fail()?;
+        ws.analysis
+            .update_file_by_uri(&lib_uri, Some("return {}".to_string()));
+        check!(ws.check_refactor(
+            r#"<??><??>local missing = require("missing")
+local util = require("util")
+local json = require("json")
+print(missing, util, json)
+"#,
+            "Organize requires",
+            r#"local json = require("json")
+
+local util = require("util")
+
+local missing = require("missing")
+print(missing, util, json)
+"#,
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_organize_requires_side_effects() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        // the modules required for their side effects are loaded at the same point
+        check!(ws.check_refactor(
+            r#"<??><??>local d = require("d")
+local c = require("c")
+local _ = require("setup")
+local b = require("b")
+local a = require("a")
+print(a, b, c, d)
+"#,
+            "Organize requires",
+            r#"local c = require("c")
+local d = require("d")
+local _ = require("setup")
+local a = require("a")
+local b = require("b")
+print(a, b, c, d)
+"#,
+        ));
+        check!(ws.check_no_refactor(
+            r#"<??><??>local b = require("b")
+local _ = require("setup")
+local a = require("a")
+print(a, b)
+"#,
+            "Organize requires",
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, check};
+    use googletest::prelude::*;
+    use lsp_types::{Position, Range};
+
+    #[gtest]
+    fn test_format_selected_statements() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_range_formatting(
+            "local a=1\nlocal b=2\nlocal c=3\n",
+            Range::new(Position::new(1, 0), Position::new(1, 9)),
+            "local a=1\nlocal b = 2\nlocal c=3\n",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_keep_surrounding_indent() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_range_formatting(
+            "function f()\n  if a then\nprint(a,b)\n  end\nend\n",
+            Range::new(Position::new(1, 0), Position::new(3, 5)),
+            "function f()\n  if a then\n      print(a, b)\n  end\nend\n",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_format_inside_block() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_range_formatting(
+            "if a then\n    x=1\n    y=2\nend\nz=3\n",
+            Range::new(Position::new(2, 0), Position::new(2, 7)),
+            "if a then\n    x=1\n    y = 2\nend\nz=3\n",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_keep_long_string_lines() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_range_formatting(
+            "do\n  local s = [[\n    text\n]]  ..  x\nend\n",
+            Range::new(Position::new(1, 0), Position::new(1, 5)),
+            "do\n  local s = [[\n    text\n]] .. x\nend\n",
+        ));
+        Ok(())
+    }
+}
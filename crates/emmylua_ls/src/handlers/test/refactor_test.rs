@@ -0,0 +1,348 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, check};
+    use googletest::prelude::*;
+    use lsp_types::CodeActionKind;
+
+    #[gtest]
+    fn test_extract_local() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local function f(a, b)
+                if a then
+                    print(<??>a + b * 2<??>)
+                end
+            end
+            "#,
+            "Extract to local variable",
+            r#"
+            local function f(a, b)
+                if a then
+                    local value = a + b * 2
+                    print(value)
+                end
+            end
+            "#,
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_extract_local_unique_name() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local value = 1
+            local x = <??>value * 2<??> + 1
+            "#,
+            "Extract to local variable",
+            r#"
+            local value = 1
+            local value1 = value * 2
+            local x = value1 + 1
+            "#,
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_extract_local_not_in_loop_condition() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_no_refactor(
+            r#"
+            local i = 1
+            while <??>i < 10<??> do
+                i = i + 1
+            end
+            "#,
+            "Extract to local variable",
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local t
+            local x = t and <??>t.value<??>
+            "#,
+            "Extract to local variable",
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local function f() end
+            print(<??>f()<??>)
+            "#,
+            "Extract to local variable",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_extract_function() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local function f(items)
+                local count = 0
+                <??>local total = 0
+                for _, item in ipairs(items) do
+                    total = total + item
+                    count = count + 1
+                end<??>
+                return total / count
+            end
+            "#,
+            "Extract to function",
+            r#"
+            local function f(items)
+                local count = 0
+                local function extracted(items, count)
+                    local total = 0
+                    for _, item in ipairs(items) do
+                        total = total + item
+                        count = count + 1
+                    end
+                    return total, count
+                end
+                local total
+                total, count = extracted(items, count)
+                return total / count
+            end
+            "#,
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_extract_function_no_return() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local name = "world"
+            <??>print("hello")
+            print(name)<??>
+            "#,
+            "Extract to function",
+            r#"
+            local name = "world"
+            local function extracted(name)
+                print("hello")
+                print(name)
+            end
+            extracted(name)
+            "#,
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_extract_function_control_flow() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_no_refactor(
+            r#"
+            local function f(a)
+                <??>if a then
+                    return 1
+                end<??>
+                return 2
+            end
+            "#,
+            "Extract to function",
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local x = <??>1<??>
+            "#,
+            "Extract to function",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_inline_variable() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local function f(a, b)
+                local <??>first<??> = a
+                return first * 2 + b, first
+            end
+            "#,
+            "Inline variable",
+            r#"
+            local function f(a, b)
+                return a * 2 + b, a
+            end
+            "#,
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_inline_variable_refused() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_no_refactor(
+            r#"
+            local <??>a<??> = 1
+            a = 2
+            local b = a
+            "#,
+            "Inline variable",
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local <??>a<??> = 1
+            local f = function()
+                return a
+            end
+            "#,
+            "Inline variable",
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local t = {}
+            local <??>a<??> = t.x()
+            local b = a
+            "#,
+            "Inline variable",
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local x = 1
+            local <??>a<??> = x
+            do
+                local x = 2
+                local b = a
+            end
+            "#,
+            "Inline variable",
+        ));
+        // the global or the table can change before the local is read
+        check!(ws.check_no_refactor(
+            r#"
+            local <??>x<??> = g
+            g = 1
+            return x
+            "#,
+            "Inline variable",
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local function push(items, v)
+                local <??>n<??> = #items
+                table.insert(items, v)
+                return n
+            end
+            "#,
+            "Inline variable",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_inline_function() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_refactor(
+            r#"
+            local function add(a, b)
+                return a + b
+            end
+            local x = <??>add<??>(1, 2 * 3) * 2
+            "#,
+            "Inline function",
+            r#"
+            local function add(a, b)
+                return a + b
+            end
+            local x = (1 + (2 * 3)) * 2
+            "#,
+        ));
+        check!(ws.check_refactor(
+            r#"
+            local function first(a, b)
+                return a or b
+            end
+            local x = <??>first<??>(1)
+            "#,
+            "Inline function",
+            r#"
+            local function first(a, b)
+                return a or b
+            end
+            local x = 1 or nil
+            "#,
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_inline_function_refused() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_no_refactor(
+            r#"
+            local function twice(a)
+                return a + a
+            end
+            local t = {}
+            local x = <??>twice<??>(t.next())
+            "#,
+            "Inline function",
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local function f(a)
+                local b = a
+                return b
+            end
+            local x = <??>f<??>(1)
+            "#,
+            "Inline function",
+        ));
+        check!(ws.check_no_refactor(
+            r#"
+            local y = 1
+            local function f(a)
+                return a + y
+            end
+            do
+                local y = 2
+                local x = <??>f<??>(1)
+            end
+            "#,
+            "Inline function",
+        ));
+        // `g()` would run before `h()`
+        check!(ws.check_no_refactor(
+            r#"
+            local function g() return 1 end
+            local function h() return 2 end
+            local function f(a)
+                return g() + a
+            end
+            local x = <??>f<??>(h())
+            "#,
+            "Inline function",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_only_requested_kinds() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        let block = r#"
+            local a = 1
+            local x = <??>a<??> + 1
+            return x
+            "#;
+        check!(ws.check_action_kinds(
+            block,
+            vec![CodeActionKind::REFACTOR_INLINE],
+            vec!["Inline variable"],
+        ));
+        check!(ws.check_action_kinds(
+            block,
+            vec![CodeActionKind::REFACTOR],
+            vec!["Extract to local variable", "Inline variable"],
+        ));
+        check!(ws.check_action_kinds(block, vec![CodeActionKind::QUICKFIX], vec![]));
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@
 mod tests {
     use crate::handlers::test_lib::{ProviderVirtualWorkspace, VirtualSemanticToken, check};
     use googletest::prelude::*;
-    use lsp_types::{SemanticTokenModifier, SemanticTokenType};
+    use lsp_types::{Position, Range, SemanticTokenModifier, SemanticTokenType};
     use std::collections::HashSet;
 
     #[gtest]
@@ -139,4 +139,40 @@ mod tests {
         ));
         Ok(())
     }
+
+    #[gtest]
+    fn test_range() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_semantic_token_range(
+            "local a = 1\nlocal b = 2\nlocal c = 3\n",
+            Range::new(Position::new(1, 0), Position::new(1, 11)),
+            vec![
+                VirtualSemanticToken {
+                    line: 1,
+                    start: 6,
+                    length: 1,
+                    token_type: SemanticTokenType::VARIABLE,
+                    token_modifier: HashSet::new(),
+                },
+                VirtualSemanticToken {
+                    line: 1,
+                    start: 10,
+                    length: 1,
+                    token_type: SemanticTokenType::NUMBER,
+                    token_modifier: HashSet::new(),
+                },
+            ],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_delta() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_semantic_token_delta(
+            "local a = 1\nlocal b = 2\nlocal c = 3\n",
+            "local a = 1\nlocal b = 'text' .. a\nlocal c = 3\n",
+        ));
+        Ok(())
+    }
 }
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, VirtualLocation, check};
+    use googletest::prelude::*;
+
+    #[gtest]
+    fn test_class_variable() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        ws.def_file(
+            "point.lua",
+            r#"
+                ---@class Point
+                ---@field x number
+            "#,
+        );
+        check!(ws.check_type_definition(
+            r#"
+                ---@type Point
+                local point
+
+                print(po<??>int)
+            "#,
+            vec![VirtualLocation {
+                file: "point.lua".to_string(),
+                line: 1,
+            }],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_union() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_type_definition(
+            r#"
+                ---@class Cat
+                ---@class Dog
+
+                ---@param pet Cat | Dog
+                local function f(pet)
+                    return p<??>et
+                end
+            "#,
+            vec![
+                VirtualLocation {
+                    file: "".to_string(),
+                    line: 1,
+                },
+                VirtualLocation {
+                    file: "".to_string(),
+                    line: 2,
+                },
+            ],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_field_alias() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_type_definition(
+            r#"
+                ---@alias Id integer
+
+                ---@class User
+                ---@field id Id
+                local user = {}
+
+                print(user.i<??>d)
+            "#,
+            vec![VirtualLocation {
+                file: "".to_string(),
+                line: 1,
+            }],
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, check};
+    use googletest::prelude::*;
+
+    #[gtest]
+    fn test_new_line_in_block() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_type_formatting("if a then\n<??>\nend\n", "\n", "if a then\n    \nend\n",));
+        check!(ws.check_type_formatting(
+            "local t = {\n    1,\n<??>\n}\n",
+            "\n",
+            "local t = {\n    1,\n    \n}\n",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_new_line_before_end() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_type_formatting(
+            "    while a do\n        b()\n<??>end\n",
+            "\n",
+            "    while a do\n        b()\n    end\n",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_closing_keyword() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_type_formatting(
+            "if a then\n    b()\n    end<??>\n",
+            "d",
+            "if a then\n    b()\nend\n",
+        ));
+        check!(ws.check_type_formatting(
+            "if a then\n    b()\n    else<??>\n    c()\nend\n",
+            "e",
+            "if a then\n    b()\nelse\n    c()\nend\n",
+        ));
+        check!(ws.check_type_formatting(
+            "local t = {\n    1,\n    }<??>\n",
+            "}",
+            "local t = {\n    1,\n}\n",
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_not_a_keyword() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_type_formatting(
+            "if a then\n    friend<??>\nend\n",
+            "d",
+            "if a then\n    friend\nend\n",
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_lib::{ProviderVirtualWorkspace, check};
+    use googletest::prelude::*;
+
+    #[gtest]
+    fn test_class_hierarchy() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        ws.def(
+            r#"
+                ---@class Animal
+                ---@class Named
+                ---@class Cat : Dog
+            "#,
+        );
+        check!(ws.check_type_hierarchy(
+            r#"
+                ---@class D<??>og : Animal, Named
+            "#,
+            "Dog",
+            vec!["Animal", "Named"],
+            vec!["Cat"],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_variable_type() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        ws.def(
+            r#"
+                ---@class Shape
+                ---@class Circle : Shape
+                ---@class Square : Shape
+            "#,
+        );
+        check!(ws.check_type_hierarchy(
+            r#"
+                ---@type Shape
+                local sha<??>pe
+            "#,
+            "Shape",
+            vec![],
+            vec!["Circle", "Square"],
+        ));
+        Ok(())
+    }
+
+    #[gtest]
+    fn test_generic_super() -> Result<()> {
+        let mut ws = ProviderVirtualWorkspace::new();
+        check!(ws.check_type_hierarchy(
+            r#"
+                ---@class List<T>
+                ---@class Names : List<string>
+                ---@type Na<??>mes
+                local names
+            "#,
+            "Names",
+            vec!["List"],
+            vec![],
+        ));
+        Ok(())
+    }
+}
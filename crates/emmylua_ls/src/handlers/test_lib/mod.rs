@@ -1,12 +1,14 @@
 use emmylua_code_analysis::{EmmyLuaAnalysis, Emmyrc, FileId, VirtualUrlGenerator};
+use emmylua_code_style::{LuaCodeStyle, LuaIndent};
 use googletest::prelude::*;
 use itertools::Itertools;
 use lsp_types::{
-    ClientCapabilities, CodeActionOrCommand, CompletionItem, CompletionItemKind,
+    ClientCapabilities, CodeActionKind, CodeActionOrCommand, CompletionItem, CompletionItemKind,
     CompletionResponse, CompletionTriggerKind, GotoDefinitionResponse, Hover, HoverContents,
-    InlayHintLabel, Location, MarkupContent, Position, SemanticTokenModifier, SemanticTokenType,
+    InlayHintLabel, Location, MarkupContent, Position, Range, SemanticToken, SemanticTokenModifier,
+    SemanticTokenType, SemanticTokensFullDeltaResult, SemanticTokensRangeResult,
     SemanticTokensResult, SignatureHelpContext, SignatureHelpTriggerKind, SignatureInformation,
-    TextEdit,
+    TextEdit, Uri,
 };
 use std::collections::HashSet;
 use std::{ops::Deref, sync::Arc};
@@ -17,10 +19,19 @@ use crate::{
     handlers::{
         code_actions::code_action,
         completion::{completion, completion_resolve},
+        declaration::declaration,
+        document_highlight::document_highlight,
+        document_range_formatting::range_format,
+        document_type_formatting::type_formatting,
         inlay_hint::inlay_hint,
+        linked_editing_range::linked_editing_range,
         rename::rename,
-        semantic_token::semantic_token,
+        semantic_token::{SemanticTokensCache, semantic_token, semantic_token_range},
         signature_helper::signature_help,
+        type_definition::type_definition,
+        type_hierarchy::{
+            prepare_type_hierarchy, type_hierarchy_subtypes, type_hierarchy_supertypes,
+        },
     },
 };
 
@@ -311,6 +322,73 @@ impl ProviderVirtualWorkspace {
         Self::assert_definition(result, expected)
     }
 
+    pub fn check_type_definition(
+        &mut self,
+        block_str: &str,
+        expected: Vec<VirtualLocation>,
+    ) -> Result<()> {
+        let (content, position) = Self::handle_file_content(block_str)?;
+        let file_id = self.def(&content);
+        let result = type_definition(&self.analysis, file_id, position)
+            .ok_or("failed to get go to type definition response")
+            .or_fail()?;
+
+        Self::assert_definition(result, expected)
+    }
+
+    pub fn check_declaration(
+        &mut self,
+        block_str: &str,
+        expected: Vec<VirtualLocation>,
+    ) -> Result<()> {
+        let (content, position) = Self::handle_file_content(block_str)?;
+        let file_id = self.def(&content);
+        let result = declaration(&self.analysis, file_id, position)
+            .ok_or("failed to get go to declaration response")
+            .or_fail()?;
+
+        Self::assert_definition(result, expected)
+    }
+
+    /// Checks the linked ranges as `(line, character)` of their starts
+    pub fn check_linked_editing_range(
+        &mut self,
+        block_str: &str,
+        expected: Vec<(u32, u32)>,
+    ) -> Result<()> {
+        let (content, position) = Self::handle_file_content(block_str)?;
+        let file_id = self.def(&content);
+        let result = linked_editing_range(&self.analysis, file_id, position);
+        let starts = result
+            .map(|result| {
+                result
+                    .ranges
+                    .iter()
+                    .map(|range| (range.start.line, range.start.character))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        verify_eq!(starts, expected)
+    }
+
+    /// Highlights at `<??>` and compares the sorted `(line, character)` starts of the ranges
+    pub fn check_document_highlight(
+        &mut self,
+        block_str: &str,
+        expected: Vec<(u32, u32)>,
+    ) -> Result<()> {
+        let (content, position) = Self::handle_file_content(block_str)?;
+        let file_id = self.def(&content);
+        let mut starts = document_highlight(&self.analysis, file_id, position)
+            .unwrap_or_default()
+            .iter()
+            .map(|highlight| (highlight.range.start.line, highlight.range.start.character))
+            .collect::<Vec<_>>();
+        starts.sort();
+        verify_eq!(starts, expected)
+    }
+
     fn assert_definition(
         result: GotoDefinitionResponse,
         expected: Vec<VirtualLocation>,
@@ -456,6 +534,103 @@ impl ProviderVirtualWorkspace {
         )
     }
 
+    /// Runs the code action titled `title` on the selection between the two `<??>` and
+    /// checks the text after its edits
+    pub fn check_refactor(&mut self, block_str: &str, title: &str, expected: &str) -> Result<()> {
+        let (content, range) = Self::handle_file_selection(block_str)?;
+        let file_id = self.def(&content);
+        let actions =
+            code_action(&self.analysis, file_id, range, Vec::new(), None).unwrap_or_default();
+        let action = actions
+            .into_iter()
+            .find_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) if action.title == title => Some(action),
+                _ => None,
+            })
+            .ok_or_else(|| format!("no code action {title}"))
+            .or_fail()?;
+        let edits = action
+            .edit
+            .and_then(|edit| edit.changes)
+            .and_then(|changes| changes.into_values().next())
+            .unwrap_or_default();
+
+        verify_eq!(self.apply_edits(file_id, edits)?, expected)
+    }
+
+    /// Checks that there is no code action titled `title` for the selection
+    pub fn check_no_refactor(&mut self, block_str: &str, title: &str) -> Result<()> {
+        let titles = self.get_action_titles(block_str, None)?;
+        verify_that!(titles, not(contains(eq(title))))
+    }
+
+    /// Checks the titles of the code actions of the `only` kinds for the selection
+    pub fn check_action_kinds(
+        &mut self,
+        block_str: &str,
+        only: Vec<CodeActionKind>,
+        expected: Vec<&str>,
+    ) -> Result<()> {
+        let titles = self.get_action_titles(block_str, Some(&only))?;
+        verify_eq!(titles, expected)
+    }
+
+    fn get_action_titles(
+        &mut self,
+        block_str: &str,
+        only: Option<&[CodeActionKind]>,
+    ) -> Result<Vec<String>> {
+        let (content, range) = Self::handle_file_selection(block_str)?;
+        let file_id = self.def(&content);
+        let actions =
+            code_action(&self.analysis, file_id, range, Vec::new(), only).unwrap_or_default();
+        Ok(actions
+            .into_iter()
+            .filter_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => Some(action.title),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn handle_file_selection(content: &str) -> Result<(String, Range)> {
+        let first = content
+            .find("<??>")
+            .ok_or("module content should include <??>")
+            .or_fail()?;
+        let second = content[first + 4..]
+            .find("<??>")
+            .map(|i| first + 4 + i)
+            .ok_or("module content should include a second <??>")
+            .or_fail()?;
+        let (_, start) = Self::handle_file_content(&format!(
+            "{}{}",
+            &content[..second],
+            &content[second + 4..]
+        ))?;
+        let (new_content, end) = Self::handle_file_content(&content.replacen("<??>", "", 1))?;
+        Ok((new_content, Range::new(start, end)))
+    }
+
+    fn apply_edits(&self, file_id: FileId, mut edits: Vec<TextEdit>) -> Result<String> {
+        let semantic_model = self
+            .analysis
+            .compilation
+            .get_semantic_model(file_id)
+            .or_fail()?;
+        let document = semantic_model.get_document();
+        let mut text = document.get_text().to_string();
+        edits.sort_by_key(|edit| (edit.range.start.line, edit.range.start.character));
+        for edit in edits.into_iter().rev() {
+            let range = document.to_rowan_range(edit.range).or_fail()?;
+            text.replace_range(
+                usize::from(range.start())..usize::from(range.end()),
+                &edit.new_text,
+            );
+        }
+        Ok(text)
+    }
+
     pub fn check_code_action(
         &mut self,
         block_str: &str,
@@ -467,7 +642,7 @@ impl ProviderVirtualWorkspace {
             .diagnose_file(file_id, CancellationToken::new())
             .ok_or("failed to diagnose file")
             .or_fail()?;
-        let result = code_action(&self.analysis, file_id, result)
+        let result = code_action(&self.analysis, file_id, Range::default(), result, None)
             .ok_or("failed to generate code action")
             .or_fail()?;
 
@@ -505,6 +680,98 @@ impl ProviderVirtualWorkspace {
             return fail!("expected SemanticTokensResult::Tokens, got {result:?}");
         };
 
+        verify_eq!(Self::decode_semantic_tokens(result.data)?, expected)
+    }
+
+    pub fn check_semantic_token_range(
+        &mut self,
+        block_str: &str,
+        range: Range,
+        expected: Vec<VirtualSemanticToken>,
+    ) -> Result<()> {
+        let file_id = self.def(block_str);
+        let result = semantic_token_range(
+            &self.analysis,
+            file_id,
+            range,
+            &ClientCapabilities::default(),
+            ClientId::VSCode,
+        )
+        .ok_or("failed to get semantic tokens")
+        .or_fail()?;
+        let SemanticTokensRangeResult::Tokens(result) = result else {
+            return fail!("expected SemanticTokensRangeResult::Tokens, got {result:?}");
+        };
+
+        verify_eq!(Self::decode_semantic_tokens(result.data)?, expected)
+    }
+
+    fn semantic_token_delta(
+        &self,
+        file_id: FileId,
+        uri: Uri,
+        previous_result_id: &str,
+        cache: &mut SemanticTokensCache,
+    ) -> Result<SemanticTokensFullDeltaResult> {
+        let result = semantic_token(
+            &self.analysis,
+            file_id,
+            &ClientCapabilities::default(),
+            ClientId::VSCode,
+        )
+        .ok_or("failed to get semantic tokens")
+        .or_fail()?;
+        let tokens = match result {
+            SemanticTokensResult::Tokens(tokens) => Some(tokens),
+            SemanticTokensResult::Partial(_) => None,
+        }
+        .ok_or("expected SemanticTokensResult::Tokens")
+        .or_fail()?;
+        Ok(cache.delta(uri, previous_result_id, tokens.data))
+    }
+
+    /// Checks that applying the delta to the tokens of `old_block` gives the tokens of
+    /// `new_block`
+    pub fn check_semantic_token_delta(&mut self, old_block: &str, new_block: &str) -> Result<()> {
+        let file_name = format!("virtual_{}.lua", self.id_counter);
+        self.id_counter += 1;
+        let uri = self.virtual_url_generator.new_uri(&file_name);
+        let mut cache = SemanticTokensCache::default();
+
+        let file_id = self.def_file(&file_name, old_block);
+        let result = self.semantic_token_delta(file_id, uri.clone(), "", &mut cache)?;
+        let SemanticTokensFullDeltaResult::Tokens(old_tokens) = result else {
+            return fail!("expected SemanticTokensFullDeltaResult::Tokens, got {result:?}");
+        };
+        let previous_result_id = old_tokens.result_id.or_fail()?;
+
+        let file_id = self.def_file(&file_name, new_block);
+        let result = self.semantic_token_delta(file_id, uri, &previous_result_id, &mut cache)?;
+        let SemanticTokensFullDeltaResult::TokensDelta(delta) = result else {
+            return fail!("expected SemanticTokensFullDeltaResult::TokensDelta, got {result:?}");
+        };
+        verify_that!(delta.edits.len(), le(1))?;
+
+        let mut tokens = old_tokens.data;
+        for edit in delta.edits {
+            let start = edit.start as usize / 5;
+            let end = start + edit.delete_count as usize / 5;
+            tokens.splice(start..end, edit.data.unwrap_or_default());
+        }
+        let SemanticTokensResult::Tokens(expected) = semantic_token(
+            &self.analysis,
+            file_id,
+            &ClientCapabilities::default(),
+            ClientId::VSCode,
+        )
+        .or_fail()?
+        else {
+            return fail!("expected SemanticTokensResult::Tokens");
+        };
+        verify_eq!(tokens, expected.data)
+    }
+
+    fn decode_semantic_tokens(data: Vec<SemanticToken>) -> Result<Vec<VirtualSemanticToken>> {
         fn type_index_to_type(index: u32) -> Result<SemanticTokenType> {
             SEMANTIC_TOKEN_TYPES
                 .get(index as usize)
@@ -534,7 +801,7 @@ impl ProviderVirtualWorkspace {
         let mut virtual_result = Vec::new();
         let mut line = 0;
         let mut start = 0;
-        for token in result.data {
+        for token in data {
             if token.delta_line > 0 {
                 line += token.delta_line;
                 start = 0;
@@ -549,7 +816,7 @@ impl ProviderVirtualWorkspace {
             });
         }
 
-        verify_eq!(virtual_result, expected)
+        Ok(virtual_result)
     }
 
     pub fn check_rename(
@@ -592,4 +859,68 @@ impl ProviderVirtualWorkspace {
             .or_fail()?;
         Self::assert_locations(result, expected)
     }
+
+    /// Formats `range` of the file and compares the text after applying the edits
+    pub fn check_range_formatting(
+        &mut self,
+        block_str: &str,
+        range: Range,
+        expected: &str,
+    ) -> Result<()> {
+        let file_id = self.def(block_str);
+        let edits = range_format(&self.analysis, file_id, range, &LuaCodeStyle::default())
+            .ok_or("failed to format range")
+            .or_fail()?;
+        let result = self.apply_edits(file_id, edits)?;
+        verify_eq!(result, expected)
+    }
+
+    /// Types `ch` at `<??>`, which is right after the typed text, and compares the text after
+    /// applying the edits
+    pub fn check_type_formatting(
+        &mut self,
+        block_str: &str,
+        ch: &str,
+        expected: &str,
+    ) -> Result<()> {
+        let (content, position) = Self::handle_file_content(block_str)?;
+        let file_id = self.def(&content);
+        // `None` means there is nothing to re-indent
+        let edits = type_formatting(&self.analysis, file_id, position, ch, &LuaIndent::Space(4))
+            .unwrap_or_default();
+        let result = self.apply_edits(file_id, edits)?;
+        verify_eq!(result, expected)
+    }
+
+    /// Prepares the type hierarchy at `<??>` and compares the names of the item, its
+    /// supertypes and its subtypes
+    pub fn check_type_hierarchy(
+        &mut self,
+        block_str: &str,
+        expected_name: &str,
+        expected_supertypes: Vec<&str>,
+        expected_subtypes: Vec<&str>,
+    ) -> Result<()> {
+        let (content, position) = Self::handle_file_content(block_str)?;
+        let file_id = self.def(&content);
+        let items = prepare_type_hierarchy(&self.analysis, file_id, position)
+            .ok_or("failed to prepare type hierarchy")
+            .or_fail()?;
+        let item = items.first().or_fail()?;
+        verify_eq!(item.name.as_str(), expected_name)?;
+
+        let supertypes = type_hierarchy_supertypes(&self.analysis, item).or_fail()?;
+        let supertypes = supertypes
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<Vec<_>>();
+        verify_eq!(supertypes, expected_supertypes)?;
+
+        let subtypes = type_hierarchy_subtypes(&self.analysis, item).or_fail()?;
+        let subtypes = subtypes
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<Vec<_>>();
+        verify_eq!(subtypes, expected_subtypes)
+    }
 }
@@ -107,6 +107,7 @@ pub async fn on_did_close_document(
         .current_open_files
         .remove(&params.text_document.uri);
     drop(workspace);
+    context.semantic_tokens_cache.lock().await.remove(uri);
 
     // 如果关闭后文件不存在, 则移除
     if let Some(file_path) = uri_to_file_path(uri) {
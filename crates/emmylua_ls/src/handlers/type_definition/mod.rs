@@ -0,0 +1,111 @@
+use emmylua_code_analysis::{
+    EmmyLuaAnalysis, FileId, LuaSemanticDeclId, LuaType, LuaTypeDeclId, SemanticDeclLevel,
+    SemanticModel,
+};
+use emmylua_parser::{LuaAstNode, LuaExpr, LuaSyntaxToken};
+use itertools::Itertools;
+use lsp_types::{
+    ClientCapabilities, GotoDefinitionResponse, Location, Position, ServerCapabilities,
+    TypeDefinitionProviderCapability, request::GotoTypeDefinitionParams,
+};
+use tokio_util::sync::CancellationToken;
+
+use super::RegisterCapabilities;
+use crate::{context::ServerContextSnapshot, util::find_token_at_position};
+
+pub async fn on_type_definition_handler(
+    context: ServerContextSnapshot,
+    params: GotoTypeDefinitionParams,
+    _: CancellationToken,
+) -> Option<GotoDefinitionResponse> {
+    let uri = params.text_document_position_params.text_document.uri;
+    let analysis = context.analysis.read().await;
+    let file_id = analysis.get_file_id(&uri)?;
+    let position = params.text_document_position_params.position;
+
+    type_definition(&analysis, file_id, position)
+}
+
+pub fn type_definition(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    position: Position,
+) -> Option<GotoDefinitionResponse> {
+    let semantic_model = analysis.compilation.get_semantic_model(file_id)?;
+    let token = find_token_at_position(&semantic_model, position)?;
+
+    let typ = get_token_type(&semantic_model, &token)?;
+    let mut type_decl_ids = Vec::new();
+    collect_type_decl_ids(&typ, &mut type_decl_ids);
+
+    let type_index = semantic_model.get_db().get_type_index();
+    let locations = type_decl_ids
+        .iter()
+        .filter_map(|type_decl_id| type_index.get_type_decl(type_decl_id))
+        .flat_map(|type_decl| type_decl.get_locations())
+        .filter_map(|location| {
+            let document = semantic_model.get_document_by_file_id(location.file_id)?;
+            document.to_lsp_location(location.range)
+        })
+        .unique()
+        .collect::<Vec<Location>>();
+
+    match locations.len() {
+        0 => None,
+        1 => Some(GotoDefinitionResponse::Scalar(locations[0].clone())),
+        _ => Some(GotoDefinitionResponse::Array(locations)),
+    }
+}
+
+/// The type of the expression at the token, or of the declaration it names
+fn get_token_type(semantic_model: &SemanticModel, token: &LuaSyntaxToken) -> Option<LuaType> {
+    if let Some(typ) = token
+        .parent()
+        .and_then(LuaExpr::cast)
+        .and_then(|expr| semantic_model.infer_expr(expr).ok())
+    {
+        return Some(typ);
+    }
+
+    match semantic_model.find_decl(token.clone().into(), SemanticDeclLevel::default())? {
+        LuaSemanticDeclId::LuaDecl(decl_id) => Some(semantic_model.get_type(decl_id.into())),
+        LuaSemanticDeclId::Member(member_id) => Some(semantic_model.get_type(member_id.into())),
+        LuaSemanticDeclId::TypeDecl(type_decl_id) => Some(LuaType::Ref(type_decl_id)),
+        _ => None,
+    }
+}
+
+fn collect_type_decl_ids(typ: &LuaType, type_decl_ids: &mut Vec<LuaTypeDeclId>) {
+    match typ {
+        LuaType::Ref(type_decl_id) | LuaType::Def(type_decl_id)
+            if !type_decl_ids.contains(type_decl_id) =>
+        {
+            type_decl_ids.push(type_decl_id.clone());
+        }
+        LuaType::Generic(generic) => {
+            collect_type_decl_ids(&generic.get_base_type(), type_decl_ids);
+        }
+        LuaType::Union(union) => {
+            for typ in union.into_vec() {
+                collect_type_decl_ids(&typ, type_decl_ids);
+            }
+        }
+        LuaType::Intersection(intersection) => {
+            for typ in intersection.get_types() {
+                collect_type_decl_ids(typ, type_decl_ids);
+            }
+        }
+        LuaType::Array(array) => collect_type_decl_ids(array.get_base(), type_decl_ids),
+        LuaType::Instance(instance) => collect_type_decl_ids(instance.get_base(), type_decl_ids),
+        _ => {}
+    }
+}
+
+pub struct TypeDefinitionCapabilities;
+
+impl RegisterCapabilities for TypeDefinitionCapabilities {
+    fn register_capabilities(server_capabilities: &mut ServerCapabilities, _: &ClientCapabilities) {
+        server_capabilities.type_definition_provider =
+            Some(TypeDefinitionProviderCapability::Simple(true));
+    }
+}
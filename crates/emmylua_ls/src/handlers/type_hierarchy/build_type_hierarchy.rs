@@ -0,0 +1,73 @@
+use emmylua_code_analysis::{DbIndex, LuaType, LuaTypeDeclId, SemanticModel};
+use lsp_types::{SymbolKind, TypeHierarchyItem};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TypeHierarchyItemData {
+    pub type_decl_id: LuaTypeDeclId,
+}
+
+pub fn build_type_hierarchy_item(
+    semantic_model: &SemanticModel,
+    type_decl_id: LuaTypeDeclId,
+) -> Option<TypeHierarchyItem> {
+    let db = semantic_model.get_db();
+    let type_decl = db.get_type_index().get_type_decl(&type_decl_id)?;
+    let location = type_decl.get_locations().first()?;
+    let document = semantic_model.get_document_by_file_id(location.file_id)?;
+    let lsp_range = document.to_lsp_range(location.range)?;
+    let kind = if type_decl.is_enum() {
+        SymbolKind::ENUM
+    } else if type_decl.is_alias() {
+        SymbolKind::TYPE_PARAMETER
+    } else {
+        SymbolKind::CLASS
+    };
+
+    Some(TypeHierarchyItem {
+        name: type_decl.get_name().to_string(),
+        kind,
+        tags: None,
+        detail: type_decl
+            .get_namespace()
+            .map(|namespace| namespace.to_string()),
+        uri: document.get_uri(),
+        range: lsp_range,
+        selection_range: lsp_range,
+        data: Some(serde_json::to_value(TypeHierarchyItemData { type_decl_id }).ok()?),
+    })
+}
+
+/// The declared type behind a class reference, a class definition or a generic instance
+pub fn get_type_decl_id(typ: &LuaType) -> Option<LuaTypeDeclId> {
+    match typ {
+        LuaType::Ref(type_decl_id) | LuaType::Def(type_decl_id) => Some(type_decl_id.clone()),
+        LuaType::Generic(generic) => Some(generic.get_base_type_id()),
+        _ => None,
+    }
+}
+
+pub fn get_super_type_ids(db: &DbIndex, type_decl_id: &LuaTypeDeclId) -> Vec<LuaTypeDeclId> {
+    let mut super_type_ids = Vec::new();
+    if let Some(super_types) = db.get_type_index().get_super_types_iter(type_decl_id) {
+        for super_type_id in super_types.filter_map(get_type_decl_id) {
+            if !super_type_ids.contains(&super_type_id) {
+                super_type_ids.push(super_type_id);
+            }
+        }
+    }
+
+    super_type_ids
+}
+
+pub fn get_sub_type_ids(db: &DbIndex, type_decl_id: &LuaTypeDeclId) -> Vec<LuaTypeDeclId> {
+    let mut sub_type_ids = db
+        .get_type_index()
+        .get_all_types()
+        .into_iter()
+        .map(|type_decl| type_decl.get_id())
+        .filter(|sub_type_id| get_super_type_ids(db, sub_type_id).contains(type_decl_id))
+        .collect::<Vec<_>>();
+    sub_type_ids.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+    sub_type_ids
+}
@@ -0,0 +1,142 @@
+mod build_type_hierarchy;
+
+use build_type_hierarchy::{
+    TypeHierarchyItemData, build_type_hierarchy_item, get_sub_type_ids, get_super_type_ids,
+    get_type_decl_id,
+};
+use emmylua_code_analysis::{
+    EmmyLuaAnalysis, FileId, LuaSemanticDeclId, LuaTypeDeclId, SemanticDeclLevel,
+};
+use lsp_types::{
+    ClientCapabilities, Position, Registration, RegistrationParams, TypeHierarchyItem,
+    TypeHierarchyPrepareParams, TypeHierarchyRegistrationOptions, TypeHierarchySubtypesParams,
+    TypeHierarchySupertypesParams,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    context::{ClientProxy, ServerContextSnapshot},
+    util::find_token_at_position,
+};
+
+pub async fn on_prepare_type_hierarchy_handler(
+    context: ServerContextSnapshot,
+    params: TypeHierarchyPrepareParams,
+    _: CancellationToken,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let uri = params.text_document_position_params.text_document.uri;
+    let analysis = context.analysis.read().await;
+    let file_id = analysis.get_file_id(&uri)?;
+    let position = params.text_document_position_params.position;
+
+    prepare_type_hierarchy(&analysis, file_id, position)
+}
+
+pub async fn on_supertypes_handler(
+    context: ServerContextSnapshot,
+    params: TypeHierarchySupertypesParams,
+    _: CancellationToken,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let analysis = context.analysis.read().await;
+    type_hierarchy_supertypes(&analysis, &params.item)
+}
+
+pub async fn on_subtypes_handler(
+    context: ServerContextSnapshot,
+    params: TypeHierarchySubtypesParams,
+    _: CancellationToken,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let analysis = context.analysis.read().await;
+    type_hierarchy_subtypes(&analysis, &params.item)
+}
+
+pub fn prepare_type_hierarchy(
+    analysis: &EmmyLuaAnalysis,
+    file_id: FileId,
+    position: Position,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let semantic_model = analysis.compilation.get_semantic_model(file_id)?;
+    let token = find_token_at_position(&semantic_model, position)?;
+
+    // a type name in the docs, or a variable typed with a class
+    let type_decl_id =
+        match semantic_model.find_decl(token.clone().into(), SemanticDeclLevel::default())? {
+            LuaSemanticDeclId::TypeDecl(type_decl_id) => type_decl_id,
+            LuaSemanticDeclId::LuaDecl(decl_id) => {
+                get_type_decl_id(&semantic_model.get_type(decl_id.into()))?
+            }
+            LuaSemanticDeclId::Member(member_id) => {
+                get_type_decl_id(&semantic_model.get_type(member_id.into()))?
+            }
+            _ => return None,
+        };
+
+    Some(vec![build_type_hierarchy_item(
+        &semantic_model,
+        type_decl_id,
+    )?])
+}
+
+pub fn type_hierarchy_supertypes(
+    analysis: &EmmyLuaAnalysis,
+    item: &TypeHierarchyItem,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let (semantic_model_file_id, type_decl_id) = get_item_type_decl(analysis, item)?;
+    let semantic_model = analysis
+        .compilation
+        .get_semantic_model(semantic_model_file_id)?;
+    let items = get_super_type_ids(semantic_model.get_db(), &type_decl_id)
+        .into_iter()
+        .filter_map(|super_type_id| build_type_hierarchy_item(&semantic_model, super_type_id))
+        .collect();
+    Some(items)
+}
+
+pub fn type_hierarchy_subtypes(
+    analysis: &EmmyLuaAnalysis,
+    item: &TypeHierarchyItem,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let (semantic_model_file_id, type_decl_id) = get_item_type_decl(analysis, item)?;
+    let semantic_model = analysis
+        .compilation
+        .get_semantic_model(semantic_model_file_id)?;
+    let items = get_sub_type_ids(semantic_model.get_db(), &type_decl_id)
+        .into_iter()
+        .filter_map(|sub_type_id| build_type_hierarchy_item(&semantic_model, sub_type_id))
+        .collect();
+    Some(items)
+}
+
+fn get_item_type_decl(
+    analysis: &EmmyLuaAnalysis,
+    item: &TypeHierarchyItem,
+) -> Option<(FileId, LuaTypeDeclId)> {
+    let data = serde_json::from_value::<TypeHierarchyItemData>(item.data.clone()?).ok()?;
+    let file_id = analysis.get_file_id(&item.uri)?;
+    Some((file_id, data.type_decl_id))
+}
+
+/// `ServerCapabilities` of lsp-types has no field for type hierarchy, so it is registered
+/// dynamically for the clients that support it
+pub fn register_type_hierarchy(client: &ClientProxy, client_capabilities: &ClientCapabilities) {
+    let dynamic_registration = client_capabilities
+        .text_document
+        .as_ref()
+        .and_then(|text_document| text_document.type_hierarchy.as_ref())
+        .and_then(|type_hierarchy| type_hierarchy.dynamic_registration)
+        .unwrap_or(false);
+    if !dynamic_registration {
+        return;
+    }
+
+    let registration = Registration {
+        id: "emmylua_type_hierarchy".to_string(),
+        method: "textDocument/prepareTypeHierarchy".to_string(),
+        register_options: Some(
+            serde_json::to_value(TypeHierarchyRegistrationOptions::default()).unwrap(),
+        ),
+    };
+    client.dynamic_register_capability(RegistrationParams {
+        registrations: vec![registration],
+    });
+}
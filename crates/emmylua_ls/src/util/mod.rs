@@ -1,7 +1,9 @@
 mod desc;
 mod module_name_convert;
 mod time_cancel_token;
+mod token_at_position;
 
 pub use desc::*;
 pub use module_name_convert::{key_name_convert, module_name_convert};
 pub use time_cancel_token::time_cancel_token;
+pub use token_at_position::find_token_at_position;
@@ -0,0 +1,29 @@
+use emmylua_code_analysis::SemanticModel;
+use emmylua_parser::{LuaAstNode, LuaSyntaxToken, LuaTokenKind};
+use lsp_types::Position;
+use rowan::TokenAtOffset;
+
+/// The token at the position, a name is preferred when the position is between two tokens
+pub fn find_token_at_position(
+    semantic_model: &SemanticModel,
+    position: Position,
+) -> Option<LuaSyntaxToken> {
+    let root = semantic_model.get_root();
+    let document = semantic_model.get_document();
+    let offset = document.get_offset(position.line as usize, position.character as usize)?;
+    if offset > root.syntax().text_range().end() {
+        return None;
+    }
+
+    match root.syntax().token_at_offset(offset) {
+        TokenAtOffset::Single(token) => Some(token),
+        TokenAtOffset::Between(left, right) => {
+            if left.kind() == LuaTokenKind::TkName.into() {
+                Some(left)
+            } else {
+                Some(right)
+            }
+        }
+        TokenAtOffset::None => None,
+    }
+}